@@ -0,0 +1,195 @@
+//! Artifacts attached to a test with [`attach`]: logs, screenshots, dumps.
+//!
+//! Bytes are saved under the artifact directory (`--artifact-dir`, default
+//! `rsspec-artifacts` in the system temp dir) in a folder named after the
+//! test; files the test already wrote are referenced where they are. The
+//! runner keeps a test's artifacts only if it failed (or `--keep-artifacts`
+//! is set), lists them in the failure summary and references them from the
+//! JUnit report.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::diagnostics::diag;
+
+/// An artifact recorded on a test, see [`attach`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    /// The name passed to [`attach`].
+    pub name: String,
+    /// Where the artifact is on disk.
+    pub path: PathBuf,
+    /// Whether rsspec wrote the file, and so may delete it again.
+    saved: bool,
+}
+
+impl Attachment {
+    /// An attachment reported by a `--jobs` worker, which already decided
+    /// whether to keep it.
+    pub(crate) fn reported(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Attachment {
+            name: name.into(),
+            path: path.into(),
+            saved: false,
+        }
+    }
+}
+
+/// The content of an artifact: bytes to save, or a file that already exists.
+///
+/// Strings and byte buffers convert to [`AttachmentSource::Bytes`], paths to
+/// [`AttachmentSource::File`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttachmentSource {
+    /// Saved to a file in the artifact directory.
+    Bytes(Vec<u8>),
+    /// Referenced in place.
+    File(PathBuf),
+}
+
+impl From<Vec<u8>> for AttachmentSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        AttachmentSource::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for AttachmentSource {
+    fn from(bytes: &[u8]) -> Self {
+        AttachmentSource::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for AttachmentSource {
+    fn from(text: String) -> Self {
+        AttachmentSource::Bytes(text.into_bytes())
+    }
+}
+
+impl From<&str> for AttachmentSource {
+    fn from(text: &str) -> Self {
+        AttachmentSource::Bytes(text.as_bytes().to_vec())
+    }
+}
+
+impl From<PathBuf> for AttachmentSource {
+    fn from(path: PathBuf) -> Self {
+        AttachmentSource::File(path)
+    }
+}
+
+impl From<&Path> for AttachmentSource {
+    fn from(path: &Path) -> Self {
+        AttachmentSource::File(path.to_path_buf())
+    }
+}
+
+static DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Save attached bytes under `dir` instead of the temp dir. Set from
+/// `--artifact-dir`.
+pub(crate) fn set_dir(dir: impl Into<PathBuf>) {
+    *DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir.into());
+}
+
+fn dir() -> PathBuf {
+    DIR.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join("rsspec-artifacts"))
+}
+
+/// Record an artifact on the current test, to be listed with its failure.
+///
+/// `content` is either the artifact itself (a `String`, `&str`, or bytes),
+/// which is saved to the artifact directory as `name`, or the [`Path`] of a
+/// file the test already wrote. Artifacts of tests that pass are discarded
+/// (and saved bytes deleted) unless the run uses `--keep-artifacts`.
+///
+/// ```rust,ignore
+/// ctx.it("renders the dashboard", || {
+///     let page = browser.open("/dashboard");
+///     rsspec::attach("dashboard.png", page.screenshot());
+///     rsspec::attach("server.log", Path::new("target/server.log"));
+///     assert!(page.contains("Welcome"));
+/// });
+/// ```
+///
+/// Outside of a test this does nothing but print a warning.
+pub fn attach(name: impl Into<String>, content: impl Into<AttachmentSource>) {
+    let name = name.into();
+    let Some(test_path) = crate::with_current_test(|test| test.map(|t| t.path.clone())) else {
+        diag!("  warning: rsspec::attach(\"{name}\") called outside of a test");
+        return;
+    };
+    let attachment = match content.into() {
+        AttachmentSource::File(path) => Attachment {
+            name,
+            path: absolute(path),
+            saved: false,
+        },
+        AttachmentSource::Bytes(bytes) => {
+            let path = absolute(dir().join(file_name(&test_path)).join(file_name(&name)));
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(&path, bytes));
+            if let Err(e) = written {
+                diag!("  warning: cannot save attachment '{name}' to {}: {e}", path.display());
+                return;
+            }
+            Attachment {
+                name,
+                path,
+                saved: true,
+            }
+        }
+    };
+    crate::add_attachment(attachment);
+}
+
+/// Delete the files rsspec saved for `attachments` (a test that passed).
+pub(crate) fn discard(attachments: Vec<Attachment>) {
+    for attachment in attachments.into_iter().filter(|a| a.saved) {
+        let _ = std::fs::remove_file(&attachment.path);
+        if let Some(parent) = attachment.path.parent() {
+            // Only succeeds once the test's folder is empty.
+            let _ = std::fs::remove_dir(parent);
+        }
+    }
+}
+
+/// A file name for `text`: path separators and other awkward characters
+/// become `_`, and `.`/`..` can't escape the artifact directory.
+fn file_name(text: &str) -> String {
+    let name: String = text
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() => c,
+            '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+    if name.chars().all(|c| c == '.') {
+        "_".repeat(name.len().max(1))
+    } else {
+        name
+    }
+}
+
+/// CI tools resolve artifact paths from elsewhere, so report them absolute.
+fn absolute(path: PathBuf) -> PathBuf {
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_replace_separators() {
+        assert_eq!(file_name("db > writes / rows"), "db___writes___rows");
+        assert_eq!(file_name("report.v2.txt"), "report.v2.txt");
+        assert_eq!(file_name(".."), "__");
+        assert_eq!(file_name(""), "_");
+    }
+}
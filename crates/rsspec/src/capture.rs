@@ -0,0 +1,162 @@
+//! `--capture`: hold back what a test prints and show it only if the test
+//! fails, the way `cargo test` does.
+//!
+//! The capture redirects file descriptors 1 and 2 into a temporary file, so
+//! it also catches output from threads and child processes the test starts.
+//! It needs Linux; elsewhere output passes through as usual.
+
+#[cfg(target_os = "linux")]
+pub(crate) use linux::{print_uncaptured, Capture};
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) use fallback::{print_uncaptured, Capture};
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::File;
+    use std::io::{Read, Seek, Write};
+    use std::os::fd::AsRawFd;
+    use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+
+    /// Duplicate of the real stdout while a capture is active, or -1.
+    static REAL_STDOUT: AtomicI32 = AtomicI32::new(-1);
+    /// Makes each capture file name unique within the process.
+    static NEXT_FILE: AtomicU64 = AtomicU64::new(0);
+
+    /// Output captured since [`start`](Self::start). Dropping it without
+    /// [`finish`](Self::finish) restores stdout and stderr and discards it.
+    pub(crate) struct Capture {
+        file: File,
+        /// Duplicates of the original stdout and stderr, until restored.
+        saved: Option<[i32; 2]>,
+    }
+
+    impl Capture {
+        /// Start capturing stdout and stderr, or `None` (with a warning) if
+        /// the redirect can't be set up.
+        pub(crate) fn start() -> Option<Self> {
+            match Self::redirect() {
+                Ok(capture) => Some(capture),
+                Err(e) => {
+                    eprintln!("rsspec: --capture unavailable: {e}");
+                    None
+                }
+            }
+        }
+
+        fn redirect() -> std::io::Result<Self> {
+            let n = NEXT_FILE.fetch_add(1, Ordering::Relaxed);
+            let name = format!("rsspec-capture-{}-{n}", std::process::id());
+            let path = std::env::temp_dir().join(name);
+            let file = File::options()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)?;
+            // The open handle keeps the data; nothing is left behind on disk.
+            std::fs::remove_file(&path)?;
+
+            let _ = std::io::stdout().flush();
+            let mut saved = [-1; 2];
+            for (fd, copy) in [1, 2].into_iter().zip(&mut saved) {
+                // SAFETY: duplicating a descriptor has no memory-safety effects.
+                *copy = unsafe { libc::dup(fd) };
+                if *copy < 0 {
+                    let error = std::io::Error::last_os_error();
+                    close_all(&saved);
+                    return Err(error);
+                }
+            }
+            let mut capture = Capture {
+                file,
+                saved: Some(saved),
+            };
+            for fd in [1, 2] {
+                // SAFETY: both descriptors are open for the duration of the call.
+                if unsafe { libc::dup2(capture.file.as_raw_fd(), fd) } < 0 {
+                    let error = std::io::Error::last_os_error();
+                    capture.restore();
+                    return Err(error);
+                }
+            }
+            REAL_STDOUT.store(saved[0], Ordering::SeqCst);
+            Ok(capture)
+        }
+
+        /// Stop capturing and return what was printed.
+        pub(crate) fn finish(mut self) -> String {
+            self.restore();
+            let mut bytes = Vec::new();
+            let _ = self.file.rewind();
+            let _ = self.file.read_to_end(&mut bytes);
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+
+        fn restore(&mut self) {
+            let Some(saved) = self.saved.take() else {
+                return;
+            };
+            let _ = std::io::stdout().flush();
+            REAL_STDOUT.store(-1, Ordering::SeqCst);
+            for (fd, copy) in [1, 2].into_iter().zip(saved) {
+                // SAFETY: `copy` is a descriptor this capture opened with `dup`.
+                unsafe { libc::dup2(copy, fd) };
+            }
+            close_all(&saved);
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) {
+            self.restore();
+        }
+    }
+
+    fn close_all(fds: &[i32]) {
+        for &fd in fds.iter().filter(|&&fd| fd >= 0) {
+            // SAFETY: only descriptors this module opened are closed.
+            unsafe { libc::close(fd) };
+        }
+    }
+
+    /// Print `line` to the real stdout, even while a capture is active.
+    pub(crate) fn print_uncaptured(line: &str) {
+        let mut out = std::io::stdout().lock();
+        let fd = REAL_STDOUT.load(Ordering::SeqCst);
+        if fd < 0 {
+            let _ = writeln!(out, "{line}");
+            let _ = out.flush();
+            return;
+        }
+        let _ = out.flush();
+        let text = format!("{line}\n");
+        // SAFETY: `text` is valid for `text.len()` bytes.
+        unsafe { libc::write(fd, text.as_ptr().cast(), text.len()) };
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod fallback {
+    use std::io::Write;
+
+    /// Output capture isn't supported here; [`start`](Self::start) always
+    /// returns `None`.
+    pub(crate) struct Capture;
+
+    impl Capture {
+        pub(crate) fn start() -> Option<Self> {
+            None
+        }
+
+        pub(crate) fn finish(self) -> String {
+            String::new()
+        }
+    }
+
+    pub(crate) fn print_uncaptured(line: &str) {
+        let mut out = std::io::stdout().lock();
+        let _ = writeln!(out, "{line}");
+        let _ = out.flush();
+    }
+}
@@ -1,7 +1,12 @@
 //! Closure-based BDD API — Context, ItBuilder, SuiteBuilder, and `run()`.
 
-use crate::runner::{self, RunConfig, Suite, TestNode};
+use crate::reporter::Reporter;
+use crate::result_tree::ResultTree;
+use crate::runner::{self, Plan, RunConfig, RunResult, Suite, TestFn, TestNode};
+use crate::shuffle::ShuffleOverride;
 use std::cell::RefCell;
+use std::panic::Location;
+use std::rc::Rc;
 
 // ============================================================================
 // Thread-local suite builder
@@ -13,13 +18,20 @@ thread_local! {
 
 pub(crate) struct SuiteBuilder {
     stack: Vec<GroupFrame>,
+    plans: Vec<Plan>,
+    missing_capabilities: Vec<String>,
 }
 
 struct GroupFrame {
     name: String,
     focused: bool,
     pending: bool,
+    allow_focus: bool,
     labels: Vec<String>,
+    replace_labels: bool,
+    owner: Option<String>,
+    resources: Vec<String>,
+    shuffle: Option<ShuffleOverride>,
     before_each: Vec<Box<dyn Fn()>>,
     after_each: Vec<Box<dyn Fn()>>,
     before_all: Vec<Box<dyn Fn()>>,
@@ -34,7 +46,12 @@ impl GroupFrame {
             name: String::new(),
             focused: false,
             pending: false,
+            allow_focus: false,
             labels: Vec::new(),
+            replace_labels: false,
+            owner: None,
+            shuffle: None,
+            resources: Vec::new(),
             before_each: Vec::new(),
             after_each: Vec::new(),
             before_all: Vec::new(),
@@ -43,12 +60,34 @@ impl GroupFrame {
             children: Vec::new(),
         }
     }
+
+    fn into_node(self) -> TestNode {
+        TestNode::Describe {
+            name: self.name,
+            focused: self.focused,
+            pending: self.pending,
+            allow_focus: self.allow_focus,
+            labels: self.labels,
+            replace_labels: self.replace_labels,
+            owner: self.owner,
+            shuffle: self.shuffle,
+            resources: self.resources,
+            before_each: self.before_each,
+            after_each: self.after_each,
+            before_all: self.before_all,
+            after_all: self.after_all,
+            just_before_each: self.just_before_each,
+            children: self.children,
+        }
+    }
 }
 
 impl SuiteBuilder {
     fn new() -> Self {
         SuiteBuilder {
             stack: vec![GroupFrame::root()],
+            plans: Vec::new(),
+            missing_capabilities: Vec::new(),
         }
     }
 
@@ -57,7 +96,12 @@ impl SuiteBuilder {
             name,
             focused,
             pending,
+            allow_focus: false,
             labels: Vec::new(),
+            replace_labels: false,
+            owner: None,
+            shuffle: None,
+            resources: Vec::new(),
             before_each: Vec::new(),
             after_each: Vec::new(),
             before_all: Vec::new(),
@@ -69,18 +113,7 @@ impl SuiteBuilder {
 
     pub(crate) fn pop_group(&mut self) {
         let frame = self.stack.pop().expect("rsspec: unbalanced group push/pop");
-        let node = TestNode::Describe {
-            name: frame.name,
-            focused: frame.focused,
-            pending: frame.pending,
-            labels: frame.labels,
-            before_each: frame.before_each,
-            after_each: frame.after_each,
-            before_all: frame.before_all,
-            after_all: frame.after_all,
-            just_before_each: frame.just_before_each,
-            children: frame.children,
-        };
+        let node = frame.into_node();
         self.current_frame_mut().children.push(node);
     }
 
@@ -112,17 +145,57 @@ impl SuiteBuilder {
         self.current_frame_mut().labels.extend(labels);
     }
 
+    fn replace_labels(&mut self, labels: Vec<String>) {
+        let frame = self.current_frame_mut();
+        frame.labels = labels;
+        frame.replace_labels = true;
+    }
+
+    fn allow_focus(&mut self) {
+        self.current_frame_mut().allow_focus = true;
+    }
+
+    fn set_owner(&mut self, owner: String) {
+        self.current_frame_mut().owner = Some(owner);
+    }
+
+    fn add_resource(&mut self, resource: String) {
+        self.current_frame_mut().resources.push(resource);
+    }
+
+    fn set_shuffle(&mut self, shuffle: ShuffleOverride) {
+        self.current_frame_mut().shuffle = Some(shuffle);
+    }
+
+    fn add_plan(&mut self, plan: Plan) {
+        self.plans.push(plan);
+    }
+
+    fn add_missing_capability(&mut self, name: String) {
+        self.missing_capabilities.push(name);
+    }
+
     fn current_frame_mut(&mut self) -> &mut GroupFrame {
         self.stack.last_mut().expect("rsspec: empty builder stack")
     }
 
-    fn into_nodes(mut self) -> Vec<TestNode> {
+    fn into_suite(mut self) -> Suite {
         assert_eq!(
             self.stack.len(),
             1,
             "rsspec: unbalanced group push/pop at finalization"
         );
-        self.stack.pop().unwrap().children
+        let mut root = self.stack.pop().unwrap();
+        let mut suite = Suite::new("", std::mem::take(&mut root.children));
+        suite.root = root.into_node();
+        suite.plans = self.plans;
+        if !self.missing_capabilities.is_empty() {
+            suite.skip_reason = Some(format!(
+                "missing capability: {}",
+                self.missing_capabilities.join(", ")
+            ));
+        }
+        suite
     }
 }
 
@@ -207,6 +280,50 @@ impl Context {
         self.xdescribe(name, body);
     }
 
+    /// Like [`describe`](Self::describe), but the scope's hooks and tests
+    /// share a value of type `S`, built by `init` for every test. See
+    /// [`StateContext`](crate::StateContext).
+    pub fn describe_with_state<S: 'static>(
+        &self,
+        name: &str,
+        init: impl Fn() -> S + 'static,
+        body: impl FnOnce(&crate::StateContext<S>),
+    ) {
+        self.describe(name, |_| crate::state::StateContext::enter(init, body));
+    }
+
+    /// Define the describe `name` once per fixture, as `name (fixture)`.
+    ///
+    /// `body` builds each copy and gets that copy's fixture value, shared
+    /// with its tests through an [`Rc`]. Use it to run a whole group of
+    /// tests against several backends; unlike a table, every copy holds any
+    /// number of tests and hooks.
+    ///
+    /// ```rust,no_run
+    /// # use std::rc::Rc;
+    /// # fn main() { rsspec::run(|ctx| {
+    /// let dirs = vec![("temp", std::env::temp_dir()), ("cwd", ".".into())];
+    /// ctx.describe_with_fixtures("scratch dir", dirs, |ctx, dir| {
+    ///     let d = Rc::clone(&dir);
+    ///     ctx.it("exists", move || assert!(d.is_dir()));
+    ///     ctx.it("is listable", move || {
+    ///         assert!(std::fs::read_dir(&*dir).is_ok());
+    ///     });
+    /// });
+    /// # }); }
+    /// ```
+    pub fn describe_with_fixtures<T: 'static>(
+        &self,
+        name: &str,
+        fixtures: Vec<(&str, T)>,
+        body: impl Fn(Context, Rc<T>),
+    ) {
+        for (fixture, value) in fixtures {
+            let value = Rc::new(value);
+            self.describe(&format!("{name} ({fixture})"), |ctx| body(ctx, value));
+        }
+    }
+
     fn describe_impl(&self, name: &str, focused: bool, pending: bool, body: impl FnOnce(Context)) {
         with_builder(|b| b.push_group(name.to_string(), focused, pending));
         body(Context);
@@ -227,31 +344,62 @@ impl Context {
     ///     .timeout(5000);
     /// # }); }
     /// ```
+    #[track_caller]
     pub fn it(&self, name: &str, body: impl Fn() + 'static) -> ItBuilder {
         ItBuilder::new(name.to_string(), body, false, false)
     }
 
     /// Focused variant of [`it`](Self::it). Only focused tests run; others are skipped.
+    #[track_caller]
     pub fn fit(&self, name: &str, body: impl Fn() + 'static) -> ItBuilder {
         ItBuilder::new(name.to_string(), body, true, false)
     }
 
     /// Pending variant of [`it`](Self::it). The body is registered but never executed.
+    #[track_caller]
     pub fn xit(&self, name: &str, body: impl Fn() + 'static) -> ItBuilder {
         ItBuilder::new(name.to_string(), body, false, true)
     }
 
+    /// Like [`it`](Self::it), for a body that is `Send + Sync`, so that a
+    /// [`timeout`](ItBuilder::timeout) can actually stop waiting for it.
+    ///
+    /// Under a timeout the body runs on its own thread (hooks stay on the
+    /// runner's). If the deadline passes, the test fails and the thread is
+    /// abandoned: it keeps running in the background until the body returns
+    /// or the process exits, so a hang costs a leaked thread instead of the
+    /// whole run. Inside the body, `current_test`, `skip!`, `context_note`
+    /// and `attach` work as usual; cleanups from `defer_cleanup` run as soon
+    /// as the body returns, before `after_each`.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.it_send("responds within a second", || {
+    ///     // e.g. a blocking request that might hang
+    /// })
+    /// .timeout(1000);
+    /// # }); }
+    /// ```
+    #[track_caller]
+    pub fn it_send(&self, name: &str, body: impl Fn() + Send + Sync + 'static) -> ItBuilder {
+        let body = TestFn::Send(std::sync::Arc::new(body));
+        ItBuilder::with_body(name.to_string(), body, false, false)
+    }
+
     /// Alias for [`it`](Self::it).
+    #[track_caller]
     pub fn specify(&self, name: &str, body: impl Fn() + 'static) -> ItBuilder {
         self.it(name, body)
     }
 
     /// Alias for [`fit`](Self::fit).
+    #[track_caller]
     pub fn fspecify(&self, name: &str, body: impl Fn() + 'static) -> ItBuilder {
         self.fit(name, body)
     }
 
     /// Alias for [`xit`](Self::xit).
+    #[track_caller]
     pub fn xspecify(&self, name: &str, body: impl Fn() + 'static) -> ItBuilder {
         self.xit(name, body)
     }
@@ -264,20 +412,117 @@ impl Context {
         with_builder(|b| b.add_before_each(Box::new(hook)));
     }
 
+    /// Like [`before_each`](Self::before_each), but only runs for tests whose
+    /// [`TestInfo`](crate::TestInfo) matches `predicate`.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.describe("accounts", |ctx| {
+    ///     ctx.before_each_if(|info| info.has_label("auth"), || { /* log in */ });
+    ///     ctx.it("shows the profile", || {}).labels(&["auth"]);
+    /// });
+    /// # }); }
+    /// ```
+    pub fn before_each_if(
+        &self,
+        predicate: impl Fn(&crate::TestInfo) -> bool + 'static,
+        hook: impl Fn() + 'static,
+    ) {
+        self.before_each(move || {
+            if crate::with_current_test(|info| info.is_some_and(&predicate)) {
+                hook();
+            }
+        });
+    }
+
     /// Register a hook that runs after every test in this scope and nested scopes,
     /// even if the test panics. Multiple `after_each` hooks run inner-to-outer.
     pub fn after_each(&self, hook: impl Fn() + 'static) {
         with_builder(|b| b.add_after_each(Box::new(hook)));
     }
 
+    /// Like [`after_each`](Self::after_each), but `hook` is told whether the
+    /// test body panicked, e.g. to snapshot state only when it failed.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.describe("orders", |ctx| {
+    ///     ctx.after_each_result(|outcome| {
+    ///         if outcome.body_panicked {
+    ///             eprintln!("{} failed; dumping the database", outcome.name);
+    ///         }
+    ///     });
+    ///     ctx.it("are stored", || {});
+    /// });
+    /// # }); }
+    /// ```
+    ///
+    /// `body_panicked` covers the `before_each` hooks and the body only, so
+    /// it isn't the test's final verdict; see
+    /// [`TestOutcome::body_panicked`](crate::TestOutcome::body_panicked).
+    pub fn after_each_result(&self, hook: impl Fn(&crate::TestOutcome) + 'static) {
+        self.after_each(move || {
+            // Cloned so the hook may `attach` to the test it's told about.
+            let name = crate::with_current_test(|info| info.map(|info| info.name.clone()));
+            hook(&crate::TestOutcome {
+                body_panicked: crate::body_panicked(),
+                name: name.as_deref().unwrap_or_default(),
+            });
+        });
+    }
+
+    /// Like [`after_each`](Self::after_each), but only runs for tests whose
+    /// [`TestInfo`](crate::TestInfo) matches `predicate`.
+    pub fn after_each_if(
+        &self,
+        predicate: impl Fn(&crate::TestInfo) -> bool + 'static,
+        hook: impl Fn() + 'static,
+    ) {
+        self.after_each(move || {
+            if crate::with_current_test(|info| info.is_some_and(&predicate)) {
+                hook();
+            }
+        });
+    }
+
     /// Register a hook that runs once before all tests in this describe scope.
     /// Not inherited by nested scopes. Skipped if all children are filtered out.
+    /// Outside any describe, it runs once before all of the suite's tests
+    /// (with `--jobs`, once in each worker).
     pub fn before_all(&self, hook: impl Fn() + 'static) {
         with_builder(|b| b.add_before_all(Box::new(hook)));
     }
 
+    /// Like [`before_all`](Self::before_all), but `init` builds a value for
+    /// the scope's tests, returned as a [`Shared`](crate::Shared) handle.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// use std::net::TcpListener;
+    ///
+    /// ctx.describe("api", |ctx| {
+    ///     let listener = ctx.before_all_value(|| TcpListener::bind("127.0.0.1:0").unwrap());
+    ///     ctx.it("listens", move || assert!(listener.get().local_addr().is_ok()));
+    /// });
+    /// # }); }
+    /// ```
+    ///
+    /// The value is built once per run of the scope and lives until the run
+    /// ends, so it outlasts the scope's `after_all` hooks. To drop it
+    /// earlier, [`take`](crate::Shared::take) it in an `after_all`.
+    pub fn before_all_value<T: 'static>(
+        &self,
+        init: impl Fn() -> T + 'static,
+    ) -> crate::Shared<T> {
+        let shared = crate::Shared::empty();
+        let slot = shared.clone();
+        self.before_all(move || slot.set(init()));
+        shared
+    }
+
     /// Register a hook that runs once after all tests in this describe scope.
     /// Not inherited by nested scopes. Runs even if `before_all` panicked.
+    /// Outside any describe, it runs once after all of the suite's tests.
     pub fn after_all(&self, hook: impl Fn() + 'static) {
         with_builder(|b| b.add_after_all(Box::new(hook)));
     }
@@ -306,6 +551,141 @@ impl Context {
         with_builder(|b| b.add_labels(labels));
     }
 
+    /// Set the labels of the current describe scope, dropping the ones it
+    /// would inherit from enclosing scopes. Tests and nested scopes below
+    /// see only these (plus their own); use it to opt a child out of a
+    /// parent's `"slow"` tag. Later [`labels`](Self::labels) calls in the
+    /// same scope add to the replacement.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.describe("database", |ctx| {
+    ///     ctx.labels(&["db", "slow"]);
+    ///
+    ///     ctx.describe("schema parsing", |ctx| {
+    ///         ctx.labels_replace(&["db"]);
+    ///         ctx.it("is fast", || { /* labels: ["db"] */ });
+    ///     });
+    /// });
+    /// # }); }
+    /// ```
+    pub fn labels_replace(&self, labels: &[&str]) {
+        let labels: Vec<String> = labels.iter().map(|s| s.to_string()).collect();
+        with_builder(|b| b.replace_labels(labels));
+    }
+
+    /// Exempt the current describe scope from the `RSSPEC_FAIL_ON_FOCUS` check.
+    ///
+    /// Use inside an intentionally committed [`fdescribe`](Self::fdescribe)
+    /// (e.g. a canary) so CI can still reject stray focus elsewhere.
+    ///
+    /// The exemption covers the focus the scope passes down to its tests,
+    /// through nested describes too. A `fit` or `fdescribe` nested inside
+    /// is focus of its own and is still checked unless it opts out itself,
+    /// as is a focused ordered step.
+    pub fn allow_focus(&self) {
+        with_builder(|b| b.allow_focus());
+    }
+
+    /// Run the current describe scope's children, and everything nested
+    /// below them, in declaration order even under `--shuffle` or `--reverse`.
+    ///
+    /// The scope itself still takes its shuffled place among its siblings.
+    /// A nested describe can opt back in with [`seed`](Self::seed).
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.describe("migrations", |ctx| {
+    ///     ctx.no_shuffle();
+    ///     ctx.it("creates the table", || {});
+    ///     ctx.it("adds the index", || {});
+    /// });
+    /// # }); }
+    /// ```
+    pub fn no_shuffle(&self) {
+        with_builder(|b| b.set_shuffle(ShuffleOverride::Pinned));
+    }
+
+    /// Under `--shuffle`, order the current describe scope's subtree with
+    /// `seed` instead of the run seed, so it keeps the same order while the
+    /// rest of the run is reshuffled. Has no effect without `--shuffle`.
+    pub fn seed(&self, seed: u64) {
+        with_builder(|b| b.set_shuffle(ShuffleOverride::Seed(seed)));
+    }
+
+    /// Set the owning team for the current describe scope. Nested scopes and
+    /// tests inherit it unless they set their own.
+    ///
+    /// Owners are selectable with `--owner` and group failures in the summary.
+    pub fn owner(&self, owner: &str) {
+        with_builder(|b| b.set_owner(owner.to_string()));
+    }
+
+    /// Declare that the tests of the current describe scope mutate the
+    /// named shared resource (a database, a port, a directory). Calls
+    /// accumulate.
+    ///
+    /// Under `--jobs`, two top-level describes that use the same resource
+    /// never run at the same time; ones with disjoint resources still run
+    /// in parallel. See also [`ItBuilder::resource`].
+    ///
+    /// Only top-level describes are scheduled. A resource declared in a
+    /// nested describe counts as its top-level describe's, held for that
+    /// whole describe's run; it doesn't order anything within it.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.describe("user store", |ctx| {
+    ///     ctx.resource("db");
+    ///     ctx.it("inserts", || { /* ... */ });
+    /// });
+    /// ctx.describe("audit log", |ctx| {
+    ///     ctx.it("truncates", || { /* ... */ }).resource("db");
+    /// });
+    /// # }); }
+    /// ```
+    pub fn resource(&self, name: &str) {
+        with_builder(|b| b.add_resource(name.to_string()));
+    }
+
+    /// Gate the whole suite on a runtime capability (GPU present, network
+    /// up, ...). `probe` is called once, immediately; if it returns `false`,
+    /// nothing runs — not even hooks — and every test is reported as skipped
+    /// with "missing capability: <name>", so reports still list them.
+    ///
+    /// ```rust,no_run
+    /// # fn detect_gpu() -> bool { false }
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.requires_capability("gpu", detect_gpu);
+    /// ctx.it("renders", || { /* ... */ });
+    /// # }); }
+    /// ```
+    pub fn requires_capability(&self, name: &str, probe: impl FnOnce() -> bool) {
+        if !probe() {
+            with_builder(|b| b.add_missing_capability(name.to_string()));
+        }
+    }
+
+    /// Register a named selection of tests, run with `--plan <name>`.
+    ///
+    /// Each term selects tests that carry it as a label (own or inherited) or
+    /// contain it in their full `describe > it` path (case-insensitive).
+    /// Selected tests must still pass any path filter and
+    /// `RSSPEC_LABEL_FILTER`. Registering the same name again extends the plan.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.plan("smoke", &["smoke", "Login >"]);
+    /// # }); }
+    /// ```
+    pub fn plan(&self, name: &str, terms: &[&str]) {
+        let plan = Plan {
+            name: name.to_string(),
+            terms: terms.iter().map(|t| t.to_string()).collect(),
+        };
+        with_builder(|b| b.add_plan(plan));
+    }
+
     // ---- Table-driven --------------------------------------------------------
 
     /// Start building a table-driven test.
@@ -324,6 +704,29 @@ impl Context {
         crate::table::TableBuilder::new(name.to_string())
     }
 
+    /// Run `test_fn` once per element of `cases`, each as its own test named
+    /// `case_1`, `case_2`, ... under a describe called `name`.
+    ///
+    /// Shorthand for a [`describe_table`](Self::describe_table) of
+    /// [`case_unnamed`](crate::table::TypedTableBuilder::case_unnamed) rows.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.it_table("squares are positive", &[2i32, 3, 4], |n| {
+    ///     assert!(n * n > 0);
+    /// });
+    /// # }); }
+    /// ```
+    pub fn it_table<T: Clone + 'static>(
+        &self,
+        name: &str,
+        cases: &[T],
+        test_fn: impl Fn(&T) + 'static,
+    ) {
+        crate::table::TypedTableBuilder::unnamed(name.to_string(), cases.iter().cloned())
+            .run(test_fn);
+    }
+
     // ---- Ordered -------------------------------------------------------------
 
     /// Define an ordered sequence of steps that run as a single test.
@@ -374,6 +777,7 @@ impl Context {
     /// .retries(3)
     /// .timeout(5000);
     /// ```
+    #[track_caller]
     pub fn async_it<F, Fut>(&self, name: &str, body: F) -> ItBuilder
     where
         F: Fn() -> Fut + 'static,
@@ -383,6 +787,7 @@ impl Context {
     }
 
     /// Focused variant of [`async_it`](Self::async_it).
+    #[track_caller]
     pub fn async_fit<F, Fut>(&self, name: &str, body: F) -> ItBuilder
     where
         F: Fn() -> Fut + 'static,
@@ -392,6 +797,7 @@ impl Context {
     }
 
     /// Pending variant of [`async_it`](Self::async_it).
+    #[track_caller]
     pub fn async_xit<F, Fut>(&self, name: &str, body: F) -> ItBuilder
     where
         F: Fn() -> Fut + 'static,
@@ -501,26 +907,58 @@ impl Context {
 /// ```
 pub struct ItBuilder {
     name: String,
-    body: Option<Box<dyn Fn()>>,
+    body: Option<TestFn>,
     focused: bool,
     pending: bool,
     labels: Vec<String>,
     retries: Option<u32>,
+    retry_backoff_ms: Option<u64>,
     timeout_ms: Option<u64>,
+    max_duration_ms: Option<u64>,
+    max_rss_mb: Option<u64>,
+    timeout_mode: crate::TimeoutMode,
     must_pass_repeatedly: Option<u32>,
+    repeat: Option<u32>,
+    flaky: bool,
+    xfail: Option<String>,
+    skip_reason: Option<String>,
+    allow_focus: bool,
+    depends_on: Vec<String>,
+    owner: Option<String>,
+    resources: Vec<String>,
+    location: &'static Location<'static>,
 }
 
 impl ItBuilder {
+    #[track_caller]
     fn new(name: String, body: impl Fn() + 'static, focused: bool, pending: bool) -> Self {
+        Self::with_body(name, TestFn::Local(Box::new(body)), focused, pending)
+    }
+
+    #[track_caller]
+    fn with_body(name: String, body: TestFn, focused: bool, pending: bool) -> Self {
         ItBuilder {
             name,
-            body: Some(Box::new(body)),
+            body: Some(body),
             focused,
             pending,
             labels: Vec::new(),
             retries: None,
+            retry_backoff_ms: None,
             timeout_ms: None,
+            max_duration_ms: None,
+            max_rss_mb: None,
+            timeout_mode: crate::TimeoutMode::Total,
             must_pass_repeatedly: None,
+            repeat: None,
+            flaky: false,
+            xfail: None,
+            skip_reason: None,
+            allow_focus: false,
+            depends_on: Vec::new(),
+            owner: None,
+            resources: Vec::new(),
+            location: Location::caller(),
         }
     }
 
@@ -537,21 +975,172 @@ impl ItBuilder {
         self
     }
 
+    /// Like [`retries`](Self::retries), but wait before each retry:
+    /// `base_ms` after the first failed attempt, then twice as long after
+    /// each further one (`base_ms * 2^(attempt - 1)`). For tests against
+    /// services that need a moment to recover.
+    pub fn retries_with_backoff(mut self, n: u32, base_ms: u64) -> Self {
+        self.retries = Some(n);
+        self.retry_backoff_ms = Some(base_ms);
+        self
+    }
+
     /// Fail the test if it exceeds `ms` milliseconds.
     ///
-    /// **Note:** The timeout is checked *after* the closure returns — the
-    /// closure cannot be forcibly aborted mid-execution. If your test blocks
-    /// forever (e.g. an infinite loop or deadlock), the timeout will not fire.
+    /// **Note:** Only a body registered with [`it_send`](Context::it_send) is
+    /// abandoned at the deadline. Every other body ([`it`](Context::it),
+    /// [`async_it`](Context::async_it), table rows) and the hooks run on the
+    /// runner's thread and are checked *after* they return — they cannot be
+    /// aborted mid-execution, so a test that blocks forever (e.g. an infinite
+    /// loop or deadlock) hangs the run.
     pub fn timeout(mut self, ms: u64) -> Self {
         self.timeout_ms = Some(ms);
         self
     }
 
+    /// Choose whether `timeout` bounds all runs of the body together
+    /// ([`TimeoutMode::Total`](crate::TimeoutMode::Total), the default) or
+    /// each retry attempt, `must_pass_repeatedly` iteration and `repeat` run
+    /// on its own ([`TimeoutMode::PerIteration`](crate::TimeoutMode::PerIteration)).
+    ///
+    /// ```rust,ignore
+    /// ctx.it("stays fast", || { /* ... */ })
+    ///     .timeout(1000)
+    ///     .timeout_mode(rsspec::TimeoutMode::PerIteration)
+    ///     .must_pass_repeatedly(10);
+    /// ```
+    pub fn timeout_mode(mut self, mode: crate::TimeoutMode) -> Self {
+        self.timeout_mode = mode;
+        self
+    }
+
+    /// Give each attempt its own `timeout` deadline, so a timed-out attempt
+    /// is retried like any other failure.
+    ///
+    /// Shorthand for [`timeout_mode(TimeoutMode::PerIteration)`](Self::timeout_mode):
+    /// by default the timeout wraps the whole retry cycle and a timeout is
+    /// final; with this the order becomes `retries(timeout(body))`.
+    /// Useful for slow-start flakes. Has no effect without `timeout`.
+    pub fn retry_on_timeout(self) -> Self {
+        self.timeout_mode(crate::TimeoutMode::PerIteration)
+    }
+
+    /// Fail the test if its body takes longer than `ms` milliseconds.
+    ///
+    /// A performance budget rather than a hang cutoff: the body runs to
+    /// completion and its duration (excluding hooks) is checked afterwards,
+    /// failing with "took Xms, budget Yms". With retries, each attempt is
+    /// checked on its own.
+    pub fn max_duration(mut self, ms: u64) -> Self {
+        self.max_duration_ms = Some(ms);
+        self
+    }
+
+    /// Cap the test's memory at `mb` megabytes.
+    ///
+    /// On Linux, in `harness = false` suites, the test runs in a child
+    /// process with `RLIMIT_AS`/`RLIMIT_DATA` set, so a runaway allocation
+    /// fails just this test instead of the whole run. Elsewhere (other
+    /// platforms, or inside cargo test's harness) this is a no-op.
+    pub fn max_rss_mb(mut self, mb: u64) -> Self {
+        self.max_rss_mb = Some(mb);
+        self
+    }
+
     /// Require the test to pass `n` consecutive times.
     pub fn must_pass_repeatedly(mut self, n: u32) -> Self {
         self.must_pass_repeatedly = Some(n);
         self
     }
+
+    /// Run the test `n` times, for stress-testing nondeterministic code.
+    ///
+    /// Unlike [`must_pass_repeatedly`](Self::must_pass_repeatedly), every run
+    /// happens even after a failure; the test then fails with how many runs
+    /// failed and the first failure. Each run includes the `before_each` and
+    /// `after_each` hooks and is retried on its own with
+    /// [`retries`](Self::retries); a [`timeout`](Self::timeout) covers all
+    /// runs together. Panics if `n` is 0.
+    pub fn repeat(mut self, n: u32) -> Self {
+        assert!(n > 0, "rsspec: repeat requires n >= 1");
+        self.repeat = Some(n);
+        self
+    }
+
+    /// Mark the test as known-flaky.
+    ///
+    /// Shorthand for `retries(3)` plus quarantine: if the test still fails
+    /// after its retries it is reported as flaky in the summary instead of
+    /// failing the run. Combine with [`retries`](Self::retries) to change the
+    /// retry budget.
+    pub fn flaky(mut self) -> Self {
+        self.flaky = true;
+        self
+    }
+
+    /// Mark the test as an expected failure, with a reason (e.g. a bug reference).
+    ///
+    /// A failure is reported as `xfail` and does not fail the run. If the test
+    /// unexpectedly passes, it is reported as an `XPASS` failure so the stale
+    /// marker gets removed once the bug is fixed.
+    pub fn xfail(mut self, reason: &str) -> Self {
+        self.xfail = Some(reason.to_string());
+        self
+    }
+
+    /// Skip the test when `condition` holds, e.g. an environment check.
+    ///
+    /// The condition is evaluated at registration. A skipped test doesn't
+    /// run, nor do its hooks; it's reported as skipped (not pending) with
+    /// `reason`. With several calls, the first one whose condition holds
+    /// gives the reason. For a condition only known inside the test, use
+    /// [`skip!`](crate::skip!) instead.
+    pub fn skip_if(mut self, condition: bool, reason: &str) -> Self {
+        if condition && self.skip_reason.is_none() {
+            self.skip_reason = Some(reason.to_string());
+        }
+        self
+    }
+
+    /// Exempt this test from the `RSSPEC_FAIL_ON_FOCUS` check. Use on an
+    /// intentionally committed [`fit`](Context::fit).
+    pub fn allow_focus(mut self) -> Self {
+        self.allow_focus = true;
+        self
+    }
+
+    /// Set the owning team for this test, overriding any inherited from
+    /// describe scopes. See [`Context::owner`].
+    pub fn owner(mut self, owner: &str) -> Self {
+        self.owner = Some(owner.to_string());
+        self
+    }
+
+    /// Declare that this test mutates the named shared resource, in
+    /// addition to any its describe scopes declare. Calls accumulate. See
+    /// [`Context::resource`].
+    ///
+    /// Like a nested describe's resources, it is held by the test's
+    /// top-level describe for that describe's whole run.
+    pub fn resource(mut self, name: &str) -> Self {
+        self.resources.push(name.to_string());
+        self
+    }
+
+    /// Run this test only after the named test has passed. `name` is either
+    /// the test's full path (`"outer > inner > test"`) or a trailing part of
+    /// it that no other test shares. Calls accumulate.
+    ///
+    /// The runner orders siblings so the dependency runs first, under
+    /// `--shuffle` and `--reverse` too, and runs it even when filters,
+    /// focus or `--at` leave it out. Under `--jobs`, top-level nodes linked
+    /// this way share a worker. A name matching no test or several, or a
+    /// cycle, fails the run before any test starts. If the dependency failed
+    /// or was skipped, this test is skipped with the reason.
+    pub fn depends_on(mut self, name: &str) -> Self {
+        self.depends_on.push(name.to_string());
+        self
+    }
 }
 
 impl Drop for ItBuilder {
@@ -570,8 +1159,21 @@ impl Drop for ItBuilder {
             pending: self.pending,
             labels: std::mem::take(&mut self.labels),
             retries: self.retries,
+            retry_backoff_ms: self.retry_backoff_ms,
             timeout_ms: self.timeout_ms,
+            max_duration_ms: self.max_duration_ms,
+            max_rss_mb: self.max_rss_mb,
+            timeout_mode: self.timeout_mode,
             must_pass_repeatedly: self.must_pass_repeatedly,
+            repeat: self.repeat,
+            flaky: self.flaky,
+            xfail: self.xfail.take(),
+            skip_reason: self.skip_reason.take(),
+            allow_focus: self.allow_focus,
+            depends_on: std::mem::take(&mut self.depends_on),
+            owner: self.owner.take(),
+            resources: std::mem::take(&mut self.resources),
+            location: Some(self.location),
             test_fn: body,
         };
         with_builder(|b| b.add_node(node));
@@ -582,11 +1184,37 @@ impl Drop for ItBuilder {
 // run() / run_inline() — entry points
 // ============================================================================
 
+/// Build a suite without running it and render its structure as an
+/// indented outline: describes, tests (starting with `- `) and the steps
+/// of ordered tests, in declaration order, with `(focused)`/`(pending)`
+/// markers.
+///
+/// The output is stable, so it can be compared against a committed golden
+/// file or a snapshot to catch tests renamed, moved or deleted by accident.
+///
+/// ```rust
+/// let outline = rsspec::describe_tree(|ctx| {
+///     ctx.describe("Calculator", |ctx| {
+///         ctx.it("adds", || {});
+///         ctx.describe("with negatives", |ctx| {
+///             ctx.xit("subtracts", || {});
+///         });
+///     });
+/// });
+/// assert_eq!(
+///     outline,
+///     "Calculator\n  - adds\n  with negatives\n    - subtracts (pending)\n"
+/// );
+/// ```
+pub fn describe_tree(body: impl FnOnce(Context)) -> String {
+    runner::outline(&build_tree(body).nodes)
+}
+
 /// Build the test tree from user closures.
-fn build_tree(body: impl FnOnce(Context)) -> Vec<TestNode> {
-    BUILDER.with(|cell| {
-        *cell.borrow_mut() = Some(SuiteBuilder::new());
-    });
+fn build_tree(body: impl FnOnce(Context)) -> Suite {
+    let _restore = BuilderRestore {
+        previous: BUILDER.with(|cell| cell.borrow_mut().replace(SuiteBuilder::new())),
+    };
 
     body(Context);
 
@@ -594,19 +1222,60 @@ fn build_tree(body: impl FnOnce(Context)) -> Vec<TestNode> {
         cell.borrow_mut()
             .take()
             .expect("rsspec: builder missing after run")
-            .into_nodes()
+            .into_suite()
     })
 }
 
+/// Puts back the builder that was active before [`build_tree`] started, even
+/// if the body panics, so a failed build leaves no half-built tree behind
+/// and a suite built while another is being defined doesn't clobber it.
+struct BuilderRestore {
+    previous: Option<SuiteBuilder>,
+}
+
+impl Drop for BuilderRestore {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        BUILDER.with(|cell| *cell.borrow_mut() = previous);
+    }
+}
+
+/// Resolve the run configuration for this process: CLI args for
+/// `harness = false` targets, defaults inside cargo test's standard harness
+/// (auto-detected via libtest-specific args). Also returns whether we're
+/// inside the harness.
+fn process_config() -> (RunConfig, bool) {
+    let args: Vec<String> = std::env::args().collect();
+    let inside_harness = runner::detect_libtest_args(&args[1..]).is_some();
+
+    let config = if inside_harness {
+        RunConfig::default()
+    } else {
+        RunConfig::from_args().for_harness_free_process()
+    };
+    if config.no_diagnostics {
+        crate::diagnostics::disable();
+    }
+    if let Some(dir) = &config.artifact_dir {
+        crate::artifacts::set_dir(dir);
+    }
+    (config, inside_harness)
+}
+
 /// Build and run a BDD test suite.
 ///
 /// Works in both contexts:
 ///
 /// - **`harness = false`** — parses CLI args for filtering/listing, calls
-///   [`std::process::exit`] on failure.
+///   [`std::process::exit`] on failure (code 1 unless `--fail-exit-code` or
+///   `--setup-exit-code` say otherwise).
 /// - **`#[test]` functions** — auto-detected via libtest-specific CLI args;
 ///   skips arg parsing and panics on failure so other tests can still run.
 ///
+/// Functions registered with [`at_exit`](crate::at_exit) run after the
+/// suite finishes, before either of those happen, and also when the run
+/// itself panics.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -617,25 +1286,136 @@ fn build_tree(body: impl FnOnce(Context)) -> Vec<TestNode> {
 /// });
 /// ```
 pub fn run(body: impl FnOnce(Context)) {
-    let nodes = build_tree(body);
+    finish(|| run_reported(body));
+}
 
-    // Auto-detect: are we inside cargo test's standard harness?
-    let args: Vec<String> = std::env::args().collect();
-    let inside_harness = runner::detect_libtest_args(&args[1..]).is_some();
+/// Build and run a suite like [`run`], but return its [`RunResult`] instead
+/// of exiting or panicking on failure.
+///
+/// CLI args are parsed and output is printed as in `run`. Use it to run
+/// several independent suites from one `main`, or to post-process results
+/// before deciding on an exit code.
+///
+/// ```rust,no_run
+/// let api = rsspec::run_and_report(|ctx| {
+///     ctx.it("responds", || {});
+/// });
+/// let db = rsspec::run_and_report(|ctx| {
+///     ctx.it("connects", || {});
+/// });
+/// if api.failed + db.failed > 0 {
+///     std::process::exit(1);
+/// }
+/// ```
+pub fn run_and_report(body: impl FnOnce(Context)) -> RunResult {
+    run_reported(body).0
+}
 
-    let config = if inside_harness {
-        RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
+/// Build and run a suite with the process config and its reporter.
+fn run_reported(body: impl FnOnce(Context)) -> (RunResult, RunConfig, bool) {
+    let suite = build_tree(body);
+    let (config, inside_harness) = process_config();
+    let mut reporter = config.reporter();
+    let result = run_suite(suite, &config, inside_harness, &mut *reporter);
+    (result, config, inside_harness)
+}
+
+/// Like [`run`], but build the suite from several functions, run as one
+/// tree with a single summary.
+///
+/// Lets a large `harness = false` suite be split into modules, each
+/// defining its part of the tree. Bodies are built in order, so their
+/// top-level describes run (and are listed) in that order.
+///
+/// ```rust,no_run
+/// mod users {
+///     pub fn spec(ctx: rsspec::Context) {
+///         ctx.describe("users", |ctx| {
+///             ctx.it("signs up", || {});
+///         });
+///     }
+/// }
+///
+/// mod billing {
+///     pub fn spec(ctx: rsspec::Context) {
+///         ctx.describe("billing", |ctx| {
+///             ctx.it("charges", || {});
+///         });
+///     }
+/// }
+///
+/// fn main() {
+///     rsspec::run_all(&[users::spec, billing::spec]);
+/// }
+/// ```
+pub fn run_all(bodies: &[fn(Context)]) {
+    run(|ctx| {
+        for body in bodies {
+            body(ctx);
         }
-    } else {
-        RunConfig::from_args()
-    };
+    });
+}
 
-    let suite = Suite::new("", nodes);
-    let result = runner::run_suites(&[suite], &config);
+/// Like [`run`], but report through `reporter` instead of the built-in
+/// tree (or JSON) output.
+///
+/// `--jobs` is ignored, since worker processes can't share the reporter.
+///
+/// ```rust,no_run
+/// struct Count(usize);
+///
+/// impl rsspec::Reporter for Count {
+///     fn it_finished(&mut self, _test: &rsspec::TestResult) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let mut count = Count(0);
+/// rsspec::run_with_reporter(&mut count, |ctx| {
+///     ctx.it("adds", || assert_eq!(1 + 1, 2));
+/// });
+/// ```
+pub fn run_with_reporter(reporter: &mut dyn Reporter, body: impl FnOnce(Context)) {
+    let suite = build_tree(body);
+    let (mut config, inside_harness) = process_config();
+    if config.jobs.take().is_some() {
+        eprintln!("rsspec: --jobs is ignored with a custom reporter");
+    }
+    finish(|| {
+        let result = run_suite(suite, &config, inside_harness, reporter);
+        (result, config, inside_harness)
+    });
+}
 
+/// Run a suite through `run`, then the `at_exit` functions, then exit on
+/// failure. A panic out of the run (from a reporter, say) still runs the
+/// `at_exit` functions before it carries on unwinding.
+pub(crate) fn finish(run: impl FnOnce() -> (RunResult, RunConfig, bool)) {
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(run));
+    crate::run_at_exit_hooks();
+    match outcome {
+        Ok((result, config, inside_harness)) => exit_on_failure(&result, &config, inside_harness),
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+/// Run `suite`, under `--watch` if asked for.
+fn run_suite(
+    suite: Suite,
+    config: &RunConfig,
+    inside_harness: bool,
+    reporter: &mut dyn Reporter,
+) -> RunResult {
+    if config.watch && !inside_harness {
+        let stdin = std::io::BufReader::new(std::io::stdin());
+        runner::run_suites_watch(&[suite], config, reporter, stdin)
+    } else {
+        runner::run_suites(&[suite], config, reporter)
+    }
+}
+
+/// Exit (or panic inside `#[test]`) if anything in `result` failed.
+fn exit_on_failure(result: &RunResult, config: &RunConfig, inside_harness: bool) {
     if result.failed > 0 {
         if inside_harness {
             // Inside #[test]: panic so other test functions still run
@@ -646,12 +1426,13 @@ pub fn run(body: impl FnOnce(Context)) {
                 .map(|(i, f)| format!("  {}. {}", i + 1, f))
                 .collect::<Vec<_>>()
                 .join("\n");
+            let bailed = if result.bailed { " (stopped early by --bail)" } else { "" };
             panic!(
-                "rsspec: {} test(s) failed\n{}",
+                "rsspec: {} test(s) failed{bailed}\n{}",
                 result.failed, details
             );
-        } else {
-            std::process::exit(1);
+        } else if let Some(code) = config.exit_code(result) {
+            std::process::exit(code);
         }
     }
 }
@@ -660,29 +1441,27 @@ pub fn run(body: impl FnOnce(Context)) {
 ///
 /// Unlike [`run`], this does **not** parse command-line args (avoiding
 /// conflicts with `cargo test`'s own filter arguments) and **panics** on
-/// failure instead of calling `process::exit`.
+/// failure instead of calling `process::exit`. It returns the
+/// [`RunResult`] otherwise, for checks on the other counts.
 ///
 /// # Example
 ///
 /// ```rust,no_run
 /// #[test]
 /// fn calculator_spec() {
-///     rsspec::run_inline(|ctx| {
+///     let result = rsspec::run_inline(|ctx| {
 ///         ctx.describe("Calculator", |ctx| {
 ///             ctx.it("adds", || { assert_eq!(2 + 3, 5); });
+///             ctx.xit("divides", || {});
 ///         });
 ///     });
+///     assert_eq!((result.passed, result.pending), (1, 1));
 /// }
 /// ```
-pub fn run_inline(body: impl FnOnce(Context)) {
-    let nodes = build_tree(body);
-    let config = RunConfig {
-        filter: None,
-        list: false,
-        include_ignored: false,
-    };
-    let suite = Suite::new("", nodes);
-    let result = runner::run_suites(&[suite], &config);
+pub fn run_inline(body: impl FnOnce(Context)) -> RunResult {
+    let suite = build_tree(body);
+    let config = RunConfig::default();
+    let result = runner::run_suites(&[suite], &config, &mut *config.reporter());
 
     if result.failed > 0 {
         let details = result
@@ -697,4 +1476,39 @@ pub fn run_inline(body: impl FnOnce(Context)) {
             result.failed, details
         );
     }
+    result
+}
+
+/// Build and run a suite like [`run`], returning the results shaped as the
+/// describe/it tree instead of exiting or panicking on failure.
+///
+/// Each test carries its status, duration, attempt count and failure
+/// message — suited to dashboards and custom renderers. CLI args are parsed
+/// as in `run` (except `--watch`), and output is printed as usual.
+///
+/// ```rust,no_run
+/// let tree = rsspec::run_tree_result(|ctx| {
+///     ctx.describe("math", |ctx| {
+///         ctx.it("adds", || assert_eq!(1 + 1, 2));
+///     });
+/// });
+/// for test in tree.tests() {
+///     println!("{} {:?} {:?}", test.path, test.status, test.duration);
+/// }
+/// ```
+pub fn run_tree_result(body: impl FnOnce(Context)) -> ResultTree {
+    let (config, _) = process_config();
+    run_tree_with(config, body).1
+}
+
+/// Build and run `body` with `config` rather than the process's arguments,
+/// so this crate's own tests aren't filtered by libtest's.
+pub(crate) fn run_tree_with(
+    config: RunConfig,
+    body: impl FnOnce(Context),
+) -> (RunResult, ResultTree) {
+    let suites = [build_tree(body)];
+    let result = runner::run_suites(&suites, &config, &mut *config.reporter());
+    let tree = ResultTree::build(&suites[0].nodes, &result.tests);
+    (result, tree)
 }
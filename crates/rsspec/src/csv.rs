@@ -0,0 +1,248 @@
+//! CSV-backed table cases — see [`TableBuilder::csv`](crate::table::TableBuilder::csv).
+//!
+//! The file is embedded with [`include_csv!`](crate::include_csv) when the
+//! test binary compiles, so it's always the version the tests were built
+//! against. Rows are parsed when the suite is built.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// CSV text embedded by [`include_csv!`](crate::include_csv), with the path
+/// it came from for error messages.
+#[derive(Clone, Copy, Debug)]
+pub struct CsvSource {
+    path: &'static str,
+    text: &'static str,
+}
+
+impl CsvSource {
+    /// CSV `text` read from `path`. Prefer [`include_csv!`](crate::include_csv).
+    pub const fn new(path: &'static str, text: &'static str) -> Self {
+        CsvSource { path, text }
+    }
+
+    /// Parse every data row into a case name (its first column) and a `T`
+    /// (the other columns).
+    ///
+    /// Panics naming the file and line if a row has the wrong number of
+    /// columns, a cell doesn't parse, or a quote is left open.
+    pub(crate) fn rows<T: CsvRow>(&self) -> Vec<(String, T)> {
+        self.parse().unwrap_or_else(|e| panic!("rsspec: {e}"))
+    }
+
+    fn parse<T: CsvRow>(&self) -> Result<Vec<(String, T)>, CsvError> {
+        let mut lines = self
+            .text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((line, header)) = lines.next() else {
+            return Ok(Vec::new());
+        };
+        let columns = split_line(header).map_err(|e| self.error(line, e))?.len();
+        if columns != T::COLUMNS + 1 {
+            let message = format!(
+                "header has {columns} columns, expected {} (the case name, then one per value)",
+                T::COLUMNS + 1
+            );
+            return Err(self.error(line, message));
+        }
+
+        lines
+            .map(|(line, text)| {
+                let cells = split_line(text).map_err(|e| self.error(line, e))?;
+                if cells.len() != columns {
+                    let message = format!("expected {columns} columns, found {}", cells.len());
+                    return Err(self.error(line, message));
+                }
+                let values: Vec<&str> = cells[1..].iter().map(String::as_str).collect();
+                let data = T::from_cells(&values).map_err(|e| self.error(line, e))?;
+                Ok((cells[0].clone(), data))
+            })
+            .collect()
+    }
+
+    fn error(&self, line: usize, message: String) -> CsvError {
+        CsvError {
+            path: self.path,
+            line,
+            message,
+        }
+    }
+}
+
+/// A malformed row, reported as `path:line: message`.
+#[derive(Debug)]
+struct CsvError {
+    path: &'static str,
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path, self.line, self.message)
+    }
+}
+
+/// Split one CSV line into cells. Cells may be quoted with `"`, with `""`
+/// for a literal quote; quoted cells can't span lines.
+fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            ',' if !quoted => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted cell".to_string());
+    }
+    cells.push(cell.trim().to_string());
+    Ok(cells)
+}
+
+/// A table row type built from CSV cells, one per column after the case name.
+///
+/// Implemented for tuples of up to six values whose types implement
+/// [`FromStr`].
+pub trait CsvRow: Sized {
+    /// How many cells a row has.
+    const COLUMNS: usize;
+
+    /// Parse the cells of one row, or explain which one is invalid.
+    fn from_cells(cells: &[&str]) -> Result<Self, String>;
+}
+
+/// Parse cell `index` (0-based, after the case name) as a `T`.
+fn cell<T: FromStr>(cells: &[&str], index: usize) -> Result<T, String>
+where
+    T::Err: fmt::Display,
+{
+    let text = cells[index];
+    text.parse().map_err(|e| {
+        format!(
+            "column {}: cannot parse {text:?} as {}: {e}",
+            index + 2,
+            std::any::type_name::<T>()
+        )
+    })
+}
+
+macro_rules! csv_row_tuple {
+    ($count:expr; $($name:ident $index:tt),+) => {
+        impl<$($name: FromStr),+> CsvRow for ($($name,)+)
+        where
+            $($name::Err: fmt::Display),+
+        {
+            const COLUMNS: usize = $count;
+
+            fn from_cells(cells: &[&str]) -> Result<Self, String> {
+                Ok(($(cell::<$name>(cells, $index)?,)+))
+            }
+        }
+    };
+}
+
+csv_row_tuple!(1; A 0);
+csv_row_tuple!(2; A 0, B 1);
+csv_row_tuple!(3; A 0, B 1, C 2);
+csv_row_tuple!(4; A 0, B 1, C 2, D 3);
+csv_row_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+csv_row_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Embed a CSV file, relative to the crate root (`CARGO_MANIFEST_DIR`), for
+/// [`TableBuilder::csv`](crate::table::TableBuilder::csv).
+///
+/// ```rust,ignore
+/// ctx.describe_table("pricing")
+///     .csv(rsspec::include_csv!("tests/data/pricing.csv"))
+///     .run(|(quantity, total): &(u32, f64)| assert_eq!(price(*quantity), *total));
+/// ```
+#[macro_export]
+macro_rules! include_csv {
+    ($path:literal $(,)?) => {
+        $crate::CsvSource::new(
+            $path,
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(text: &'static str) -> CsvSource {
+        CsvSource::new("data.csv", text)
+    }
+
+    #[test]
+    fn rows_skip_the_header_and_blank_lines() {
+        let csv = source("name,a,b\nsmall, 1, 2\n\n\"big, \"\"quoted\"\"\",100,200\n");
+        let rows: Vec<(String, (i32, u8))> = csv.parse().unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("small".to_string(), (1, 2)),
+                ("big, \"quoted\"".to_string(), (100, 200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_file_line_and_column() {
+        let error = |text| source(text).parse::<(i32, u8)>().unwrap_err().to_string();
+        assert_eq!(
+            error("name,a,b\nok,1,2\nbad,1,300\n"),
+            "data.csv:3: column 3: cannot parse \"300\" as u8: \
+             number too large to fit in target type"
+        );
+        assert_eq!(
+            error("name,a,b\n\nshort,1\n"),
+            "data.csv:3: expected 3 columns, found 2"
+        );
+        assert_eq!(
+            error("name,a\n"),
+            "data.csv:1: header has 2 columns, expected 3 (the case name, then one per value)"
+        );
+        assert_eq!(
+            error("name,a,b\n\"open,1,2\n"),
+            "data.csv:2: unterminated quoted cell"
+        );
+    }
+
+    #[test]
+    fn csv_tables_run_one_test_per_row() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            ctx.describe_table("sums")
+                .csv(source("name,a,b,sum\none,1,1,2\nwrong,2,2,5\n"))
+                .run(|(a, b, sum): &(i32, i32, i32)| assert_eq!(a + b, *sum));
+        });
+        let results: Vec<_> = tree
+            .tests()
+            .iter()
+            .map(|t| (t.path.as_str(), t.status))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("sums > one", crate::TestStatus::Passed),
+                ("sums > wrong", crate::TestStatus::Failed),
+            ]
+        );
+    }
+}
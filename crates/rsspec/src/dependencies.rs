@@ -0,0 +1,259 @@
+//! `depends_on`: resolving dependency names to tests and ordering siblings so
+//! every dependency runs before the tests that need it.
+//!
+//! A name is a test's full `describe > it` path, or its trailing part when
+//! exactly one test ends with it. Names that match no test or several, and
+//! dependencies that loop back on themselves, are reported before the run.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::runner::TestNode;
+
+/// The `depends_on` declarations of one suite, resolved to full paths.
+#[derive(Debug, Default)]
+pub(crate) struct Dependencies {
+    /// Full paths each dependent test needs, keyed by the dependent's path.
+    of: BTreeMap<String, Vec<String>>,
+    /// Unknown or ambiguous names and cycles, one message each.
+    pub(crate) errors: Vec<String>,
+}
+
+impl Dependencies {
+    /// Resolve every `depends_on` in `nodes` against the tests they contain.
+    pub(crate) fn resolve(nodes: &[TestNode]) -> Self {
+        let mut tests = Vec::new();
+        let mut declared = Vec::new();
+        collect(nodes, &[], &mut tests, &mut declared);
+
+        let mut dependencies = Dependencies::default();
+        for (dependent, names) in declared {
+            let mut resolved = Vec::new();
+            for name in names {
+                let suffix = format!("> {name}");
+                let mut matches: Vec<&String> = tests.iter().filter(|t| **t == name).collect();
+                if matches.is_empty() {
+                    matches = tests.iter().filter(|t| t.ends_with(&suffix)).collect();
+                }
+                match matches.as_slice() {
+                    [] => dependencies
+                        .errors
+                        .push(format!("'{dependent}' depends on '{name}', which names no test")),
+                    [path] => resolved.push((*path).clone()),
+                    several => {
+                        let candidates: Vec<&str> = several.iter().map(|p| p.as_str()).collect();
+                        dependencies.errors.push(format!(
+                            "'{dependent}' depends on '{name}', which matches several tests \
+                             ({}); use the full path",
+                            candidates.join(", ")
+                        ));
+                    }
+                }
+            }
+            if !resolved.is_empty() {
+                dependencies.of.entry(dependent).or_default().extend(resolved);
+            }
+        }
+
+        match dependencies.find_cycle() {
+            Some(cycle) => dependencies.errors.push(format!(
+                "depends_on cycle: {}",
+                cycle.iter().map(|p| format!("'{p}'")).collect::<Vec<_>>().join(" → ")
+            )),
+            None => dependencies.check_sibling_order(nodes, &[]),
+        }
+        dependencies
+    }
+
+    /// Whether no test declares a dependency.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.of.is_empty()
+    }
+
+    /// Full paths of the tests `path` depends on.
+    pub(crate) fn of(&self, path: &str) -> &[String] {
+        self.of.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// Reorder `order`, indices of the siblings `nodes` under `path`, so that
+    /// a sibling holding a dependency runs before the siblings holding its
+    /// dependents. Otherwise `order` is kept; siblings caught in a cycle
+    /// (already reported by [`resolve`](Self::resolve)) keep it too.
+    pub(crate) fn order(
+        &self,
+        nodes: &[TestNode],
+        path: &[String],
+        order: Vec<usize>,
+    ) -> Vec<usize> {
+        let before = self.sibling_edges(nodes, path);
+        if before.iter().all(BTreeSet::is_empty) {
+            return order;
+        }
+        let mut placed = vec![false; nodes.len()];
+        let mut sorted = Vec::with_capacity(order.len());
+        while sorted.len() < order.len() {
+            let ready = order
+                .iter()
+                .copied()
+                .find(|&i| !placed[i] && before[i].iter().all(|&j| placed[j]));
+            let Some(i) = ready else {
+                sorted.extend(order.iter().copied().filter(|&i| !placed[i]));
+                break;
+            };
+            placed[i] = true;
+            sorted.push(i);
+        }
+        sorted
+    }
+
+    /// Split `order`, the top-level siblings `nodes`, into groups linked by
+    /// dependencies, so `--jobs` runs each group in one worker. Groups come
+    /// in the order of their first member and keep `order` within.
+    pub(crate) fn groups(&self, nodes: &[TestNode], order: &[usize]) -> Vec<Vec<usize>> {
+        let before = self.sibling_edges(nodes, &[]);
+        // Union-find over the siblings: each points towards its group's root.
+        let mut group: Vec<usize> = (0..nodes.len()).collect();
+        fn root(group: &mut [usize], mut i: usize) -> usize {
+            while group[i] != i {
+                group[i] = group[group[i]];
+                i = group[i];
+            }
+            i
+        }
+        for (i, needs) in before.iter().enumerate() {
+            for &j in needs {
+                let (a, b) = (root(&mut group, i), root(&mut group, j));
+                group[a.max(b)] = a.min(b);
+            }
+        }
+        let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+        for &i in order {
+            let leader = root(&mut group, i);
+            match groups.iter_mut().find(|(l, _)| *l == leader) {
+                Some((_, members)) => members.push(i),
+                None => groups.push((leader, vec![i])),
+            }
+        }
+        groups.into_iter().map(|(_, members)| members).collect()
+    }
+
+    /// For each of the siblings `nodes` under `path`, the other siblings
+    /// whose tests one of its tests depends on.
+    fn sibling_edges(&self, nodes: &[TestNode], path: &[String]) -> Vec<BTreeSet<usize>> {
+        let mut before = vec![BTreeSet::new(); nodes.len()];
+        if self.of.is_empty() || nodes.len() < 2 {
+            return before;
+        }
+        // Which sibling each test under `path` belongs to.
+        let mut owner: BTreeMap<String, usize> = BTreeMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let mut tests = Vec::new();
+            collect(std::slice::from_ref(node), path, &mut tests, &mut Vec::new());
+            owner.extend(tests.into_iter().map(|t| (t, i)));
+        }
+        for (dependent, needs) in &self.of {
+            let Some(&i) = owner.get(dependent) else { continue };
+            for need in needs {
+                match owner.get(need) {
+                    Some(&j) if j != i => {
+                        before[i].insert(j);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        before
+    }
+
+    /// A chain of tests that ends where it started, if there is one.
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        fn visit<'a>(
+            deps: &'a Dependencies,
+            path: &'a str,
+            stack: &mut Vec<&'a str>,
+            done: &mut BTreeSet<&'a str>,
+        ) -> Option<Vec<String>> {
+            if let Some(start) = stack.iter().position(|p| *p == path) {
+                let mut cycle: Vec<String> = stack[start..].iter().map(|p| p.to_string()).collect();
+                cycle.push(path.to_string());
+                return Some(cycle);
+            }
+            if !done.insert(path) {
+                return None;
+            }
+            stack.push(path);
+            for need in deps.of(path) {
+                if let Some(cycle) = visit(deps, need, stack, done) {
+                    return Some(cycle);
+                }
+            }
+            stack.pop();
+            None
+        }
+        let mut done = BTreeSet::new();
+        self.of.keys().find_map(|path| visit(self, path, &mut Vec::new(), &mut done))
+    }
+
+    /// Report siblings that each hold a dependency of the other, which no
+    /// order of the two can satisfy even though the tests form no cycle.
+    fn check_sibling_order(&mut self, nodes: &[TestNode], path: &[String]) {
+        let before = self.sibling_edges(nodes, path);
+        let order = self.order(nodes, path, (0..nodes.len()).collect());
+        let mut seen = vec![false; nodes.len()];
+        for &i in &order {
+            if let Some(&j) = before[i].iter().find(|&&j| !seen[j]) {
+                let name = |k: usize| {
+                    let mut p = path.to_vec();
+                    p.push(node_name(&nodes[k]).to_string());
+                    p.join(" > ")
+                };
+                self.errors.push(format!(
+                    "'{}' and '{}' each hold a dependency of the other, so neither can run first",
+                    name(j),
+                    name(i)
+                ));
+                return;
+            }
+            seen[i] = true;
+        }
+        for node in nodes {
+            if let TestNode::Describe { name, children, .. } = node {
+                let mut child_path = path.to_vec();
+                child_path.push(name.clone());
+                self.check_sibling_order(children, &child_path);
+            }
+        }
+    }
+}
+
+fn node_name(node: &TestNode) -> &str {
+    match node {
+        TestNode::Describe { name, .. }
+        | TestNode::It { name, .. }
+        | TestNode::Ordered { name, .. } => name,
+    }
+}
+
+/// Collect the full path of every test in `nodes`, and each test's
+/// `depends_on` names.
+fn collect(
+    nodes: &[TestNode],
+    path: &[String],
+    tests: &mut Vec<String>,
+    declared: &mut Vec<(String, Vec<String>)>,
+) {
+    for node in nodes {
+        let mut node_path = path.to_vec();
+        node_path.push(node_name(node).to_string());
+        match node {
+            TestNode::Describe { children, .. } => collect(children, &node_path, tests, declared),
+            TestNode::It { depends_on, .. } => {
+                let full_path = node_path.join(" > ");
+                if !depends_on.is_empty() {
+                    declared.push((full_path.clone(), depends_on.clone()));
+                }
+                tests.push(full_path);
+            }
+            TestNode::Ordered { .. } => tests.push(node_path.join(" > ")),
+        }
+    }
+}
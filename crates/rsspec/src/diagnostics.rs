@@ -0,0 +1,100 @@
+//! Diagnostics sink — where in-test progress notes are written.
+//!
+//! Retry notices, `by()` steps, tolerated ordered-step failures and hook
+//! warnings are written here rather than straight to stderr, so they can be
+//! captured or silenced without touching the runner's stdout tree. The
+//! default sink is stderr; `--no-diagnostics` disables it.
+
+use std::io::Write;
+use std::sync::Mutex;
+
+enum Sink {
+    Stderr,
+    Disabled,
+    Writer(Box<dyn Write + Send>),
+}
+
+static SINK: Mutex<Sink> = Mutex::new(Sink::Stderr);
+
+fn replace(sink: Sink) {
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/// Send diagnostics to `writer` instead of stderr.
+pub fn set_sink(writer: impl Write + Send + 'static) {
+    replace(Sink::Writer(Box::new(writer)));
+}
+
+/// Discard all diagnostics. Equivalent to `--no-diagnostics`.
+pub fn disable() {
+    replace(Sink::Disabled);
+}
+
+/// Restore the default sink (stderr).
+pub fn reset() {
+    replace(Sink::Stderr);
+}
+
+/// Write one diagnostic line to the current sink. Write errors are ignored.
+pub(crate) fn emit(args: std::fmt::Arguments<'_>) {
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    match &mut *sink {
+        Sink::Stderr => eprintln!("{args}"),
+        Sink::Disabled => {}
+        Sink::Writer(w) => {
+            let _ = writeln!(w, "{args}");
+        }
+    }
+}
+
+/// `eprintln!`-style shorthand for [`emit`].
+macro_rules! diag {
+    ($($arg:tt)*) => {
+        $crate::diagnostics::emit(format_args!($($arg)*))
+    };
+}
+
+pub(crate) use diag;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuf {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    /// Serializes the tests that swap the global sink.
+    static SINK_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn sink_can_be_redirected_and_disabled() {
+        let _lock = SINK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let buf = SharedBuf::default();
+        set_sink(buf.clone());
+        crate::by("log in");
+        disable();
+        crate::by("hidden");
+        reset();
+
+        let out = buf.contents();
+        assert!(out.contains("  STEP: log in\n"));
+        assert!(!out.contains("hidden"));
+    }
+}
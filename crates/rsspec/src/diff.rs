@@ -0,0 +1,255 @@
+//! [`compare!`](crate::compare): an equality assertion that fails with a
+//! line diff of the two values' pretty `Debug` output, so a mismatch deep in
+//! a large struct shows only what changed.
+//!
+//! The diff is a plain longest-common-subsequence over lines, after the
+//! common prefix and suffix are set aside.
+
+use std::fmt::Debug;
+
+use crate::style::{use_color, Style};
+
+/// Unchanged lines kept around each change; longer runs are elided.
+const CONTEXT: usize = 3;
+
+/// Past this many line pairs (after trimming), skip the LCS table and show
+/// the differing middle as removed, then added.
+const MAX_TABLE: usize = 4_000_000;
+
+/// Fail the current test if `actual != expected`, with a diff of their
+/// `{:#?}` output: `-` lines are expected only, `+` lines actual only.
+/// Prefer the [`compare!`](crate::compare) macro.
+#[track_caller]
+pub fn compare<T: Debug + PartialEq + ?Sized>(actual: &T, expected: &T) {
+    crate::record_assertion();
+    if actual != expected {
+        let (actual, expected) = (format!("{actual:#?}"), format!("{expected:#?}"));
+        panic!("{}", mismatch(&actual, &expected, use_color()));
+    }
+}
+
+/// Assert that two values are equal, failing with a colored line diff of
+/// their `Debug` output (plain with `NO_COLOR` or off a terminal).
+///
+/// ```rust
+/// #[derive(Debug, PartialEq)]
+/// struct User { name: &'static str, admin: bool }
+///
+/// rsspec::compare!(User { name: "ada", admin: true }, User { name: "ada", admin: true });
+/// ```
+///
+/// A mismatch fails with:
+///
+/// ```text
+/// values differ (- expected, + actual):
+///   User {
+///       name: "ada",
+/// -     admin: true,
+/// +     admin: false,
+///   }
+/// ```
+#[macro_export]
+macro_rules! compare {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::compare(&$actual, &$expected)
+    };
+}
+
+/// The failure message for two differing `Debug` renderings.
+pub(crate) fn mismatch(actual: &str, expected: &str, color: bool) -> String {
+    let paint = |style: Style, text: String| if color { style.wrap(&text) } else { text };
+    let mut out = String::from("values differ (- expected, + actual):");
+    if actual.lines().eq(expected.lines()) {
+        // Equal renderings of unequal values (e.g. NaN, custom `PartialEq`).
+        out.push_str("\n  (same Debug output)");
+    }
+    for line in elide(&diff_lines(expected, actual)) {
+        out.push('\n');
+        out.push_str(&match line {
+            Shown::Line(Line::Same(text)) => format!("  {text}"),
+            Shown::Line(Line::Removed(text)) => paint(Style::new().red(), format!("- {text}")),
+            Shown::Line(Line::Added(text)) => paint(Style::new().green(), format!("+ {text}")),
+            Shown::Elided(n) => paint(Style::new().dim(), format!("  ... {n} unchanged lines")),
+        });
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Lines turning `old` into `new`.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|l| Line::Same(l)).collect();
+    if a.len().saturating_mul(b.len()) > MAX_TABLE {
+        lines.extend(a.iter().map(|l| Line::Removed(l)));
+        lines.extend(b.iter().map(|l| Line::Added(l)));
+    } else {
+        lines.extend(lcs_diff(a, b));
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|l| Line::Same(l)));
+    lines
+}
+
+fn lcs_diff<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<Line<'a>> {
+    // table[i][j]: length of the LCS of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut table = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i * width + j] = if a[i] == b[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(Line::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            lines.push(Line::Removed(a[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|l| Line::Removed(l)));
+    lines.extend(b[j..].iter().map(|l| Line::Added(l)));
+    lines
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Shown<'a> {
+    Line(Line<'a>),
+    /// This many unchanged lines, left out.
+    Elided(usize),
+}
+
+/// Keep changes and up to [`CONTEXT`] unchanged lines around each. A single
+/// unchanged line between them is kept rather than elided.
+fn elide<'a>(lines: &[Line<'a>]) -> Vec<Shown<'a>> {
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&i| !matches!(lines[i], Line::Same(_)))
+        .collect();
+    let near_change = |i: usize| {
+        changed
+            .iter()
+            .any(|&c| c.saturating_sub(CONTEXT) <= i && i <= c + CONTEXT)
+    };
+
+    let mut shown = Vec::new();
+    let mut skipped: Vec<Line> = Vec::new();
+    let flush = |shown: &mut Vec<Shown<'a>>, skipped: &mut Vec<Line<'a>>| match skipped.len() {
+        0 => {}
+        1 => shown.push(Shown::Line(skipped.remove(0))),
+        n => {
+            shown.push(Shown::Elided(n));
+            skipped.clear();
+        }
+    };
+    for (i, &line) in lines.iter().enumerate() {
+        if near_change(i) {
+            flush(&mut shown, &mut skipped);
+            shown.push(Shown::Line(line));
+        } else {
+            skipped.push(line);
+        }
+    }
+    flush(&mut shown, &mut skipped);
+    shown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_keeps_common_lines_and_marks_changes() {
+        assert_eq!(
+            diff_lines("a\nb\nc\nd", "a\nc\nx\nd"),
+            [
+                Line::Same("a"),
+                Line::Removed("b"),
+                Line::Same("c"),
+                Line::Added("x"),
+                Line::Same("d"),
+            ]
+        );
+        assert_eq!(diff_lines("", "a"), [Line::Added("a")]);
+    }
+
+    #[test]
+    fn long_unchanged_runs_are_elided() {
+        let old: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut new = old.clone();
+        new[10] = "ten".to_string();
+        let (old, new) = (old.join("\n"), new.join("\n"));
+        let shown = elide(&diff_lines(&old, &new));
+        assert_eq!(shown.first(), Some(&Shown::Elided(7)));
+        let changes = [Shown::Line(Line::Removed("10")), Shown::Line(Line::Added("ten"))];
+        assert_eq!(shown[4..6], changes);
+        assert_eq!(shown.last(), Some(&Shown::Elided(6)));
+        assert_eq!(shown.len(), 1 + CONTEXT + 2 + CONTEXT + 1);
+
+        let (old, new) = ("a\nb\nc\nd\ne", "a\nb\nc\nd\nE");
+        assert_eq!(elide(&diff_lines(old, new))[0], Shown::Line(Line::Same("a")));
+    }
+
+    #[test]
+    fn changed_lines_are_colored_when_color_is_on() {
+        assert_eq!(
+            mismatch("1", "2", true),
+            "values differ (- expected, + actual):\n\x1b[31m- 2\x1b[0m\n\x1b[32m+ 1\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn compare_fails_with_a_diff_of_the_debug_output() {
+        #[derive(Debug, PartialEq)]
+        struct User {
+            name: &'static str,
+            admin: bool,
+        }
+
+        compare(&User { name: "ada", admin: true }, &User { name: "ada", admin: true });
+        let failure = std::panic::catch_unwind(|| {
+            compare!(User { name: "ada", admin: false }, User { name: "ada", admin: true });
+        });
+        assert!(failure.is_err());
+        let (actual, expected) = (
+            format!("{:#?}", User { name: "ada", admin: false }),
+            format!("{:#?}", User { name: "ada", admin: true }),
+        );
+        assert_eq!(
+            mismatch(&actual, &expected, false),
+            "values differ (- expected, + actual):
+  User {
+      name: \"ada\",
+-     admin: true,
++     admin: false,
+  }"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Fluent assertions without extra dependencies: `expect(value).to_...()`.
+//!
+//! Each check panics with a message naming the actual and expected values,
+//! which the runner reports under the failing test, and returns the
+//! expectation so checks can be chained. Checks count as assertions for
+//! `RSSPEC_WARN_NO_ASSERT`.
+//!
+//! ```rust
+//! use rsspec::expect;
+//!
+//! expect(2 + 2).to_equal(4).to_be_greater_than(3).to_be_at_most(4);
+//! expect(vec!["ada", "grace"]).to_contain("ada");
+//! expect("hello world").to_contain("world");
+//! expect("42".parse::<u32>()).to_be_ok();
+//! ```
+//!
+//! For matchers beyond these, enable the `googletest` feature.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Start an expectation about `actual`. See the [module docs](self).
+pub fn expect<T>(actual: T) -> Expectation<T> {
+    Expectation { actual }
+}
+
+/// A value under test, returned by [`expect`].
+pub struct Expectation<T> {
+    actual: T,
+}
+
+impl<T> Expectation<T> {
+    /// The value under test, for checks this module doesn't cover.
+    pub fn into_inner(self) -> T {
+        self.actual
+    }
+
+    #[track_caller]
+    fn check(self, passed: bool, failure: impl FnOnce(&T) -> String) -> Self {
+        crate::record_assertion();
+        if !passed {
+            panic!("{}", failure(&self.actual));
+        }
+        self
+    }
+}
+
+impl<T: Debug> Expectation<T> {
+    /// Fail unless the value equals `expected`. Multi-line values fail with a
+    /// line diff, as with [`compare!`](crate::compare).
+    #[track_caller]
+    pub fn to_equal<U: Debug>(self, expected: U) -> Self
+    where
+        T: PartialEq<U>,
+    {
+        let passed = self.actual == expected;
+        self.check(passed, |actual| {
+            let (actual, expected) = (format!("{actual:#?}"), format!("{expected:#?}"));
+            if actual.contains('\n') || expected.contains('\n') {
+                crate::diff::mismatch(&actual, &expected, crate::style::use_color())
+            } else {
+                format!("expected {actual} to equal {expected}")
+            }
+        })
+    }
+
+    /// Fail if the value equals `unexpected`.
+    #[track_caller]
+    pub fn to_not_equal<U: Debug>(self, unexpected: U) -> Self
+    where
+        T: PartialEq<U>,
+    {
+        let passed = self.actual != unexpected;
+        self.check(passed, |actual| format!("expected {actual:?} to not equal {unexpected:?}"))
+    }
+
+    /// Fail unless the value contains `item`: an element of a collection, or
+    /// a substring or `char` of a string.
+    #[track_caller]
+    pub fn to_contain<I: Debug>(self, item: I) -> Self
+    where
+        T: Contains<I>,
+    {
+        let passed = self.actual.contains_item(&item);
+        self.check(passed, |actual| format!("expected {actual:?} to contain {item:?}"))
+    }
+
+    /// Fail if the value contains `item` (see [`to_contain`](Self::to_contain)).
+    #[track_caller]
+    pub fn to_not_contain<I: Debug>(self, item: I) -> Self
+    where
+        T: Contains<I>,
+    {
+        let passed = !self.actual.contains_item(&item);
+        self.check(passed, |actual| format!("expected {actual:?} to not contain {item:?}"))
+    }
+}
+
+impl<T: PartialOrd + Debug> Expectation<T> {
+    /// Fail unless the value is greater than `bound`.
+    #[track_caller]
+    pub fn to_be_greater_than(self, bound: T) -> Self {
+        let passed = self.actual > bound;
+        self.check(passed, |actual| format!("expected {actual:?} to be greater than {bound:?}"))
+    }
+
+    /// Fail unless the value is less than `bound`.
+    #[track_caller]
+    pub fn to_be_less_than(self, bound: T) -> Self {
+        let passed = self.actual < bound;
+        self.check(passed, |actual| format!("expected {actual:?} to be less than {bound:?}"))
+    }
+
+    /// Fail unless the value is greater than or equal to `bound`.
+    #[track_caller]
+    pub fn to_be_at_least(self, bound: T) -> Self {
+        let passed = self.actual >= bound;
+        self.check(passed, |actual| format!("expected {actual:?} to be at least {bound:?}"))
+    }
+
+    /// Fail unless the value is less than or equal to `bound`.
+    #[track_caller]
+    pub fn to_be_at_most(self, bound: T) -> Self {
+        let passed = self.actual <= bound;
+        self.check(passed, |actual| format!("expected {actual:?} to be at most {bound:?}"))
+    }
+}
+
+impl Expectation<bool> {
+    /// Fail unless the value is `true`.
+    #[track_caller]
+    pub fn to_be_true(self) -> Self {
+        let passed = self.actual;
+        self.check(passed, |_| "expected true, got false".to_string())
+    }
+
+    /// Fail unless the value is `false`.
+    #[track_caller]
+    pub fn to_be_false(self) -> Self {
+        let passed = !self.actual;
+        self.check(passed, |_| "expected false, got true".to_string())
+    }
+}
+
+impl<V: Debug> Expectation<Option<V>> {
+    /// Fail unless the value is `Some`.
+    #[track_caller]
+    pub fn to_be_some(self) -> Self {
+        let passed = self.actual.is_some();
+        self.check(passed, |_| "expected Some(_), got None".to_string())
+    }
+
+    /// Fail unless the value is `None`.
+    #[track_caller]
+    pub fn to_be_none(self) -> Self {
+        let passed = self.actual.is_none();
+        self.check(passed, |actual| format!("expected None, got {actual:?}"))
+    }
+}
+
+impl<V: Debug, E: Debug> Expectation<Result<V, E>> {
+    /// Fail unless the value is `Ok`.
+    #[track_caller]
+    pub fn to_be_ok(self) -> Self {
+        let passed = self.actual.is_ok();
+        self.check(passed, |actual| format!("expected Ok(_), got {actual:?}"))
+    }
+
+    /// Fail unless the value is `Err`.
+    #[track_caller]
+    pub fn to_be_err(self) -> Self {
+        let passed = self.actual.is_err();
+        self.check(passed, |actual| format!("expected Err(_), got {actual:?}"))
+    }
+}
+
+/// Values [`Expectation::to_contain`] can look inside: collections by
+/// element, strings by substring or `char`, and references to either.
+pub trait Contains<I> {
+    /// Whether `item` is in `self`.
+    fn contains_item(&self, item: &I) -> bool;
+}
+
+impl<I, C: Contains<I> + ?Sized> Contains<I> for &C {
+    fn contains_item(&self, item: &I) -> bool {
+        (**self).contains_item(item)
+    }
+}
+
+impl<E: PartialEq> Contains<E> for [E] {
+    fn contains_item(&self, item: &E) -> bool {
+        self.contains(item)
+    }
+}
+
+impl<E: PartialEq, const N: usize> Contains<E> for [E; N] {
+    fn contains_item(&self, item: &E) -> bool {
+        self.contains(item)
+    }
+}
+
+impl<E: PartialEq> Contains<E> for Vec<E> {
+    fn contains_item(&self, item: &E) -> bool {
+        self.contains(item)
+    }
+}
+
+impl<E: PartialEq> Contains<E> for VecDeque<E> {
+    fn contains_item(&self, item: &E) -> bool {
+        self.contains(item)
+    }
+}
+
+impl<E: Eq + Hash> Contains<E> for HashSet<E> {
+    fn contains_item(&self, item: &E) -> bool {
+        self.contains(item)
+    }
+}
+
+impl<E: Ord> Contains<E> for BTreeSet<E> {
+    fn contains_item(&self, item: &E) -> bool {
+        self.contains(item)
+    }
+}
+
+impl Contains<&str> for str {
+    fn contains_item(&self, item: &&str) -> bool {
+        self.contains(*item)
+    }
+}
+
+impl Contains<char> for str {
+    fn contains_item(&self, item: &char) -> bool {
+        self.contains(*item)
+    }
+}
+
+impl Contains<&str> for String {
+    fn contains_item(&self, item: &&str) -> bool {
+        self.contains(*item)
+    }
+}
+
+impl Contains<char> for String {
+    fn contains_item(&self, item: &char) -> bool {
+        self.contains(*item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(check: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let payload = std::panic::catch_unwind(check).unwrap_err();
+        crate::runner::panic_message(&*payload)
+    }
+
+    #[test]
+    fn passing_checks_chain() {
+        crate::take_assertion_count();
+        expect(5).to_equal(5).to_not_equal(4).to_be_greater_than(4).to_be_at_most(5);
+        expect(vec![1, 2, 3]).to_contain(2).to_not_contain(7);
+        expect(&["a", "b"][..]).to_contain("b");
+        expect(String::from("hello")).to_contain("ell").to_contain('o');
+        expect(Some(1)).to_be_some();
+        expect(Err::<(), _>("bad")).to_be_err();
+        expect(1.5 < 2.0).to_be_true();
+        assert_eq!(crate::take_assertion_count(), 12);
+    }
+
+    #[test]
+    fn failures_name_both_values() {
+        assert_eq!(failure(|| { expect(3).to_equal(4); }), "expected 3 to equal 4");
+        assert_eq!(
+            failure(|| { expect(vec![1, 2]).to_contain(3); }),
+            "expected [1, 2] to contain 3"
+        );
+        assert_eq!(
+            failure(|| { expect("abc").to_contain("z"); }),
+            "expected \"abc\" to contain \"z\""
+        );
+        assert_eq!(
+            failure(|| { expect(2).to_be_greater_than(2); }),
+            "expected 2 to be greater than 2"
+        );
+        assert_eq!(failure(|| { expect(Some(4)).to_be_none(); }), "expected None, got Some(4)");
+        assert_eq!(
+            failure(|| { expect("x".parse::<u8>()).to_be_ok(); }),
+            "expected Ok(_), got Err(ParseIntError { kind: InvalidDigit })"
+        );
+        assert_eq!(failure(|| { expect(false).to_be_true(); }), "expected true, got false");
+    }
+
+    #[test]
+    fn multi_line_values_fail_with_a_diff() {
+        let message = failure(|| { expect(vec![1, 2]).to_equal(vec![1, 3]); });
+        assert!(message.starts_with("values differ (- expected, + actual):"), "{message}");
+        assert!(message.contains("-     3,") && message.contains("+     2,"), "{message}");
+    }
+}
@@ -0,0 +1,456 @@
+//! Label filters, as used by `RSSPEC_LABEL_FILTER`.
+//!
+//! Use this module to select tests by label in your own harness with the
+//! same rules as rsspec's runner. [`parse_label_filter`] validates a filter
+//! once and returns a [`LabelFilter`] to match many label sets without
+//! reparsing; [`labels_match_filter`] and [`check_labels`] are one-shot
+//! shorthands.
+//!
+//! # Grammar
+//!
+//! The filter syntax is stable:
+//!
+//! - `integration` — an exact label
+//! - `team:*`, `tier:?` — a term with `*` (any run of characters) or `?`
+//!   (one character) is a glob matched against each label
+//! - `/tier:\d+/` — a term wrapped in slashes is a regex that must match a
+//!   whole label (requires the `regex` feature); operators, spaces and
+//!   parentheses inside it are part of the pattern
+//! - `not a`, `!a` — negation
+//! - `a and b`, `a+b` — all must match
+//! - `a or b` — any must match
+//! - `a,b` — a list: at least one of its plain items must match (if it has
+//!   any), and none of its negated items. `integration,!slow` means
+//!   "integration, but not slow", while `integration or not slow` also
+//!   selects every test without `slow`
+//! - `(...)` — grouping
+//!
+//! Negation binds tightest, then `and`/`+`, then `or`/`,`. Keywords are
+//! case-insensitive, and reserved: a label named `and`, `or` or `not` can't
+//! be selected. Mixing `+` and `,` in one group, or `or` and `,`, is
+//! rejected as ambiguous; group with parentheses instead, as in
+//! `(integration,smoke)+!slow`.
+//!
+//! ```rust
+//! let filter = rsspec::filter::parse_label_filter("(integration or smoke) and not slow")?;
+//! assert!(filter.matches(&["integration"]));
+//! assert!(!filter.matches(&["smoke", "slow"]));
+//!
+//! assert!(rsspec::filter::parse_label_filter("a+b,c").is_err());
+//! # Ok::<(), rsspec::filter::FilterError>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed label filter, see the [module docs](self) for the grammar.
+#[derive(Debug)]
+pub struct LabelFilter {
+    source: String,
+    expr: Expr,
+}
+
+impl LabelFilter {
+    /// Whether a test with `labels` is selected by this filter.
+    pub fn matches(&self, labels: &[&str]) -> bool {
+        self.expr.matches(labels)
+    }
+
+    /// The filter as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for LabelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for LabelFilter {
+    type Err = FilterError;
+
+    fn from_str(filter: &str) -> Result<Self, FilterError> {
+        parse_label_filter(filter)
+    }
+}
+
+/// Why a label filter couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilterError(String);
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// Parse and validate a label filter.
+///
+/// Fails on malformed expressions, invalid regexes, and regex terms when the
+/// `regex` feature is off.
+pub fn parse_label_filter(filter: &str) -> Result<LabelFilter, FilterError> {
+    let mut parser = Parser {
+        tokens: tokenize(filter)?,
+        pos: 0,
+        shorthands: Vec::new(),
+    };
+    let expr = parser.group()?;
+    match parser.next() {
+        None => Ok(LabelFilter {
+            source: filter.to_string(),
+            expr,
+        }),
+        Some(Token::Close) => Err(FilterError("unmatched ')'".to_string())),
+        Some(token) => Err(FilterError(format!("unexpected {token}"))),
+    }
+}
+
+/// Whether `labels` match `filter`. A malformed filter prints its error to
+/// stderr and matches nothing.
+pub fn labels_match_filter(labels: &[&str], filter: &str) -> bool {
+    match parse_label_filter(filter) {
+        Ok(filter) => filter.matches(labels),
+        Err(e) => {
+            eprintln!("rsspec: invalid label filter '{filter}' — {e}");
+            false
+        }
+    }
+}
+
+/// Whether `labels` match the `RSSPEC_LABEL_FILTER` env var, like
+/// [`labels_match_filter`]. Returns `true` if no filter is set.
+pub fn check_labels(labels: &[&str]) -> bool {
+    match std::env::var("RSSPEC_LABEL_FILTER") {
+        Ok(filter) if !filter.is_empty() => labels_match_filter(labels, &filter),
+        _ => true,
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    /// A `,` list: positive items OR-ed, negated items as exclusions.
+    List(Vec<Expr>),
+}
+
+impl Expr {
+    fn matches(&self, labels: &[&str]) -> bool {
+        match self {
+            Expr::Term(term) => labels.iter().any(|label| term.matches(label)),
+            Expr::Not(inner) => !inner.matches(labels),
+            Expr::And(items) => items.iter().all(|e| e.matches(labels)),
+            Expr::Or(items) => items.iter().any(|e| e.matches(labels)),
+            Expr::List(items) => {
+                let (exclusions, positives): (Vec<_>, Vec<_>) =
+                    items.iter().partition(|e| matches!(e, Expr::Not(_)));
+                exclusions.iter().all(|e| e.matches(labels))
+                    && (positives.is_empty() || positives.iter().any(|e| e.matches(labels)))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Term {
+    Exact(String),
+    Glob(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Term {
+    fn new(term: &str) -> Result<Self, FilterError> {
+        if let Some(pattern) = term.strip_prefix('/').and_then(|t| t.strip_suffix('/')) {
+            return Self::regex(pattern);
+        }
+        if term.contains(['*', '?']) {
+            return Ok(Term::Glob(term.to_string()));
+        }
+        Ok(Term::Exact(term.to_string()))
+    }
+
+    #[cfg(feature = "regex")]
+    fn regex(pattern: &str) -> Result<Self, FilterError> {
+        // Anchored, like exact and glob terms: `/tier:\d/` doesn't match "tier:12".
+        regex::Regex::new(&format!("^(?:{pattern})$"))
+            .map(Term::Regex)
+            .map_err(|e| FilterError(format!("invalid regex '/{pattern}/': {e}")))
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn regex(pattern: &str) -> Result<Self, FilterError> {
+        Err(FilterError(format!(
+            "regex term '/{pattern}/' needs the `regex` feature of rsspec"
+        )))
+    }
+
+    fn matches(&self, label: &str) -> bool {
+        match self {
+            Term::Exact(term) => term == label,
+            Term::Glob(glob) => glob_matches(glob, label),
+            #[cfg(feature = "regex")]
+            Term::Regex(re) => re.is_match(label),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Open,
+    Close,
+    /// `and` or `+`; the flag is set for the `+` shorthand.
+    And(bool),
+    /// `or` or `,`; the flag is set for the `,` shorthand.
+    Or(bool),
+    Not,
+    Term(&'a str),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+            Token::And(true) => f.write_str("'+'"),
+            Token::And(false) => f.write_str("'and'"),
+            Token::Or(true) => f.write_str("','"),
+            Token::Or(false) => f.write_str("'or'"),
+            Token::Not => f.write_str("'not'"),
+            Token::Term(term) => write!(f, "label '{term}'"),
+        }
+    }
+}
+
+/// Split a filter into tokens. A term starting with `/` runs to the next
+/// `/`, so a regex may contain operators, parentheses and spaces.
+fn tokenize(filter: &str) -> Result<Vec<Token<'_>>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut rest = filter;
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = match c {
+            c if c.is_whitespace() => (None, c.len_utf8()),
+            '(' => (Some(Token::Open), 1),
+            ')' => (Some(Token::Close), 1),
+            '+' => (Some(Token::And(true)), 1),
+            ',' => (Some(Token::Or(true)), 1),
+            '!' => (Some(Token::Not), 1),
+            '/' => {
+                let end = rest[1..]
+                    .find('/')
+                    .ok_or_else(|| FilterError(format!("unterminated regex '{rest}'")))?;
+                (Some(Token::Term(&rest[..end + 2])), end + 2)
+            }
+            _ => {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || "()+,".contains(c))
+                    .unwrap_or(rest.len());
+                let word = &rest[..len];
+                let token = match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And(false),
+                    "or" => Token::Or(false),
+                    "not" => Token::Not,
+                    _ => Token::Term(word),
+                };
+                (Some(token), len)
+            }
+        };
+        tokens.extend(token);
+        rest = &rest[len..];
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    /// Per open group: whether `+` and `,` were used in it.
+    shorthands: Vec<(bool, bool)>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += usize::from(token.is_some());
+        token
+    }
+
+    /// A parenthesized group, or the whole filter.
+    fn group(&mut self) -> Result<Expr, FilterError> {
+        self.shorthands.push((false, false));
+        let expr = self.or()?;
+        if let Some((true, true)) = self.shorthands.pop() {
+            return Err(FilterError(
+                "cannot mix '+' (AND) and ',' (OR). Use one or the other, \
+                 or group with parentheses."
+                    .to_string(),
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut items = vec![self.and()?];
+        let (mut comma, mut keyword) = (false, false);
+        while let Some(Token::Or(shorthand)) = self.peek() {
+            self.next();
+            comma |= shorthand;
+            keyword |= !shorthand;
+            items.push(self.and()?);
+        }
+        if comma && keyword {
+            return Err(FilterError(
+                "cannot mix 'or' and ',' in one group".to_string(),
+            ));
+        }
+        self.note_shorthand(false, comma);
+        Ok(match items.len() {
+            1 => items.remove(0),
+            _ if comma => Expr::List(items),
+            _ => Expr::Or(items),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut items = vec![self.unary()?];
+        let mut plus = false;
+        while let Some(Token::And(shorthand)) = self.peek() {
+            self.next();
+            plus |= shorthand;
+            items.push(self.unary()?);
+        }
+        self.note_shorthand(plus, false);
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::And(items)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.group()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(FilterError("missing ')'".to_string())),
+                }
+            }
+            Some(Token::Term(term)) => Term::new(term).map(Expr::Term),
+            Some(token) => Err(FilterError(format!("expected a label, found {token}"))),
+            None => Err(FilterError("expected a label, found the end".to_string())),
+        }
+    }
+
+    fn note_shorthand(&mut self, plus: bool, comma: bool) {
+        if let Some((p, c)) = self.shorthands.last_mut() {
+            *p |= plus;
+            *c |= comma;
+        }
+    }
+}
+
+/// Match `text` against a glob where `*` is any run of characters and `?`
+/// exactly one.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, t));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character.
+                Some((sg, st)) => {
+                    g = sg + 1;
+                    t = st + 1;
+                    star = Some((sg, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regex_terms_are_single_tokens() {
+        assert_eq!(
+            tokenize(r"/tier:\d+/+fast").unwrap(),
+            [
+                Token::Term(r"/tier:\d+/"),
+                Token::And(true),
+                Token::Term("fast")
+            ]
+        );
+        assert_eq!(
+            tokenize(r"!/a{1,2} (b)/,b").unwrap(),
+            [
+                Token::Not,
+                Token::Term(r"/a{1,2} (b)/"),
+                Token::Or(true),
+                Token::Term("b")
+            ]
+        );
+        assert_eq!(
+            tokenize("team/payments").unwrap(),
+            [Token::Term("team/payments")]
+        );
+    }
+
+    #[test]
+    fn parse_errors_name_the_problem() {
+        let error = |filter| parse_label_filter(filter).unwrap_err().to_string();
+        assert_eq!(
+            error("a+b,c"),
+            "cannot mix '+' (AND) and ',' (OR). Use one or the other, \
+             or group with parentheses."
+        );
+        assert_eq!(error("(a"), "missing ')'");
+        assert_eq!(error("a)"), "unmatched ')'");
+        assert_eq!(error("a and"), "expected a label, found the end");
+        assert_eq!(error("a b"), "unexpected label 'b'");
+    }
+
+    #[test]
+    fn parsed_filters_are_reusable() {
+        let filter: LabelFilter = "team:*+!slow".parse().unwrap();
+        assert_eq!(filter.to_string(), "team:*+!slow");
+        assert!(filter.matches(&["team:search"]));
+        assert!(!filter.matches(&["team:search", "slow"]));
+        assert!(!filter.matches(&[]));
+    }
+
+    #[cfg(not(feature = "regex"))]
+    #[test]
+    fn regex_terms_need_the_feature() {
+        assert_eq!(
+            parse_label_filter("/a/").unwrap_err().to_string(),
+            "regex term '/a/' needs the `regex` feature of rsspec"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Subprocess isolation for tests with a memory limit (`max_rss_mb`).
+//!
+//! On Linux the runner re-executes the test binary to run just that test. The
+//! child caps its own address space with `setrlimit` before building the
+//! suite, so a runaway allocation fails that one test instead of taking the
+//! whole CI job down. Elsewhere the limit is a no-op.
+
+use std::any::Any;
+use std::process::{Command, Stdio};
+
+/// Full path of the test a child process should run.
+const TEST_ENV: &str = "RSSPEC_ISOLATED_TEST";
+/// Memory limit, in megabytes, the child applies to itself.
+const LIMIT_ENV: &str = "RSSPEC_ISOLATED_RSS_MB";
+
+/// Whether memory limits are enforced on this platform.
+pub(crate) const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// If this process was spawned to run a single isolated test, apply its
+/// memory limit and return the test's full path.
+pub(crate) fn child_test() -> Option<String> {
+    let path = std::env::var(TEST_ENV).ok()?;
+    if let Some(mb) = std::env::var(LIMIT_ENV).ok().and_then(|v| v.parse().ok()) {
+        apply_memory_limit(mb);
+    }
+    Some(path)
+}
+
+#[cfg(target_os = "linux")]
+fn apply_memory_limit(mb: u64) {
+    let bytes = mb.saturating_mul(1024 * 1024);
+    let limit = libc::rlimit {
+        rlim_cur: bytes,
+        rlim_max: bytes,
+    };
+    // SAFETY: `limit` is a valid rlimit for the duration of both calls.
+    let failed = unsafe {
+        libc::setrlimit(libc::RLIMIT_AS, &limit) != 0
+            || libc::setrlimit(libc::RLIMIT_DATA, &limit) != 0
+    };
+    if failed {
+        eprintln!("rsspec: could not apply the {mb} MB memory limit");
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_memory_limit(_mb: u64) {}
+
+/// Run the test at `path` in a child process limited to `mb` megabytes.
+///
+/// The child's own failure message is propagated when it finishes normally;
+/// if it aborts (typically a failed allocation), the exit status and last
+/// line of stderr are reported instead.
+pub(crate) fn run_in_child(path: &str, mb: u64) -> Result<(), Box<dyn Any + Send>> {
+    let output = std::env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .env(TEST_ENV, path)
+            .env(LIMIT_ENV, mb.to_string())
+            .env("NO_COLOR", "1")
+            .stdin(Stdio::null())
+            .output()
+    });
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => return Err(Box::new(format!("could not spawn isolated test: {e}"))),
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+
+    // The child prints its failures as "  1. <path>: <message>".
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let prefix = format!("1. {path}: ");
+    if let Some(message) = stdout
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix(&prefix))
+    {
+        return Err(Box::new(message.to_string()));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let last_line = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+    Err(Box::new(format!(
+        "isolated test exited with {} under a {mb} MB memory limit: {last_line}",
+        output.status
+    )))
+}
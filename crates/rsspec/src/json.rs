@@ -0,0 +1,245 @@
+//! JSON event stream for `--output-format json`.
+//!
+//! Instead of the colored tree, the runner prints one JSON object per line:
+//! a `describe_enter` event when a describe starts, one event per test as
+//! it finishes, and a final `summary`. Hand-rolled so there's no `serde`
+//! dependency; every value is a string, a number, a boolean, a list of
+//! strings or `null`, except the lists of objects under `failures` and an
+//! ordered block's `steps`. `--plan-dump` uses the same encoding.
+
+use std::fmt::Write as _;
+
+use crate::reporter::{Reporter, RunSummary};
+use crate::result_tree::TestResult;
+use crate::runner::{PlannedTest, TestStatus};
+
+/// The [`Reporter`] behind `--output-format json`.
+pub(crate) struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn describe_entered(&mut self, path: &str, name: &str) {
+        println!("{}", Object::new("describe_enter").str("path", path).str("name", name).finish());
+    }
+
+    fn it_finished(&mut self, test: &TestResult) {
+        println!("{}", test_event_line(test));
+    }
+
+    fn run_finished(&mut self, summary: &RunSummary) {
+        println!("{}", summary_line(summary));
+    }
+}
+
+fn test_event_line(record: &TestResult) -> String {
+    let kind = match record.status {
+        TestStatus::Passed => "it_pass",
+        TestStatus::Failed => "it_fail",
+        TestStatus::Flaky => "it_flaky",
+        TestStatus::XFailed => "it_xfail",
+        TestStatus::Skipped => "it_skip",
+        TestStatus::Pending => "it_pending",
+    };
+    let message = record.message.as_deref();
+    let (error, reason) = match record.status {
+        TestStatus::Skipped | TestStatus::Pending => (None, message),
+        _ => (message, None),
+    };
+    let mut object = Object::new(kind)
+        .str("path", &record.path)
+        .str("name", &record.name)
+        .num("duration_ms", record.duration.as_millis())
+        .opt_str("error", error);
+    if let Some(reason) = reason {
+        object = object.str("reason", reason);
+    }
+    if !record.ordered_steps.is_empty() {
+        let steps: Vec<String> = record
+            .ordered_steps
+            .iter()
+            .map(|s| {
+                let message = s.message.as_deref().map_or_else(|| "null".to_string(), string);
+                let status = match s.status {
+                    TestStatus::Passed => "pass",
+                    TestStatus::Failed => "fail",
+                    TestStatus::Pending => "pending",
+                    _ => "skip",
+                };
+                format!(
+                    "{{\"name\":{},\"status\":\"{status}\",\"message\":{message}}}",
+                    string(&s.name)
+                )
+            })
+            .collect();
+        let _ = write!(object.0, ",\"steps\":[{}]", steps.join(","));
+    }
+    object.finish()
+}
+
+fn summary_line(result: &RunSummary) -> String {
+    let failures: Vec<String> = result
+        .failures
+        .iter()
+        .map(|f| format!("{{\"path\":{},\"error\":{}}}", string(&f.path), string(&f.message)))
+        .collect();
+    let mut line = Object::new("summary")
+        .num("passed", result.passed)
+        .num("failed", result.failed)
+        .num("flaky", result.flaky)
+        .num("xfailed", result.xfailed)
+        .num("pending", result.pending)
+        .num("skipped", result.skipped)
+        .num("duration_ms", result.duration.as_millis())
+        .0;
+    let _ = write!(line, ",\"failures\":[{}]}}", failures.join(","));
+    line
+}
+
+/// One line of `--plan-dump` output.
+pub(crate) fn plan_line(test: &PlannedTest) -> String {
+    Object::new("plan")
+        .str("path", &test.path)
+        .strs("labels", &test.labels)
+        .bool("pending", test.pending)
+        .finish()
+}
+
+/// A single-line JSON object under construction; always starts with `kind`.
+struct Object(String);
+
+impl Object {
+    fn new(kind: &str) -> Self {
+        Object(format!("{{\"kind\":{}", string(kind)))
+    }
+
+    fn str(mut self, key: &str, value: &str) -> Self {
+        let _ = write!(self.0, ",\"{key}\":{}", string(value));
+        self
+    }
+
+    fn opt_str(mut self, key: &str, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => self.str(key, value),
+            None => {
+                let _ = write!(self.0, ",\"{key}\":null");
+                self
+            }
+        }
+    }
+
+    fn strs(mut self, key: &str, values: &[String]) -> Self {
+        let values: Vec<String> = values.iter().map(|v| string(v)).collect();
+        let _ = write!(self.0, ",\"{key}\":[{}]", values.join(","));
+        self
+    }
+
+    fn bool(mut self, key: &str, value: bool) -> Self {
+        let _ = write!(self.0, ",\"{key}\":{value}");
+        self
+    }
+
+    fn num(mut self, key: &str, value: impl std::fmt::Display) -> Self {
+        let _ = write!(self.0, ",\"{key}\":{value}");
+        self
+    }
+
+    fn finish(mut self) -> String {
+        self.0.push('}');
+        self.0
+    }
+}
+
+/// `text` as a quoted JSON string, which is also a valid YAML
+/// double-quoted scalar.
+pub(crate) fn string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Failure;
+    use std::time::Duration;
+
+    fn record(path: &str, status: TestStatus, message: Option<&str>) -> TestResult {
+        TestResult {
+            name: path.rsplit(" > ").next().unwrap_or_default().to_string(),
+            path: path.to_string(),
+            status,
+            duration: Duration::from_millis(7),
+            attempts: 1,
+            message: message.map(str::to_string),
+            owner: None,
+            attachments: Vec::new(),
+            output: None,
+            steps: 0,
+            assertions: 0,
+            ordered_steps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_events_carry_path_duration_and_error() {
+        assert_eq!(
+            test_event_line(&record("math > adds", TestStatus::Passed, None)),
+            r#"{"kind":"it_pass","path":"math > adds","name":"adds","duration_ms":7,"error":null}"#
+        );
+        assert_eq!(
+            test_event_line(&record("fails", TestStatus::Failed, Some("left: \"a\"\nright: \\"))),
+            r#"{"kind":"it_fail","path":"fails","name":"fails","duration_ms":7,"error":"left: \"a\"\nright: \\"}"#
+        );
+        assert_eq!(
+            test_event_line(&record("db > later", TestStatus::Skipped, Some("no db"))),
+            r#"{"kind":"it_skip","path":"db > later","name":"later","duration_ms":7,"error":null,"reason":"no db"}"#
+        );
+    }
+
+    #[test]
+    fn ordered_blocks_list_their_steps() {
+        let mut test = record("signup", TestStatus::Failed, Some("boom"));
+        let step = |name: &str, status, message: Option<&str>| crate::result_tree::StepResult {
+            name: name.to_string(),
+            status,
+            message: message.map(str::to_string),
+        };
+        test.ordered_steps = vec![
+            step("create", TestStatus::Passed, None),
+            step("verify", TestStatus::Failed, Some("boom")),
+            step("login", TestStatus::Skipped, Some("an earlier step failed")),
+        ];
+        assert_eq!(
+            test_event_line(&test),
+            r#"{"kind":"it_fail","path":"signup","name":"signup","duration_ms":7,"error":"boom","steps":[{"name":"create","status":"pass","message":null},{"name":"verify","status":"fail","message":"boom"},{"name":"login","status":"skip","message":"an earlier step failed"}]}"#
+        );
+    }
+
+    #[test]
+    fn summary_lists_counts_and_failures() {
+        let result = RunSummary {
+            passed: 2,
+            failed: 1,
+            duration: Duration::from_millis(1500),
+            failures: vec![Failure::new("db (before_all)", "no \u{1b}db")],
+            ..RunSummary::default()
+        };
+        assert_eq!(
+            summary_line(&result),
+            r#"{"kind":"summary","passed":2,"failed":1,"flaky":0,"xfailed":0,"pending":0,"skipped":0,"duration_ms":1500,"failures":[{"path":"db (before_all)","error":"no \u001bdb"}]}"#
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! JUnit XML report for `--junit <path>`.
+//!
+//! One `<testsuite>` per suite passed to `run_suites`. A test's describe
+//! path becomes its `classname` (joined by `>`) and its own name the
+//! testcase `name`, which is how GitLab and most CI dashboards group results.
+//! Failed `before_all`/`after_all` hooks are reported as failed testcases
+//! named after the scope, e.g. `db (before_all)`, so the report fails too.
+//! An ordered block's steps are listed in its `<system-out>`.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use crate::runner::{Failure, TestRecord, TestStatus};
+
+/// The tests one suite produced, in run order.
+pub(crate) struct SuiteReport<'a> {
+    pub name: &'a str,
+    pub tests: &'a [TestRecord],
+    /// Failures of the suite's scope hooks, which aren't tests.
+    pub hook_failures: Vec<&'a Failure>,
+}
+
+impl SuiteReport<'_> {
+    /// Testcases in the report: tests plus failed hooks.
+    fn cases(&self) -> usize {
+        self.tests.len() + self.hook_failures.len()
+    }
+
+    fn failures(&self) -> usize {
+        let failed = self.tests.iter().filter(|t| t.status == TestStatus::Failed).count();
+        failed + self.hook_failures.len()
+    }
+}
+
+/// Write the report for `suites` to `path`.
+pub(crate) fn write(path: &str, suites: &[SuiteReport<'_>], elapsed: Duration) -> std::io::Result<()> {
+    std::fs::write(path, render(suites, elapsed))
+}
+
+/// Render `suites` as a JUnit `<testsuites>` document.
+pub(crate) fn render(suites: &[SuiteReport<'_>], elapsed: Duration) -> String {
+    let all = || suites.iter().flat_map(|s| s.tests);
+    let failures: usize = suites.iter().map(SuiteReport::failures).sum();
+    let skipped = all().filter(|t| is_skipped(t.status)).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"rsspec\" tests=\"{}\" failures=\"{failures}\" errors=\"0\" skipped=\"{skipped}\" time=\"{:.3}\">",
+        suites.iter().map(SuiteReport::cases).sum::<usize>(),
+        elapsed.as_secs_f64()
+    );
+    for suite in suites {
+        render_suite(&mut xml, suite);
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn render_suite(xml: &mut String, suite: &SuiteReport<'_>) {
+    let name = if suite.name.is_empty() { "rsspec" } else { suite.name };
+    let failures = suite.failures();
+    let skipped = suite.tests.iter().filter(|t| is_skipped(t.status)).count();
+    let time: Duration = suite.tests.iter().map(|t| t.duration).sum();
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" errors=\"0\" skipped=\"{skipped}\" time=\"{:.3}\">",
+        escape(name),
+        suite.cases(),
+        time.as_secs_f64()
+    );
+
+    for test in suite.tests {
+        let message = test.message.as_deref().unwrap_or_default();
+        let mut body = String::new();
+        match test.status {
+            TestStatus::Passed => {}
+            TestStatus::Failed => failure_element(&mut body, message),
+            status => {
+                // Quarantined outcomes are reported as skipped so they don't
+                // fail the CI job, but keep their reason visible.
+                let reason = match status {
+                    TestStatus::Flaky => format!("flaky: {message}"),
+                    TestStatus::XFailed => format!("xfail: {message}"),
+                    _ => message.to_string(),
+                };
+                if reason.is_empty() {
+                    body.push_str("      <skipped/>\n");
+                } else {
+                    let _ = writeln!(body, "      <skipped message=\"{}\"/>", escape(&reason));
+                }
+            }
+        }
+        if !test.ordered_steps.is_empty() || !test.attachments.is_empty() {
+            body.push_str("      <system-out>\n");
+            let total = test.ordered_steps.len();
+            for (i, step) in test.ordered_steps.iter().enumerate() {
+                let mark = match step.status {
+                    TestStatus::Passed => "✓",
+                    TestStatus::Failed => "✗",
+                    _ => "-",
+                };
+                let line = match &step.message {
+                    Some(message) => format!("{mark} [{}/{total}] {}: {message}", i + 1, step.name),
+                    None => format!("{mark} [{}/{total}] {}", i + 1, step.name),
+                };
+                let _ = writeln!(body, "{}", escape(&line));
+            }
+            // Attachment references in the format of Jenkins' JUnit
+            // Attachments plugin, which GitLab also reads.
+            for attachment in &test.attachments {
+                let path = attachment.path.to_string_lossy();
+                let _ = writeln!(body, "[[ATTACHMENT|{}]]", escape(&path));
+            }
+            body.push_str("</system-out>\n");
+        }
+        testcase(xml, &test.path, test.duration, &body);
+    }
+    for failure in &suite.hook_failures {
+        let mut body = String::new();
+        failure_element(&mut body, &failure.message);
+        testcase(xml, &failure.path, Duration::ZERO, &body);
+    }
+    xml.push_str("  </testsuite>\n");
+}
+
+/// A `<testcase>` for `path`, its describes as the classname, around `body`.
+fn testcase(xml: &mut String, path: &str, duration: Duration, body: &str) {
+    let (classname, name) = match path.rsplit_once(" > ") {
+        Some((describes, name)) => (describes.replace(" > ", ">"), name),
+        None => (String::new(), path),
+    };
+    let _ = write!(
+        xml,
+        "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+        escape(&classname),
+        escape(name),
+        duration.as_secs_f64()
+    );
+    if body.is_empty() {
+        xml.push_str("/>\n");
+    } else {
+        let _ = write!(xml, ">\n{body}    </testcase>\n");
+    }
+}
+
+fn failure_element(body: &mut String, message: &str) {
+    let _ = writeln!(
+        body,
+        "      <failure message=\"{}\">{}</failure>",
+        escape(message.lines().next().unwrap_or_default()),
+        escape(message)
+    );
+}
+
+/// Tests reported with `<skipped/>`.
+fn is_skipped(status: TestStatus) -> bool {
+    !matches!(status, TestStatus::Passed | TestStatus::Failed)
+}
+
+/// Escape text for use in XML attributes and character data, dropping
+/// control characters XML 1.0 can't represent (e.g. ANSI escapes).
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            '\t' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::Attachment;
+    use crate::result_tree::StepResult;
+
+    fn record(path: &str, status: TestStatus, message: Option<&str>) -> TestRecord {
+        TestRecord {
+            path: path.to_string(),
+            status,
+            duration: Duration::from_millis(12),
+            attempts: 1,
+            message: message.map(str::to_string),
+            owner: None,
+            attachments: Vec::new(),
+            output: None,
+            steps: 0,
+            assertions: 0,
+            ordered_steps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_classnames_failures_and_skips() {
+        let mut tests = vec![
+            record("Parser > numbers > reads ints", TestStatus::Passed, None),
+            record(
+                "Parser > rejects <tags>",
+                TestStatus::Failed,
+                Some("expected \"a\" & 'b'\nsecond line"),
+            ),
+            record("top level", TestStatus::Pending, None),
+            record("Parser > later", TestStatus::Skipped, Some("before_all failed")),
+        ];
+        tests[1].attachments.push(Attachment::reported("page.html", "/tmp/a/page.html"));
+        tests[1].ordered_steps = vec![
+            StepResult {
+                name: "open".to_string(),
+                status: TestStatus::Passed,
+                message: None,
+            },
+            StepResult {
+                name: "parse".to_string(),
+                status: TestStatus::Failed,
+                message: Some("bad <tag>".to_string()),
+            },
+        ];
+        let xml = render(
+            &[SuiteReport {
+                name: "parser",
+                tests: &tests,
+                hook_failures: Vec::new(),
+            }],
+            Duration::from_millis(1500),
+        );
+
+        assert!(xml.contains(
+            "<testsuites name=\"rsspec\" tests=\"4\" failures=\"1\" errors=\"0\" skipped=\"2\" time=\"1.500\">"
+        ));
+        assert!(xml.contains("<testsuite name=\"parser\" tests=\"4\" failures=\"1\""));
+        assert!(xml.contains(
+            "<testcase classname=\"Parser&gt;numbers\" name=\"reads ints\" time=\"0.012\"/>"
+        ));
+        assert!(xml.contains("name=\"rejects &lt;tags&gt;\""));
+        assert!(xml.contains(
+            "second line</failure>\n      <system-out>\n\
+             ✓ [1/2] open\n✗ [2/2] parse: bad &lt;tag&gt;\n\
+             [[ATTACHMENT|/tmp/a/page.html]]\n</system-out>\n    </testcase>"
+        ));
+        assert!(xml.contains(
+            "<failure message=\"expected &quot;a&quot; &amp; &apos;b&apos;\">expected &quot;a&quot; &amp; &apos;b&apos;&#10;second line</failure>"
+        ));
+        assert!(xml.contains(
+            "<testcase classname=\"\" name=\"top level\" time=\"0.012\">\n      <skipped/>"
+        ));
+        assert!(xml.contains("<skipped message=\"before_all failed\"/>"));
+    }
+
+    #[test]
+    fn failed_hooks_fail_the_report() {
+        let tests = [record("db > reads", TestStatus::Skipped, Some("before_all failed"))];
+        let failures = [
+            Failure::new("db (before_all)", "no database"),
+            Failure::new("cache > warm (after_all)", "flush failed"),
+        ];
+        let xml = render(
+            &[SuiteReport {
+                name: "",
+                tests: &tests,
+                hook_failures: failures.iter().collect(),
+            }],
+            Duration::ZERO,
+        );
+
+        assert!(xml.contains("<testsuites name=\"rsspec\" tests=\"3\" failures=\"2\""));
+        assert!(xml.contains(
+            "<testcase classname=\"\" name=\"db (before_all)\" time=\"0.000\">\n      \
+             <failure message=\"no database\">no database</failure>"
+        ));
+        assert!(xml.contains("<testcase classname=\"cache\" name=\"warm (after_all)\""));
+    }
+
+    #[test]
+    fn escape_drops_control_characters() {
+        assert_eq!(escape("\u{1b}[31mred\u{1b}[0m"), "[31mred[0m");
+    }
+}
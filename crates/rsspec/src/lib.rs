@@ -24,14 +24,64 @@
 //! ## Features
 //!
 //! - `googletest` — re-exports `googletest` matchers via `rsspec::matchers`
+//! - `regex` — regex terms in label filters (see [`filter`]) and regex
+//!   `--focus`/`--skip` patterns
+//! - `test-support` — `HookSpy`, hook call counts for testing a suite's hooks
 //! - `tokio` — async test support via `async_it`, `async_before_each`, etc.
+//! - `tracing` — per-test capture of `tracing` events, checked with
+//!   [`assert_logged!`] (see [`logs`])
 
+mod artifacts;
+mod capture;
+pub mod diagnostics;
+#[cfg(feature = "tracing")]
+pub mod logs;
 pub(crate) mod runner;
+pub(crate) mod shuffle;
+pub mod soft;
+mod spy;
+pub(crate) mod style;
 mod context;
+mod csv;
+mod dependencies;
+mod diff;
+pub mod expect;
+pub(crate) mod isolate;
+mod json;
+mod junit;
+pub mod filter;
+mod messages;
 pub(crate) mod ordered;
+mod parallel;
+mod reporter;
+mod rerun;
+mod result_tree;
+mod shared;
+mod state;
+mod tap;
 pub(crate) mod table;
 
-pub use context::{Context, ItBuilder, run, run_inline};
+pub use artifacts::{attach, Attachment, AttachmentSource};
+pub use csv::{CsvRow, CsvSource};
+pub use diff::compare;
+pub use expect::{expect, Expectation};
+pub use context::{
+    Context, ItBuilder, describe_tree, run, run_all, run_and_report, run_inline,
+    run_tree_result, run_with_reporter,
+};
+pub use messages::{Messages, set_messages};
+pub use reporter::{Reporter, RunSummary};
+pub use result_tree::{ResultNode, ResultTree, StepResult, TestResult};
+pub use runner::{
+    Failure, OrderedStep, RunConfig, RunResult, ScopeTiming, Suite, TestFn, TestNode, TestStatus,
+    TimeoutMode, run_suites,
+};
+pub use shared::Shared;
+pub use shuffle::ShuffleOverride;
+pub use soft::{soft, SoftAssertions};
+#[cfg(feature = "test-support")]
+pub use spy::{HookPhase, HookSpy};
+pub use state::StateContext;
 
 /// Re-export of the [`googletest`] crate. Available with the `googletest` feature.
 #[cfg(feature = "googletest")]
@@ -75,23 +125,47 @@ where
     }
 }
 
+// ============================================================================
+// tracing capture (requires `tracing` feature)
+// ============================================================================
+
+/// Run one attempt of a test, capturing its `tracing` events when the
+/// `tracing` feature is enabled.
+pub(crate) fn capture_logs(f: impl FnOnce()) {
+    #[cfg(feature = "tracing")]
+    logs::capture(f);
+    #[cfg(not(feature = "tracing"))]
+    f();
+}
+
 use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+
+use crate::diagnostics::diag;
 
 thread_local! {
     /// Per-thread flag to suppress panic output during retries.
     /// Checked by the custom panic hook installed at init time.
     static SUPPRESS_PANIC_OUTPUT: RefCell<bool> = const { RefCell::new(false) };
+    /// Backtrace of this thread's latest panic, when backtraces are enabled.
+    static LAST_BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
 }
 
-/// Install a panic hook that respects the per-thread suppression flag.
-/// Called once; wraps the default hook so normal panics still print.
-fn install_panic_hook() {
+/// Install a panic hook that respects the per-thread suppression flag and
+/// keeps the panic's backtrace for the failure report. Called once; wraps
+/// the default hook so normal panics still print.
+pub(crate) fn install_panic_hook() {
     use std::sync::Once;
     static INIT: Once = Once::new();
     INIT.call_once(|| {
         let prev = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |info| {
+            // Only captured under RUST_BACKTRACE / RUST_LIB_BACKTRACE, as
+            // in the standard library.
+            let backtrace = std::backtrace::Backtrace::capture();
+            if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+                LAST_BACKTRACE.with(|cell| *cell.borrow_mut() = Some(backtrace.to_string()));
+            }
             let suppress = SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow());
             if !suppress {
                 prev(info);
@@ -100,6 +174,103 @@ fn install_panic_hook() {
     });
 }
 
+/// Return and clear the backtrace of this thread's latest panic.
+pub(crate) fn take_backtrace() -> Option<String> {
+    LAST_BACKTRACE.with(|cell| cell.borrow_mut().take())
+}
+
+/// Keep `backtrace` as if the panic hook had captured it for a panic.
+#[cfg(test)]
+pub(crate) fn seed_backtrace(backtrace: &str) {
+    LAST_BACKTRACE.with(|cell| *cell.borrow_mut() = Some(backtrace.to_string()));
+}
+
+type PayloadDescriber = fn(&(dyn std::any::Any + Send)) -> Option<String>;
+
+/// Custom panic payload types by `type_name`, consulted by the runner when a
+/// panic payload is neither a string nor a boxed error.
+static PANIC_PAYLOADS: std::sync::Mutex<Vec<(&'static str, PayloadDescriber)>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// Register a custom panic payload type so failures raised with
+/// [`std::panic::panic_any`] show its `Display` output instead of
+/// "unknown panic payload".
+///
+/// ```rust
+/// #[derive(Debug)]
+/// struct MyError(u32);
+/// impl std::fmt::Display for MyError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "my error {}", self.0)
+///     }
+/// }
+///
+/// rsspec::register_panic_payload::<MyError>();
+/// ```
+pub fn register_panic_payload<T: std::fmt::Display + Send + 'static>() {
+    fn describe<T: std::fmt::Display + 'static>(
+        payload: &(dyn std::any::Any + Send),
+    ) -> Option<String> {
+        payload.downcast_ref::<T>().map(|v| v.to_string())
+    }
+    let mut registry = PANIC_PAYLOADS.lock().unwrap_or_else(|e| e.into_inner());
+    let name = std::any::type_name::<T>();
+    if !registry.iter().any(|(registered, _)| *registered == name) {
+        registry.push((name, describe::<T>));
+    }
+}
+
+/// Describe a panic payload using the registered types, if any match.
+pub(crate) fn describe_registered_payload(payload: &(dyn std::any::Any + Send)) -> Option<String> {
+    let registry = PANIC_PAYLOADS.lock().unwrap_or_else(|e| e.into_inner());
+    registry.iter().find_map(|(_, describe)| describe(payload))
+}
+
+/// The `type_name`s of the types passed to [`register_panic_payload`].
+pub(crate) fn registered_payload_types() -> Vec<&'static str> {
+    let registry = PANIC_PAYLOADS.lock().unwrap_or_else(|e| e.into_inner());
+    registry.iter().map(|(name, _)| *name).collect()
+}
+
+/// Run `f` and fail if it panics with a message containing `needle`.
+///
+/// For regression tests that check a specific panic was removed. Any other
+/// panic is passed through unchanged, so it fails the test with its own
+/// message. Use [`assert_no_panic`] to reject every panic.
+///
+/// ```rust
+/// fn parse(input: &str) -> usize {
+///     input.len()
+/// }
+///
+/// let len = rsspec::assert_no_panic_matching(|| parse("v1"), "deprecated");
+/// assert_eq!(len, 2);
+/// ```
+#[track_caller]
+pub fn assert_no_panic_matching<R>(f: impl FnOnce() -> R, needle: &str) -> R {
+    record_assertion();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(e) => {
+            let message = runner::panic_message(&*e);
+            if message.contains(needle) {
+                panic!("expected no panic matching {needle:?}, but it panicked with: {message}");
+            }
+            resume_unwind(e)
+        }
+    }
+}
+
+/// Run `f` and fail if it panics at all, naming the panic in the failure.
+#[track_caller]
+pub fn assert_no_panic<R>(f: impl FnOnce() -> R) -> R {
+    record_assertion();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(e) => panic!("expected no panic, but it panicked with: {}", runner::panic_message(&*e)),
+    }
+}
+
 /// A drop guard that runs cleanup code even if the test panics.
 pub struct Guard<F: FnOnce()> {
     f: Option<F>,
@@ -120,73 +291,9 @@ impl<F: FnOnce()> Drop for Guard<F> {
     }
 }
 
-/// Check if the current test's labels match the `RSSPEC_LABEL_FILTER` env var.
-///
-/// Returns `true` (run the test) if no filter is set.
-pub(crate) fn check_labels(labels: &[&str]) -> bool {
-    let filter = match std::env::var("RSSPEC_LABEL_FILTER") {
-        Ok(f) if !f.is_empty() => f,
-        _ => return true,
-    };
-    labels_match_filter(labels, &filter)
-}
-
-/// Check if labels match a filter string.
-///
-/// Filter syntax:
-/// - `integration` — matches if any label equals "integration"
-/// - `!slow` — excludes if any label equals "slow"
-/// - `integration,smoke` — OR: matches if any positive term matches
-/// - `integration+fast` — AND: all terms must match (negation supported: `integration+!slow`)
-pub(crate) fn labels_match_filter(labels: &[&str], filter: &str) -> bool {
-    // Reject ambiguous filters mixing AND (+) and OR (,) syntax
-    if filter.contains('+') && filter.contains(',') {
-        eprintln!(
-            "rsspec: invalid label filter '{filter}' — cannot mix '+' (AND) and ',' (OR). \
-             Use one or the other."
-        );
-        return false;
-    }
-
-    // AND filter: "a+b+!c" means all terms must match
-    if filter.contains('+') {
-        return filter.split('+').all(|term| {
-            let term = term.trim();
-            if let Some(negated) = term.strip_prefix('!') {
-                !labels.contains(&negated)
-            } else {
-                labels.contains(&term)
-            }
-        });
-    }
-
-    // OR filter: "a,b" means any must match
-    // Separate positive and negative terms: positive terms use OR, negative terms use AND
-    let mut has_positive = false;
-    let mut positive_match = false;
-
-    for term in filter.split(',') {
-        let term = term.trim();
-        if let Some(negated) = term.strip_prefix('!') {
-            // Negative terms are exclusions: if any matches, exclude the test
-            if labels.contains(&negated) {
-                return false;
-            }
-        } else {
-            has_positive = true;
-            if labels.contains(&term) {
-                positive_match = true;
-            }
-        }
-    }
-
-    // If there were positive terms, at least one must have matched.
-    // If only negative terms, they all passed (none excluded).
-    !has_positive || positive_match
-}
-
-/// Retry a test function up to `retries` additional times on failure.
-pub(crate) fn with_retries(retries: u32, f: impl Fn()) {
+/// Retry a test function up to `retries` additional times on failure,
+/// sleeping `backoff_ms * 2^(attempt - 1)` before each retry.
+pub(crate) fn with_retries(retries: u32, backoff_ms: u64, f: impl Fn()) {
     install_panic_hook();
 
     let max_attempts = retries + 1;
@@ -204,7 +311,12 @@ pub(crate) fn with_retries(retries: u32, f: impl Fn()) {
             }
             Err(e) => {
                 if attempt < max_attempts {
-                    eprintln!("  attempt {attempt}/{max_attempts} failed, retrying...");
+                    let m = messages::messages();
+                    diag!("  {} {attempt}/{max_attempts} {}", m.attempt, m.retrying);
+                    let delay = backoff_ms.saturating_mul(1 << (attempt - 1).min(63));
+                    if delay > 0 {
+                        std::thread::sleep(std::time::Duration::from_millis(delay));
+                    }
                 }
                 last_panic = Some(e);
             }
@@ -225,12 +337,47 @@ pub(crate) fn must_pass_repeatedly(n: u32, f: impl Fn()) {
     assert!(n > 0, "rsspec: must_pass_repeatedly requires n >= 1");
     for attempt in 1..=n {
         if let Err(e) = catch_unwind(AssertUnwindSafe(&f)) {
-            eprintln!("  must_pass_repeatedly: failed on attempt {attempt}/{n}");
+            diag!("  must_pass_repeatedly: failed on attempt {attempt}/{n}");
             resume_unwind(e);
         }
     }
 }
 
+/// Run `f` `n` times, all of them even if some fail, then fail with how many
+/// runs failed and the first failure if any did.
+///
+/// For stress-testing nondeterministic code; see also
+/// [`ItBuilder::repeat`]. Panics if `n` is 0.
+///
+/// ```rust
+/// rsspec::repeat(100, || assert!(std::collections::HashSet::from([1, 2]).contains(&1)));
+/// ```
+pub fn repeat(n: u32, f: impl Fn()) {
+    assert!(n > 0, "rsspec: repeat requires n >= 1");
+    install_panic_hook();
+    let suppressed = SUPPRESS_PANIC_OUTPUT.with(|cell| cell.replace(true));
+    let mut failed = 0;
+    let mut first_failure = None;
+    for run in 1..=n {
+        // Retries inside `f` turn suppression back off when they finish.
+        SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow_mut() = true);
+        if let Err(e) = catch_unwind(AssertUnwindSafe(&f)) {
+            // The deadline has passed, so every later run would time out too.
+            if e.is::<runner::TimedOut>() {
+                SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow_mut() = suppressed);
+                resume_unwind(e);
+            }
+            failed += 1;
+            first_failure.get_or_insert((run, e));
+        }
+    }
+    SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow_mut() = suppressed);
+    if let Some((run, e)) = first_failure {
+        let message = runner::panic_message(&*e);
+        panic!("{failed}/{n} runs failed; first failure (run {run}): {message}");
+    }
+}
+
 /// Panics if `RSSPEC_FAIL_ON_FOCUS` is set and focus mode is active.
 pub(crate) fn check_fail_on_focus() {
     if let Ok(val) = std::env::var("RSSPEC_FAIL_ON_FOCUS") {
@@ -271,7 +418,7 @@ pub(crate) fn run_deferred_cleanups() {
         let mut first_panic = None;
         for cleanup in cleanups {
             if let Err(e) = catch_unwind(AssertUnwindSafe(cleanup)) {
-                eprintln!("  warning: deferred cleanup panicked");
+                diag!("  warning: deferred cleanup panicked");
                 if first_panic.is_none() {
                     first_panic = Some(e);
                 }
@@ -283,13 +430,197 @@ pub(crate) fn run_deferred_cleanups() {
     });
 }
 
+// ============================================================================
+// TestInfo — metadata about the running test
+// ============================================================================
+
+/// Metadata about the test currently executing.
+///
+/// Available from hooks and test bodies via [`current_test`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestInfo {
+    /// The test's own name.
+    pub name: String,
+    /// Full `describe > it` path.
+    pub path: String,
+    /// Effective labels: inherited from enclosing describes plus the test's own.
+    pub labels: Vec<String>,
+    /// Effective owner: the test's own, else the innermost describe's.
+    pub owner: Option<String>,
+    /// Artifacts recorded so far with [`attach`].
+    pub attachments: Vec<Attachment>,
+}
+
+impl TestInfo {
+    /// Whether the test carries (or inherits) `label`.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|l| l == label)
+    }
+}
+
+thread_local! {
+    static CURRENT_TEST: RefCell<Option<TestInfo>> = const { RefCell::new(None) };
+}
+
+/// Return the metadata of the test currently executing on this thread, or
+/// `None` outside of a test.
+pub fn current_test() -> Option<TestInfo> {
+    CURRENT_TEST.with(|cell| cell.borrow().clone())
+}
+
+/// Run `f` against the current test's metadata without cloning it.
+pub(crate) fn with_current_test<R>(f: impl FnOnce(Option<&TestInfo>) -> R) -> R {
+    CURRENT_TEST.with(|cell| f(cell.borrow().as_ref()))
+}
+
+pub(crate) fn set_current_test(info: Option<TestInfo>) {
+    CURRENT_TEST.with(|cell| *cell.borrow_mut() = info);
+}
+
+/// Record `attachment` on the current test, if there is one.
+pub(crate) fn add_attachment(attachment: Attachment) {
+    CURRENT_TEST.with(|cell| {
+        if let Some(test) = cell.borrow_mut().as_mut() {
+            test.attachments.push(attachment);
+        }
+    });
+}
+
+/// Remove and return the current test's attachments.
+pub(crate) fn take_attachments() -> Vec<Attachment> {
+    CURRENT_TEST.with(|cell| {
+        cell.borrow_mut()
+            .as_mut()
+            .map(|test| std::mem::take(&mut test.attachments))
+            .unwrap_or_default()
+    })
+}
+
+/// How a test went, as seen by an
+/// [`after_each_result`](Context::after_each_result) hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestOutcome<'a> {
+    /// Whether this attempt's `before_each` or `just_before_each` hooks or
+    /// its body panicked, a failed assertion or a `max_duration` overrun
+    /// included.
+    ///
+    /// This is not the test's verdict, which is only settled after the
+    /// `after_each` hooks run. A test can still fail with this `false`: by
+    /// a failing `after_each` hook or cleanup, a `timeout` (which abandons
+    /// the attempt from outside), `--strict-assertions`, or an `xfail` test
+    /// passing. An `xfail` test that fails as expected, or a failed attempt
+    /// that a retry makes up for, has it `true` on the failed attempt.
+    pub body_panicked: bool,
+    /// The test's own name.
+    pub name: &'a str,
+}
+
+thread_local! {
+    static BODY_PANICKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Record whether the current attempt's hooks or body panicked, for the
+/// `after_each` hooks that follow.
+pub(crate) fn set_body_panicked(panicked: bool) {
+    BODY_PANICKED.with(|cell| cell.set(panicked));
+}
+
+/// Whether the current attempt's hooks or body panicked.
+pub(crate) fn body_panicked() -> bool {
+    BODY_PANICKED.with(Cell::get)
+}
+
+// ============================================================================
+// at_exit — process-level finalization
+// ============================================================================
+
+static AT_EXIT: std::sync::Mutex<Vec<Box<dyn FnOnce() + Send>>> = std::sync::Mutex::new(Vec::new());
+
+/// Register a function to run once when [`run`] finishes, right before it
+/// decides whether to exit the process.
+///
+/// [`run_all`] and [`run_with_reporter`] run them too, also when the run
+/// panics. Functions that return to the caller ([`run_inline`],
+/// [`run_and_report`], [`run_tree_result`], [`run_suites`]) leave them for
+/// the process's final `run`, since more suites may follow.
+///
+/// Unlike destructors, these are guaranteed to run on the failure path where
+/// `run` calls [`std::process::exit`], which makes them suitable for flushing
+/// coverage or profiling data. They run in LIFO order, each isolated so a
+/// panic in one does not prevent the others.
+pub fn at_exit(f: impl FnOnce() + Send + 'static) {
+    AT_EXIT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Box::new(f));
+}
+
+/// Run and clear all registered `at_exit` functions.
+pub(crate) fn run_at_exit_hooks() {
+    let hooks: Vec<_> = AT_EXIT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain(..)
+        .collect();
+    for hook in hooks.into_iter().rev() {
+        if catch_unwind(AssertUnwindSafe(hook)).is_err() {
+            diag!("  warning: at_exit hook panicked");
+        }
+    }
+}
+
 // ============================================================================
 // By — step documentation
 // ============================================================================
 
-/// Document a step within a test. Prints the step description to stderr.
+/// Document a step within a test. Writes the step description to the
+/// [`diagnostics`] sink (stderr by default).
+///
+/// Steps are counted per test; `--show-steps` lists the count next to it.
 pub fn by(description: &str) {
-    eprintln!("  STEP: {description}");
+    STEPS.with(|count| count.set(count.get().saturating_add(1)));
+    diag!("  STEP: {description}");
+}
+
+// ============================================================================
+// Context notes — ambient context for failure messages
+// ============================================================================
+
+thread_local! {
+    static CONTEXT_NOTES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Attach a note to the current test, shown with its failure message.
+///
+/// Notes stack up for the rest of the test and are listed most recent first,
+/// so a failure inside a loop says where it happened without a custom message
+/// on every assertion. They are cleared before each test (and each retry).
+///
+/// ```rust,ignore
+/// for user in &users {
+///     rsspec::context_note(format!("processing user {}", user.id));
+///     assert!(user.is_valid());
+/// }
+/// ```
+pub fn context_note(note: impl Into<String>) {
+    CONTEXT_NOTES.with(|notes| notes.borrow_mut().push(note.into()));
+}
+
+/// Run `f` with `note` attached, removing the note again if `f` returns.
+///
+/// If `f` panics the note stays, so it appears in the failure message.
+pub fn with_context_note<R>(note: impl Into<String>, f: impl FnOnce() -> R) -> R {
+    context_note(note);
+    let value = f();
+    CONTEXT_NOTES.with(|notes| notes.borrow_mut().pop());
+    value
+}
+
+/// Remove and return the current notes, most recent first.
+pub(crate) fn take_context_notes() -> Vec<String> {
+    let mut notes = CONTEXT_NOTES.with(|notes| std::mem::take(&mut *notes.borrow_mut()));
+    notes.reverse();
+    notes
 }
 
 // ============================================================================
@@ -305,6 +636,9 @@ thread_local! {
 /// Sets a thread-local flag so the runner can report the test as skipped
 /// rather than passed. Use via the [`skip!`] macro, which also returns
 /// from the test closure.
+///
+/// Skipping is for tests that don't apply in this environment (a missing
+/// database, the wrong OS); for tests that aren't finished, use [`pending`].
 pub fn skip(reason: &str) {
     SKIP_REASON.with(|cell| {
         *cell.borrow_mut() = Some(reason.to_string());
@@ -316,6 +650,243 @@ pub(crate) fn take_skip_reason() -> Option<String> {
     SKIP_REASON.with(|cell| cell.borrow_mut().take())
 }
 
+thread_local! {
+    static PENDING_REASON: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Mark the current test pending at runtime with a reason.
+///
+/// Like [`skip`], but the test is reported as pending, the same as an
+/// `xit`: it is known to be incomplete rather than inapplicable here. Use
+/// via the [`pending!`] macro, which also returns from the test closure.
+pub fn pending(reason: &str) {
+    PENDING_REASON.with(|cell| {
+        *cell.borrow_mut() = Some(reason.to_string());
+    });
+}
+
+/// Check and clear the pending flag set by [`pending`].
+pub(crate) fn take_pending_reason() -> Option<String> {
+    PENDING_REASON.with(|cell| cell.borrow_mut().take())
+}
+
+thread_local! {
+    static SCOPE_SKIP_REASON: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Skip every test of the current describe scope, from one of its
+/// `before_all` hooks, e.g. when a service the scope needs is unavailable.
+///
+/// The scope's remaining `before_all` hooks and all of its tests (nested
+/// ones included) are bypassed and reported as skipped with `reason`;
+/// `after_all` still runs. Has no effect outside a `before_all` hook.
+///
+/// ```rust,ignore
+/// ctx.describe("payments", |ctx| {
+///     ctx.before_all(|| {
+///         if !sandbox_reachable() {
+///             rsspec::skip_scope("payment sandbox unreachable");
+///         }
+///     });
+///     ctx.it("charges a card", || { /* ... */ });
+/// });
+/// ```
+pub fn skip_scope(reason: &str) {
+    SCOPE_SKIP_REASON.with(|cell| *cell.borrow_mut() = Some(reason.to_string()));
+}
+
+/// Check and clear the scope skip flag set by [`skip_scope`].
+pub(crate) fn take_scope_skip_reason() -> Option<String> {
+    SCOPE_SKIP_REASON.with(|cell| cell.borrow_mut().take())
+}
+
+// ============================================================================
+// Assertion and step counting — RSSPEC_WARN_NO_ASSERT, --show-steps
+// ============================================================================
+
+thread_local! {
+    static ASSERTIONS: Cell<u32> = const { Cell::new(0) };
+    static STEPS: Cell<u32> = const { Cell::new(0) };
+    static NOTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Count one assertion for the current test.
+///
+/// With `RSSPEC_WARN_NO_ASSERT=1`, tests that pass without any counted
+/// assertion are listed in the summary, and `--show-steps` shows each test's
+/// count. rsspec's own assertion helpers call this; std's `assert!` family
+/// can't, so call it from your own helpers.
+pub fn record_assertion() {
+    ASSERTIONS.with(|count| count.set(count.get().saturating_add(1)));
+}
+
+/// Return and reset the current test's assertion count.
+pub(crate) fn take_assertion_count() -> u32 {
+    ASSERTIONS.with(|count| count.replace(0))
+}
+
+/// Mark the current test as deliberately making no assertions, e.g. one
+/// that only checks its code doesn't panic.
+///
+/// Such a test passes under `--strict-assertions` and isn't listed by
+/// `RSSPEC_WARN_NO_ASSERT`.
+pub fn noted() {
+    NOTED.with(|noted| noted.set(true));
+}
+
+/// Return and reset whether the current test called [`noted`].
+pub(crate) fn take_noted() -> bool {
+    NOTED.with(|noted| noted.replace(false))
+}
+
+/// Return and reset the number of [`by`] steps the current test took.
+pub(crate) fn take_step_count() -> u32 {
+    STEPS.with(|count| count.replace(0))
+}
+
+/// Whether `RSSPEC_WARN_NO_ASSERT` asks for tests without assertions to be listed.
+pub(crate) fn warn_no_assert_enabled() -> bool {
+    std::env::var("RSSPEC_WARN_NO_ASSERT")
+        .is_ok_and(|val| val == "1" || val.eq_ignore_ascii_case("true"))
+}
+
+// ============================================================================
+// Threaded bodies — `it_send` tests under a timeout
+// ============================================================================
+
+/// What a body run by [`run_on_thread`] left in its thread's per-test state.
+struct ThreadOutcome {
+    result: std::thread::Result<()>,
+    skip_reason: Option<String>,
+    pending_reason: Option<String>,
+    notes: Vec<String>,
+    attachments: Vec<Attachment>,
+    assertions: u32,
+    steps: u32,
+    noted: bool,
+    backtrace: Option<String>,
+}
+
+/// Run a `Send` test body on a new thread, waiting at most `timeout`.
+///
+/// The body sees the current test's [`TestInfo`]; its skip or pending
+/// reason, context notes, attachments, assertion and step counts and panic
+/// backtrace are carried back to this thread afterwards.
+/// Cleanups it defers run on its thread as soon as it returns. Returns
+/// `None` if the deadline passed: the thread is then abandoned and keeps
+/// running until the body returns or the process exits.
+pub(crate) fn run_on_thread(
+    body: std::sync::Arc<dyn Fn() + Send + Sync>,
+    timeout: std::time::Duration,
+) -> Option<std::thread::Result<()>> {
+    let test = current_test();
+    let name = test.as_ref().map_or_else(String::new, |t| t.path.clone());
+    let suppress = SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let spawned = std::thread::Builder::new().name(name).spawn(move || {
+        set_current_test(test);
+        SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow_mut() = suppress);
+        // A body failure takes priority over a cleanup failure.
+        let result = catch_unwind(AssertUnwindSafe(|| body()));
+        let cleanups = catch_unwind(run_deferred_cleanups);
+        let _ = sender.send(ThreadOutcome {
+            result: result.and(cleanups),
+            skip_reason: take_skip_reason(),
+            pending_reason: take_pending_reason(),
+            notes: CONTEXT_NOTES.with(|notes| std::mem::take(&mut *notes.borrow_mut())),
+            attachments: take_attachments(),
+            assertions: take_assertion_count(),
+            steps: take_step_count(),
+            noted: take_noted(),
+            backtrace: take_backtrace(),
+        });
+        set_current_test(None);
+    });
+    if let Err(e) = spawned {
+        return Some(Err(Box::new(format!("rsspec: cannot spawn test thread: {e}"))));
+    }
+
+    let outcome = match receiver.recv_timeout(timeout) {
+        Ok(outcome) => outcome,
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => return None,
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+            return Some(Err(Box::new("rsspec: test thread exited without reporting")));
+        }
+    };
+    if let Some(reason) = outcome.skip_reason {
+        skip(&reason);
+    }
+    if let Some(reason) = outcome.pending_reason {
+        pending(&reason);
+    }
+    CONTEXT_NOTES.with(|notes| notes.borrow_mut().extend(outcome.notes));
+    for attachment in outcome.attachments {
+        add_attachment(attachment);
+    }
+    ASSERTIONS.with(|count| count.set(count.get().saturating_add(outcome.assertions)));
+    STEPS.with(|count| count.set(count.get().saturating_add(outcome.steps)));
+    if outcome.noted {
+        noted();
+    }
+    if outcome.backtrace.is_some() {
+        LAST_BACKTRACE.with(|cell| *cell.borrow_mut() = outcome.backtrace);
+    }
+    Some(outcome.result)
+}
+
+// ============================================================================
+// RSSPEC_DEBUG — per-test thread-local invariants
+// ============================================================================
+
+/// Names of the per-test thread-locals that still hold state.
+///
+/// Between tests, no cleanups should be queued, no skip reason pending,
+/// panic-output suppression off, and no current test set.
+pub(crate) fn leaked_thread_locals() -> Vec<&'static str> {
+    let mut leaked = Vec::new();
+    if CLEANUP_STACK.with(|stack| !stack.borrow().is_empty()) {
+        leaked.push("CLEANUP_STACK is not empty");
+    }
+    if SKIP_REASON.with(|cell| cell.borrow().is_some()) {
+        leaked.push("SKIP_REASON is set");
+    }
+    if PENDING_REASON.with(|cell| cell.borrow().is_some()) {
+        leaked.push("PENDING_REASON is set");
+    }
+    if SCOPE_SKIP_REASON.with(|cell| cell.borrow().is_some()) {
+        leaked.push("SCOPE_SKIP_REASON is set");
+    }
+    if SUPPRESS_PANIC_OUTPUT.with(|cell| *cell.borrow()) {
+        leaked.push("panic output is still suppressed");
+    }
+    if CURRENT_TEST.with(|cell| cell.borrow().is_some()) {
+        leaked.push("current test is still set");
+    }
+    if CONTEXT_NOTES.with(|notes| !notes.borrow().is_empty()) {
+        leaked.push("context notes were not cleared");
+    }
+    leaked
+}
+
+/// Panics if `RSSPEC_DEBUG` is set and a test left thread-local state behind.
+///
+/// A framework self-check run after every test: leaked state makes the next
+/// test on this thread misbehave far from the cause.
+pub(crate) fn check_thread_local_invariants(test_path: &str) {
+    let enabled = std::env::var("RSSPEC_DEBUG")
+        .is_ok_and(|val| val == "1" || val.eq_ignore_ascii_case("true"));
+    if !enabled {
+        return;
+    }
+    let leaked = leaked_thread_locals();
+    if !leaked.is_empty() {
+        panic!(
+            "rsspec: RSSPEC_DEBUG: test '{test_path}' leaked thread-local state: {}",
+            leaked.join(", ")
+        );
+    }
+}
+
 /// Skip the current test at runtime. Prints the reason and returns from the test.
 #[macro_export]
 macro_rules! skip {
@@ -325,6 +896,26 @@ macro_rules! skip {
     }};
 }
 
+/// Mark the current test pending at runtime. Prints the reason and returns
+/// from the test.
+///
+/// ```rust,ignore
+/// ctx.it("exports to PDF", || {
+///     let doc = render();
+///     if !pdf_backend_ready() {
+///         rsspec::pending!("PDF backend not implemented yet");
+///     }
+///     assert!(export_pdf(&doc).is_ok());
+/// });
+/// ```
+#[macro_export]
+macro_rules! pending {
+    ($reason:expr) => {{
+        rsspec::pending($reason);
+        return;
+    }};
+}
+
 /// Document a step within a test (macro form).
 #[macro_export]
 macro_rules! by {
@@ -336,6 +927,16 @@ macro_rules! by {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::filter::labels_match_filter;
+
+    #[test]
+    fn leaked_thread_locals_reports_pending_skip() {
+        assert!(leaked_thread_locals().is_empty());
+        skip("leaks");
+        assert_eq!(leaked_thread_locals(), vec!["SKIP_REASON is set"]);
+        take_skip_reason();
+        assert!(leaked_thread_locals().is_empty());
+    }
 
     #[test]
     fn test_guard_runs_on_success() {
@@ -408,9 +1009,148 @@ mod tests {
         assert!(!labels_match_filter(&["fast"], "integration,smoke"));
     }
 
+    #[test]
+    fn test_labels_glob_terms() {
+        assert!(labels_match_filter(&["team:payments", "tier:2"], "team:*"));
+        assert!(!labels_match_filter(&["tier:2"], "team:*"));
+        assert!(labels_match_filter(&["tier:2"], "tier:?"));
+        assert!(!labels_match_filter(&["tier:12"], "tier:?"));
+        assert!(labels_match_filter(&["db-slow-write"], "db*write"));
+        // Plain terms stay exact
+        assert!(!labels_match_filter(&["team:payments"], "team:"));
+    }
+
+    #[test]
+    fn test_labels_glob_with_and_or_negation() {
+        assert!(labels_match_filter(&["team:search", "fast"], "team:*+!slow"));
+        assert!(!labels_match_filter(&["team:search", "slow"], "team:*+!slow"));
+        assert!(labels_match_filter(&["smoke"], "team:*,smoke"));
+        assert!(!labels_match_filter(&["team:search", "tier:1"], "smoke,!tier:*"));
+        assert!(labels_match_filter(&["fast"], "!team:*"));
+    }
+
+    #[test]
+    fn test_labels_parenthesized_groups() {
+        let filter = "(integration,smoke)+!slow";
+        assert!(labels_match_filter(&["smoke"], filter));
+        assert!(labels_match_filter(&["integration", "fast"], filter));
+        assert!(!labels_match_filter(&["smoke", "slow"], filter));
+        assert!(!labels_match_filter(&["unit"], filter));
+
+        let nested = "a+(b,(c+!d))";
+        assert!(labels_match_filter(&["a", "b"], nested));
+        assert!(labels_match_filter(&["a", "c"], nested));
+        assert!(!labels_match_filter(&["a", "c", "d"], nested));
+        assert!(!labels_match_filter(&["b", "c"], nested));
+    }
+
+    #[test]
+    fn test_labels_keyword_operators() {
+        let filter = "(integration or smoke) and not slow";
+        assert!(labels_match_filter(&["integration"], filter));
+        assert!(!labels_match_filter(&["smoke", "slow"], filter));
+        assert!(!labels_match_filter(&["fast"], filter));
+        // `and` binds tighter than `or`
+        assert!(labels_match_filter(&["c"], "a and b or c"));
+        assert!(!labels_match_filter(&["a"], "a and b or c"));
+        // Plain boolean `or`, unlike a `,` list: "not slow" alone is enough
+        assert!(labels_match_filter(&["fast"], "integration or not slow"));
+        assert!(!labels_match_filter(&["fast"], "integration,!slow"));
+    }
+
+    #[test]
+    fn test_labels_malformed_filters_match_nothing() {
+        for filter in ["(a", "a)", "a+", "a b", "not", "()", "a or b,c", "/unterminated"] {
+            assert!(!labels_match_filter(&["a", "b", "c"], filter), "{filter}");
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_labels_regex_terms() {
+        assert!(labels_match_filter(&["tier:2"], r"/tier:\d/"));
+        assert!(!labels_match_filter(&["tier:12"], r"/tier:\d/"));
+        assert!(labels_match_filter(&["tier:12", "fast"], r"/tier:\d+/+fast"));
+        assert!(!labels_match_filter(&["tier:1"], r"smoke,!/tier:[0-9]/"));
+        // Invalid regexes match nothing
+        assert!(!labels_match_filter(&["a"], "/(/"));
+    }
+
+    /// Serializes the tests that register `at_exit` functions, since each
+    /// drains the functions the others registered.
+    static AT_EXIT_TESTS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn test_at_exit_runs_lifo_despite_panics() {
+        use std::sync::Mutex;
+        static ORDER: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+        let _serial = AT_EXIT_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+
+        at_exit(|| ORDER.lock().unwrap().push(1));
+        at_exit(|| panic!("flush failed"));
+        at_exit(|| ORDER.lock().unwrap().push(3));
+        run_at_exit_hooks();
+
+        assert_eq!(*ORDER.lock().unwrap(), [3, 1]);
+        // Hooks run once: a second call is a no-op.
+        run_at_exit_hooks();
+        assert_eq!(ORDER.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn at_exit_runs_when_the_run_panics() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        static FLUSHED: AtomicBool = AtomicBool::new(false);
+        let _serial = AT_EXIT_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+
+        at_exit(|| FLUSHED.store(true, Ordering::SeqCst));
+        let outcome = catch_unwind(|| {
+            crate::context::finish(|| panic!("reporter broke"));
+        });
+
+        assert!(outcome.is_err(), "the panic should carry on after the at_exit functions");
+        assert!(FLUSHED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn assert_no_panic_matching_only_rejects_matching_panics() {
+        assert_eq!(assert_no_panic_matching(|| 7, "deprecated"), 7);
+
+        let matching = catch_unwind(|| {
+            assert_no_panic_matching(|| panic!("parse: deprecated syntax"), "deprecated")
+        });
+        let message = runner::panic_message(&*matching.unwrap_err());
+        assert_eq!(
+            message,
+            "expected no panic matching \"deprecated\", but it panicked with: \
+             parse: deprecated syntax"
+        );
+
+        let unrelated = catch_unwind(|| assert_no_panic_matching(|| panic!("disk full"), "x"));
+        assert_eq!(runner::panic_message(&*unrelated.unwrap_err()), "disk full");
+
+        let any = catch_unwind(|| assert_no_panic(|| panic!("disk full")));
+        assert_eq!(
+            runner::panic_message(&*any.unwrap_err()),
+            "expected no panic, but it panicked with: disk full"
+        );
+    }
+
+    #[test]
+    fn assertion_helpers_are_counted_across_threads() {
+        take_assertion_count();
+        assert_no_panic(|| ());
+        record_assertion();
+        let body = std::sync::Arc::new(|| assert_no_panic_matching(|| (), "x"));
+        let outcome = run_on_thread(body, std::time::Duration::from_secs(5));
+        assert!(matches!(outcome, Some(Ok(()))));
+        assert_eq!(take_assertion_count(), 3);
+        assert_eq!(take_assertion_count(), 0);
+    }
+
     #[test]
     fn test_with_retries_success_first_try() {
-        with_retries(3, || {
+        with_retries(3, 0, || {
             assert_eq!(1, 1);
         });
     }
@@ -421,7 +1161,7 @@ mod tests {
         static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
         ATTEMPTS.store(0, Ordering::SeqCst);
 
-        with_retries(3, || {
+        with_retries(3, 0, || {
             let n = ATTEMPTS.fetch_add(1, Ordering::SeqCst);
             if n < 2 {
                 panic!("not yet");
@@ -430,4 +1170,21 @@ mod tests {
 
         assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
     }
+
+    #[test]
+    fn test_with_retries_backs_off_exponentially() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+        let start = std::time::Instant::now();
+        with_retries(3, 10, || {
+            if ATTEMPTS.fetch_add(1, Ordering::SeqCst) < 3 {
+                panic!("not yet");
+            }
+        });
+
+        // 10ms + 20ms + 40ms before the three retries.
+        assert!(start.elapsed() >= std::time::Duration::from_millis(70));
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 4);
+    }
 }
@@ -0,0 +1,197 @@
+//! Per-test capture of [`tracing`] events. Available with the `tracing` feature.
+//!
+//! While a test runs (hooks included), a scoped subscriber records every
+//! event emitted on the test's thread. Each attempt starts with an empty
+//! buffer, so tests can check what they logged without wiring up a
+//! subscriber themselves:
+//!
+//! ```rust,ignore
+//! ctx.it("warns on the legacy path", || {
+//!     legacy_api();
+//!     rsspec::assert_logged!(tracing::Level::WARN, "deprecated");
+//! });
+//! ```
+//!
+//! Events from other threads (e.g. ones the test spawns) are not captured.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Level, Metadata, Subscriber};
+
+/// A `tracing` event captured during the current test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedEvent {
+    pub level: Level,
+    /// The event's target, usually the module path that emitted it.
+    pub target: String,
+    /// The formatted message, or empty if the event had none.
+    pub message: String,
+    /// The remaining fields, as `(name, value)` pairs in declaration order.
+    pub fields: Vec<(String, String)>,
+}
+
+impl fmt::Display for CapturedEvent {
+    /// The message followed by `name=value` fields, e.g. `retrying attempt=2`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        for (i, (name, value)) in self.fields.iter().enumerate() {
+            let sep = if i == 0 && self.message.is_empty() { "" } else { " " };
+            write!(f, "{sep}{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static EVENTS: RefCell<Vec<CapturedEvent>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The events captured so far in the current test, oldest first.
+pub fn events() -> Vec<CapturedEvent> {
+    EVENTS.with(|events| events.borrow().clone())
+}
+
+/// Whether the current test emitted an event at `level` whose message or
+/// fields (as rendered by [`CapturedEvent`]'s `Display`) contain `text`.
+pub fn logged(level: Level, text: &str) -> bool {
+    EVENTS.with(|events| {
+        events
+            .borrow()
+            .iter()
+            .any(|event| event.level == level && event.to_string().contains(text))
+    })
+}
+
+/// Panic unless [`logged`]`(level, text)`. Prefer the [`assert_logged!`](crate::assert_logged) macro.
+#[track_caller]
+pub fn assert_logged(level: Level, text: &str) {
+    crate::record_assertion();
+    if logged(level, text) {
+        return;
+    }
+    let captured: Vec<String> = events()
+        .iter()
+        .map(|e| format!("\n  {} {}: {e}", e.level, e.target))
+        .collect();
+    let captured = if captured.is_empty() {
+        " (none)".to_string()
+    } else {
+        captured.concat()
+    };
+    panic!("expected an event at {level} containing {text:?}; captured events:{captured}");
+}
+
+/// Run one test attempt with this thread's events captured into a fresh buffer.
+pub(crate) fn capture(f: impl FnOnce()) {
+    EVENTS.with(|events| events.borrow_mut().clear());
+    tracing::subscriber::with_default(CaptureSubscriber::default(), f);
+}
+
+/// Records events into [`EVENTS`]. Spans are given ids but otherwise ignored.
+#[derive(Default)]
+struct CaptureSubscriber {
+    next_span: AtomicU64,
+}
+
+impl Subscriber for CaptureSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        // Span ids must be non-zero.
+        span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut captured = CapturedEvent {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: String::new(),
+            fields: Vec::new(),
+        };
+        event.record(&mut FieldVisitor(&mut captured));
+        EVENTS.with(|events| events.borrow_mut().push(captured));
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+struct FieldVisitor<'a>(&'a mut CapturedEvent);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, format!("{value:?}"));
+    }
+}
+
+impl FieldVisitor<'_> {
+    fn push(&mut self, field: &Field, value: String) {
+        if field.name() == "message" {
+            self.0.message = value;
+        } else {
+            self.0.fields.push((field.name().to_string(), value));
+        }
+    }
+}
+
+/// Assert that the current test emitted a `tracing` event at a level whose
+/// message or fields contain some text. Requires the `tracing` feature.
+///
+/// ```rust,ignore
+/// rsspec::assert_logged!(tracing::Level::WARN, "deprecated");
+/// ```
+#[macro_export]
+macro_rules! assert_logged {
+    ($level:expr, $text:expr $(,)?) => {
+        $crate::logs::assert_logged($level, $text)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestStatus;
+
+    #[test]
+    fn events_are_captured_per_test() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            ctx.describe("logging", |ctx| {
+                ctx.before_each(|| tracing::info!("setting up"));
+                ctx.it("warns", || {
+                    tracing::warn!(user = 42, "deprecated call");
+                    assert_eq!(events().len(), 2);
+                    assert_eq!(events()[1].to_string(), "deprecated call user=42");
+                    crate::assert_logged!(Level::WARN, "deprecated");
+                    crate::assert_logged!(Level::WARN, "user=42");
+                    assert!(!logged(Level::ERROR, "deprecated"));
+                });
+                ctx.it("starts empty", || {
+                    assert!(!logged(Level::WARN, "deprecated"));
+                    crate::assert_logged!(Level::ERROR, "never logged");
+                });
+            });
+        });
+
+        let statuses: Vec<_> = tree.tests().iter().map(|t| t.status).collect();
+        assert_eq!(statuses, vec![TestStatus::Passed, TestStatus::Failed]);
+        let failed = tree.tests()[1];
+        let message = failed.message.as_deref().unwrap_or_default();
+        assert!(message.starts_with("expected an event at ERROR containing \"never logged\""));
+        assert!(message.contains("INFO rsspec::logs::tests: setting up"));
+    }
+}
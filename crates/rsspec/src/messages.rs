@@ -0,0 +1,102 @@
+//! User-facing strings printed by the runner, replaceable for localization
+//! or branding with [`set_messages`].
+
+use std::sync::Mutex;
+
+/// The fixed words and headings in the runner's output.
+///
+/// Override only what you need; the rest stay English:
+///
+/// ```rust
+/// rsspec::set_messages(rsspec::Messages {
+///     pass: "BESTANDEN",
+///     fail: "FEHLGESCHLAGEN",
+///     passed: "bestanden",
+///     failed: "fehlgeschlagen",
+///     ..rsspec::Messages::default()
+/// });
+/// ```
+///
+/// Machine-readable output (`--output-format json`, `--junit`,
+/// `--summary-format nextest`) is not affected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Messages {
+    /// Verdict line when nothing failed.
+    pub pass: &'static str,
+    /// Verdict line when something failed.
+    pub fail: &'static str,
+    /// Summary counts, printed as `3 passed, 1 failed, ...`.
+    pub passed: &'static str,
+    pub failed: &'static str,
+    pub flaky: &'static str,
+    pub xfailed: &'static str,
+    pub pending: &'static str,
+    pub skipped: &'static str,
+    /// Printed as `0 matched` when every test was filtered out.
+    pub matched: &'static str,
+    /// Heading of the numbered failure list.
+    pub failures: &'static str,
+    /// Heading of the per-owner failure list.
+    pub failures_by_owner: &'static str,
+    /// Heading of the quarantined flaky failure list.
+    pub flaky_failures: &'static str,
+    /// Heading of the list of files attached to failed tests.
+    pub artifacts: &'static str,
+    /// Heading of the tests that passed without assertions (`RSSPEC_WARN_NO_ASSERT`).
+    pub no_assertions: &'static str,
+    /// Heading of the per-describe `before_all`/`after_all` times (`--timing`).
+    pub scope_timings: &'static str,
+    /// `--dry-run` summary, printed as `12 tests would run`.
+    pub would_run: &'static str,
+    /// Prefix of a failed test's message in the tree.
+    pub error: &'static str,
+    /// Heading of a failed test's `--capture` output in the tree.
+    pub output: &'static str,
+    /// Retry notice, printed as `attempt 1/3 failed, retrying...`.
+    pub attempt: &'static str,
+    pub retrying: &'static str,
+}
+
+impl Messages {
+    /// The default English strings.
+    pub const ENGLISH: Messages = Messages {
+        pass: "PASS",
+        fail: "FAIL",
+        passed: "passed",
+        failed: "failed",
+        flaky: "flaky",
+        xfailed: "xfailed",
+        pending: "pending",
+        skipped: "skipped",
+        matched: "matched",
+        failures: "Failures:",
+        failures_by_owner: "Failures by owner:",
+        flaky_failures: "Flaky (not counted as failures):",
+        artifacts: "Artifacts:",
+        no_assertions: "Passed without rsspec assertions:",
+        scope_timings: "Scope hook timing:",
+        would_run: "tests would run",
+        error: "Error:",
+        output: "Output:",
+        attempt: "attempt",
+        retrying: "failed, retrying...",
+    };
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Messages::ENGLISH
+    }
+}
+
+static MESSAGES: Mutex<Messages> = Mutex::new(Messages::ENGLISH);
+
+/// Replace the strings used by the runner's output for the rest of the process.
+pub fn set_messages(messages: Messages) {
+    *MESSAGES.lock().unwrap_or_else(|e| e.into_inner()) = messages;
+}
+
+/// The strings currently in effect.
+pub(crate) fn messages() -> Messages {
+    *MESSAGES.lock().unwrap_or_else(|e| e.into_inner())
+}
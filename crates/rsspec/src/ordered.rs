@@ -18,8 +18,10 @@ use crate::runner::{OrderedStep, TestNode};
 pub struct OrderedContext {
     name: String,
     continue_on_failure: bool,
+    tolerate: usize,
     steps: Vec<OrderedStep>,
     labels: Vec<String>,
+    on_failure: Vec<Box<dyn Fn()>>,
 }
 
 impl OrderedContext {
@@ -27,19 +29,98 @@ impl OrderedContext {
         OrderedContext {
             name,
             continue_on_failure,
+            tolerate: 0,
             steps: Vec::new(),
             labels: Vec::new(),
+            on_failure: Vec::new(),
         }
     }
 
     /// Add a named step to the sequence.
     pub fn step(&mut self, name: &str, body: impl Fn() + 'static) {
+        self.push_step(name, body, false, false);
+    }
+
+    /// Focused variant of [`step`](Self::step). It focuses the sequence, as
+    /// `fit` does a test, and only the sequence's focused steps run; the
+    /// others are logged as skipped.
+    pub fn fstep(&mut self, name: &str, body: impl Fn() + 'static) {
+        self.push_step(name, body, true, false);
+    }
+
+    /// Pending variant of [`step`](Self::step): logged as pending and passed
+    /// over without running; the steps after it still run.
+    pub fn xstep(&mut self, name: &str, body: impl Fn() + 'static) {
+        self.push_step(name, body, false, true);
+    }
+
+    /// Add a step with a cleanup that undoes it if a later step fails.
+    ///
+    /// When the sequence fails, the cleanups of the steps that completed
+    /// run in reverse order, before any [`on_failure`](Self::on_failure)
+    /// rollbacks. The failing step's own cleanup doesn't run. With
+    /// `continue_on_failure`, this happens once all steps have run.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.ordered("provision", |oct| {
+    ///     oct.step_with_cleanup("create vm", || { /* ... */ }, || { /* delete the vm */ });
+    ///     oct.step_with_cleanup("attach disk", || { /* ... */ }, || { /* detach it */ });
+    ///     oct.step("boot", || { /* on failure: detach, then delete */ });
+    /// });
+    /// # }); }
+    /// ```
+    pub fn step_with_cleanup(
+        &mut self,
+        name: &str,
+        body: impl Fn() + 'static,
+        cleanup: impl Fn() + 'static,
+    ) {
+        self.push_step(name, body, false, false);
+        if let Some(step) = self.steps.last_mut() {
+            step.cleanup = Some(Box::new(cleanup));
+        }
+    }
+
+    fn push_step(&mut self, name: &str, body: impl Fn() + 'static, focused: bool, pending: bool) {
         self.steps.push(OrderedStep {
             name: name.to_string(),
             body: Box::new(body),
+            focused,
+            pending,
+            cleanup: None,
         });
     }
 
+    /// Register a rollback action that runs if any step fails.
+    ///
+    /// Rollbacks run in reverse registration order, after the failing step
+    /// and before `after_each` hooks. They all run regardless of which step
+    /// failed, so each should tolerate a resource that was never created. A
+    /// rollback that panics is reported as a warning; the step failure stands.
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.ordered("provision", |oct| {
+    ///     oct.step("create vm", || { /* ... */ });
+    ///     oct.on_failure(|| { /* delete the vm if it exists */ });
+    ///     oct.step("attach disk", || { /* ... */ });
+    /// });
+    /// # }); }
+    /// ```
+    pub fn on_failure(&mut self, rollback: impl Fn() + 'static) {
+        self.on_failure.push(Box::new(rollback));
+    }
+
+    /// Let up to `n` steps fail without failing the sequence, e.g. for
+    /// known-flaky sub-steps. Every step still runs, as with
+    /// [`Context::ordered_continue_on_failure`](crate::Context::ordered_continue_on_failure),
+    /// and failed steps are listed either way.
+    pub fn tolerate(&mut self, n: usize) {
+        self.continue_on_failure = true;
+        self.tolerate = n;
+    }
+
     /// Add labels to this ordered test. Labels accumulate across multiple calls.
     pub fn labels(&mut self, labels: &[&str]) {
         self.labels.extend(labels.iter().map(|s| s.to_string()));
@@ -62,7 +143,9 @@ impl OrderedContext {
             name: self.name,
             labels: self.labels,
             continue_on_failure: self.continue_on_failure,
+            tolerate: self.tolerate,
             steps: self.steps,
+            on_failure: self.on_failure,
         }
     }
 }
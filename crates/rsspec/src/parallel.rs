@@ -0,0 +1,395 @@
+//! Process-level parallelism for `--jobs N`.
+//!
+//! Test closures aren't `Send`, so rather than threads the runner re-executes
+//! the test binary once per top-level describe (or top-level test), with the
+//! same arguments plus a hidden `--rsspec-worker` flag naming the unit to
+//! run. Up to N workers run at once. Each writes its tree output to a temp
+//! file and its results to another; the parent prints the output in
+//! declaration order and merges the results, so the tree stays coherent.
+//!
+//! A top-level node is the smallest unit: the tests inside one describe never
+//! run in parallel with each other, and the suite's top-level `before_all` and
+//! `after_all` hooks run once in every worker.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use crate::artifacts::Attachment;
+use crate::result_tree::StepResult;
+use crate::runner::{Failure, RunResult, ScopeTiming, TestRecord, TestStatus};
+
+/// Hidden flag selecting the unit a worker process runs.
+pub(crate) const WORKER_FLAG: &str = "--rsspec-worker";
+/// Set for workers whose parent writes color to a terminal, since the
+/// worker's own stdout is a file.
+pub(crate) const COLOR_ENV: &str = "RSSPEC_WORKER_COLOR";
+
+/// The unit a worker process runs and where it reports back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct WorkerUnit {
+    /// Index into the suites passed to `run_suites`.
+    pub suite: usize,
+    /// Index of the top-level node within that suite.
+    pub node: usize,
+    /// File the worker writes its results to.
+    pub results: PathBuf,
+}
+
+impl WorkerUnit {
+    /// Parse the `--rsspec-worker` value, `SUITE:NODE:RESULTS_PATH`.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, ':');
+        Some(WorkerUnit {
+            suite: parts.next()?.parse().ok()?,
+            node: parts.next()?.parse().ok()?,
+            results: PathBuf::from(parts.next()?),
+        })
+    }
+}
+
+/// A running worker process.
+pub(crate) struct Worker {
+    child: Child,
+    output: PathBuf,
+    results: PathBuf,
+}
+
+impl Worker {
+    /// Start a worker for top-level node `node` of suite `suite`, passing it
+    /// the parent's `args` (and `seed`, so shuffled runs agree on the order).
+    pub(crate) fn spawn(
+        args: &[String],
+        suite: usize,
+        node: usize,
+        seed: Option<u64>,
+    ) -> std::io::Result<Self> {
+        let base = std::env::temp_dir().join(format!(
+            "rsspec-{}-{suite}-{node}",
+            std::process::id()
+        ));
+        let output = base.with_extension("out");
+        let results = base.with_extension("results");
+
+        let mut command = Command::new(std::env::current_exe()?);
+        command
+            .args(args)
+            .arg(format!("{WORKER_FLAG}={suite}:{node}:{}", results.display()))
+            .stdin(Stdio::null())
+            .stdout(File::create(&output)?);
+        if let Some(seed) = seed {
+            command.arg(format!("--seed={seed}"));
+        }
+        if crate::style::use_color() {
+            command.env(COLOR_ENV, "1");
+        }
+        Ok(Worker {
+            child: command.spawn()?,
+            output,
+            results,
+        })
+    }
+
+    /// Stop a worker whose results are no longer wanted (e.g. after `--bail`).
+    pub(crate) fn kill(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_file(&self.output);
+        let _ = std::fs::remove_file(&self.results);
+    }
+
+    /// Wait for the worker. Returns its tree output and results, or a
+    /// message if it died before reporting (e.g. a crash or `process::exit`).
+    pub(crate) fn finish(mut self) -> Result<(String, RunResult), String> {
+        let status = self.child.wait().map_err(|e| format!("worker failed: {e}"))?;
+        let output = std::fs::read_to_string(&self.output).unwrap_or_default();
+        let results = std::fs::read_to_string(&self.results).ok();
+        let _ = std::fs::remove_file(&self.output);
+        let _ = std::fs::remove_file(&self.results);
+        match results.as_deref().and_then(decode) {
+            Some(result) => Ok((output, result)),
+            None => Err(format!("worker exited with {status} before reporting results")),
+        }
+    }
+}
+
+/// Write a worker's results for the parent to [`decode`].
+pub(crate) fn write_results(unit: &WorkerUnit, result: &RunResult) -> std::io::Result<()> {
+    std::fs::write(&unit.results, encode(result))
+}
+
+// One record per line, tab-separated, with `\`, tab and newline escaped.
+// Optional fields are `-` when absent and `=value` when present.
+
+fn encode(result: &RunResult) -> String {
+    let mut out = format!(
+        "counts\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        result.passed, result.failed, result.pending, result.skipped, result.flaky, result.xfailed
+    );
+    for t in &result.tests {
+        out.push_str(&format!(
+            "test\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            status_name(t.status),
+            t.duration.as_nanos(),
+            t.attempts,
+            t.steps,
+            t.assertions,
+            escape(&t.path),
+            optional(t.message.as_deref()),
+            optional(t.owner.as_deref()),
+        ));
+        for a in &t.attachments {
+            let path = a.path.to_string_lossy();
+            out.push_str(&format!("attachment\t{}\t{}\n", escape(&a.name), escape(&path)));
+        }
+        if let Some(output) = &t.output {
+            out.push_str(&format!("output\t{}\n", escape(output)));
+        }
+        for s in &t.ordered_steps {
+            out.push_str(&format!(
+                "step\t{}\t{}\t{}\n",
+                status_name(s.status),
+                escape(&s.name),
+                optional(s.message.as_deref()),
+            ));
+        }
+    }
+    for f in &result.failures {
+        out.push_str(&format!("failure\t{}\t{}\n", escape(&f.path), escape(&f.message)));
+        if let Some(location) = &f.location {
+            out.push_str(&format!("location\t{}\n", escape(location)));
+        }
+        if let Some(backtrace) = &f.backtrace {
+            out.push_str(&format!("backtrace\t{}\n", escape(backtrace)));
+        }
+    }
+    for f in &result.flaky_failures {
+        out.push_str(&format!("flaky\t{}\t{}\n", escape(&f.path), escape(&f.message)));
+    }
+    for path in &result.no_assertions {
+        out.push_str(&format!("noassert\t{}\n", escape(path)));
+    }
+    for t in &result.scope_timings {
+        out.push_str(&format!(
+            "scope\t{}\t{}\t{}\t{}\n",
+            t.before_all.as_nanos(),
+            t.after_all.as_nanos(),
+            t.tests,
+            escape(&t.path),
+        ));
+    }
+    out.push_str("end\n");
+    out
+}
+
+fn decode(text: &str) -> Option<RunResult> {
+    let mut result = RunResult::default();
+    let mut complete = false;
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields.as_slice() {
+            ["counts", counts @ ..] if counts.len() == 6 => {
+                let n: Vec<usize> = counts.iter().map(|c| c.parse().ok()).collect::<Option<_>>()?;
+                result.passed = n[0];
+                result.failed = n[1];
+                result.pending = n[2];
+                result.skipped = n[3];
+                result.flaky = n[4];
+                result.xfailed = n[5];
+            }
+            ["test", status, nanos, attempts, steps, assertions, path, message, owner] => {
+                result.tests.push(TestRecord {
+                    path: unescape(path),
+                    status: parse_status(status)?,
+                    duration: Duration::from_nanos(nanos.parse().ok()?),
+                    attempts: attempts.parse().ok()?,
+                    message: parse_optional(message)?,
+                    owner: parse_optional(owner)?,
+                    attachments: Vec::new(),
+                    output: None,
+                    steps: steps.parse().ok()?,
+                    assertions: assertions.parse().ok()?,
+                    ordered_steps: Vec::new(),
+                });
+            }
+            // These follow the test line they belong to.
+            ["attachment", name, path] => {
+                let test = result.tests.last_mut()?;
+                test.attachments.push(Attachment::reported(unescape(name), unescape(path)));
+            }
+            ["output", output] => result.tests.last_mut()?.output = Some(unescape(output)),
+            ["step", status, name, message] => {
+                result.tests.last_mut()?.ordered_steps.push(StepResult {
+                    name: unescape(name),
+                    status: parse_status(status)?,
+                    message: parse_optional(message)?,
+                });
+            }
+            ["failure", path, message] => {
+                result.failures.push(Failure::new(unescape(path), unescape(message)));
+            }
+            // These follow the failure line they belong to.
+            ["location", location] => {
+                result.failures.last_mut()?.location = Some(unescape(location));
+            }
+            ["backtrace", backtrace] => {
+                result.failures.last_mut()?.backtrace = Some(unescape(backtrace));
+            }
+            ["flaky", path, message] => {
+                result.flaky_failures.push(Failure::new(unescape(path), unescape(message)));
+            }
+            ["noassert", path] => result.no_assertions.push(unescape(path)),
+            ["scope", before_all, after_all, tests, path] => {
+                result.scope_timings.push(ScopeTiming {
+                    path: unescape(path),
+                    before_all: Duration::from_nanos(before_all.parse().ok()?),
+                    after_all: Duration::from_nanos(after_all.parse().ok()?),
+                    tests: tests.parse().ok()?,
+                });
+            }
+            ["end"] => complete = true,
+            _ => return None,
+        }
+    }
+    complete.then_some(result)
+}
+
+fn status_name(status: TestStatus) -> &'static str {
+    match status {
+        TestStatus::Passed => "passed",
+        TestStatus::Failed => "failed",
+        TestStatus::Flaky => "flaky",
+        TestStatus::XFailed => "xfailed",
+        TestStatus::Skipped => "skipped",
+        TestStatus::Pending => "pending",
+    }
+}
+
+fn parse_status(name: &str) -> Option<TestStatus> {
+    Some(match name {
+        "passed" => TestStatus::Passed,
+        "failed" => TestStatus::Failed,
+        "flaky" => TestStatus::Flaky,
+        "xfailed" => TestStatus::XFailed,
+        "skipped" => TestStatus::Skipped,
+        "pending" => TestStatus::Pending,
+        _ => return None,
+    })
+}
+
+fn optional(value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("={}", escape(value)),
+        None => "-".to_string(),
+    }
+}
+
+fn parse_optional(field: &str) -> Option<Option<String>> {
+    match field {
+        "-" => Some(None),
+        _ => Some(Some(unescape(field.strip_prefix('=')?))),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_round_trip() {
+        let mut result = RunResult::default();
+        result.record("db > reads", TestStatus::Passed).duration = Duration::from_micros(1500);
+        let record = result.record("db > writes", TestStatus::Failed);
+        record.message = Some("left:\t1\nright: \\2".to_string());
+        record.owner = Some("storage".to_string());
+        record.attempts = 3;
+        record.steps = 2;
+        record.assertions = 5;
+        record.attachments.push(Attachment::reported("dump\tfile", "/tmp/dump"));
+        record.output = Some("connecting\n\tretry 1\n".to_string());
+        record.ordered_steps = vec![
+            StepResult {
+                name: "connect".to_string(),
+                status: TestStatus::Passed,
+                message: None,
+            },
+            StepResult {
+                name: "write\trow".to_string(),
+                status: TestStatus::Failed,
+                message: Some("disk full".to_string()),
+            },
+        ];
+        result.failed += 1;
+        result.failures.push(Failure::new("db > writes", "left:\t1\nright: \\2"));
+        let backtrace = "   0: db::write\n             at src/db.rs:7";
+        result.failures[0].backtrace = Some(backtrace.to_string());
+        result.failures[0].location = Some("tests/db.rs:12".to_string());
+        result.failures.push(Failure::new("db (after_all)", "teardown"));
+        result.no_assertions.push("db > reads".to_string());
+        result.scope_timings.push(ScopeTiming {
+            path: "db\tsetup".to_string(),
+            before_all: Duration::from_millis(2100),
+            after_all: Duration::from_micros(300),
+            tests: 2,
+        });
+
+        let decoded = decode(&encode(&result)).expect("decodes");
+        assert_eq!(decoded.passed, 1);
+        assert_eq!(decoded.failed, 2);
+        assert_eq!(decoded.tests.len(), 2);
+        assert_eq!(decoded.tests[0].duration, Duration::from_micros(1500));
+        assert_eq!(decoded.tests[1].message, result.tests[1].message);
+        assert_eq!(decoded.tests[1].owner.as_deref(), Some("storage"));
+        assert_eq!(decoded.tests[1].attempts, 3);
+        assert_eq!((decoded.tests[1].steps, decoded.tests[1].assertions), (2, 5));
+        assert_eq!(decoded.tests[1].attachments, result.tests[1].attachments);
+        assert_eq!(decoded.tests[1].output, result.tests[1].output);
+        assert_eq!(decoded.tests[1].ordered_steps, result.tests[1].ordered_steps);
+        assert_eq!(decoded.failures, result.failures);
+        assert_eq!(decoded.no_assertions, result.no_assertions);
+        assert_eq!(decoded.scope_timings, result.scope_timings);
+    }
+
+    #[test]
+    fn truncated_results_are_rejected() {
+        let text = encode(&RunResult::default());
+        assert!(decode(&text).is_some());
+        assert!(decode(text.trim_end_matches("end\n")).is_none());
+    }
+
+    #[test]
+    fn worker_unit_parses_paths_with_colons() {
+        assert_eq!(
+            WorkerUnit::parse("0:3:C:\\tmp\\r.results"),
+            Some(WorkerUnit {
+                suite: 0,
+                node: 3,
+                results: PathBuf::from("C:\\tmp\\r.results"),
+            })
+        );
+        assert_eq!(WorkerUnit::parse("0:x:/tmp/r"), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Pluggable run output.
+//!
+//! The runner reports progress as events on a [`Reporter`]. The colored tree
+//! and `--output-format json` are the built-in implementations; pass your
+//! own to [`run_with_reporter`](crate::run_with_reporter) to stream results
+//! somewhere else (a web UI, a database, a chat channel).
+
+use std::time::Duration;
+
+use crate::result_tree::TestResult;
+use crate::runner::{Failure, RunResult, ScopeTiming, TestRecord};
+
+/// Receives events as a run progresses. Every method defaults to doing
+/// nothing, so implement only the ones you need.
+///
+/// Events arrive in run order: `run_started`, then per suite
+/// `suite_started` followed by nested `describe_entered`/`describe_finished`
+/// pairs around the `it_finished` events of their tests, and finally
+/// `run_finished`.
+///
+/// ```rust,no_run
+/// struct Dots;
+///
+/// impl rsspec::Reporter for Dots {
+///     fn it_finished(&mut self, test: &rsspec::TestResult) {
+///         print!("{}", if test.status == rsspec::TestStatus::Failed { "F" } else { "." });
+///     }
+///
+///     fn run_finished(&mut self, summary: &rsspec::RunSummary) {
+///         println!("\n{} passed, {} failed", summary.passed, summary.failed);
+///     }
+/// }
+///
+/// rsspec::run_with_reporter(&mut Dots, |ctx| {
+///     ctx.it("adds", || assert_eq!(1 + 1, 2));
+/// });
+/// ```
+pub trait Reporter {
+    /// The run is starting. `seed` is set when tests are shuffled.
+    fn run_started(&mut self, seed: Option<u64>) {
+        let _ = seed;
+    }
+
+    /// A suite is starting. `name` is empty for the single suite built by
+    /// [`run`](crate::run).
+    fn suite_started(&mut self, name: &str) {
+        let _ = name;
+    }
+
+    /// A describe scope was entered. `path` is its full `describe > ...` path.
+    fn describe_entered(&mut self, path: &str, name: &str) {
+        let _ = (path, name);
+    }
+
+    /// The describe at `path` finished, including its `after_all` hooks.
+    fn describe_finished(&mut self, path: &str) {
+        let _ = path;
+    }
+
+    /// A test finished, or was skipped or left pending.
+    ///
+    /// Tests skipped only because focus mode is active carry no message.
+    fn it_finished(&mut self, test: &TestResult) {
+        let _ = test;
+    }
+
+    /// A scope-level hook failed outside any single test, e.g. `before_all`
+    /// (`hook` names it) for the describe at `path`.
+    fn hook_failed(&mut self, path: &str, hook: &str, message: &str) {
+        let _ = (path, hook, message);
+    }
+
+    /// The run finished.
+    fn run_finished(&mut self, summary: &RunSummary) {
+        let _ = summary;
+    }
+}
+
+/// Totals and failures of a finished run, passed to
+/// [`Reporter::run_finished`].
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    pub passed: usize,
+    /// Failed tests plus failed scope hooks.
+    pub failed: usize,
+    /// Flaky tests that still failed after their retries. Not counted in `failed`.
+    pub flaky: usize,
+    pub xfailed: usize,
+    pub pending: usize,
+    pub skipped: usize,
+    /// The run stopped early because `--bail` was reached.
+    pub bailed: bool,
+    /// Wall time of the whole run.
+    pub duration: Duration,
+    /// Every test reached by the run, in run order.
+    pub tests: Vec<TestResult>,
+    /// Test and hook failures, in the order they happened.
+    pub failures: Vec<Failure>,
+    /// Quarantined failures of `flaky` tests.
+    pub flaky_failures: Vec<Failure>,
+    /// Tests that passed without a counted assertion, listed when
+    /// `RSSPEC_WARN_NO_ASSERT` is set.
+    pub no_assertions: Vec<String>,
+    /// `before_all`/`after_all` times of the describes that have them.
+    pub scope_timings: Vec<ScopeTiming>,
+    /// With `--dry-run`, how many tests the filters selected. They're listed
+    /// in `tests` as skipped, with the message "dry run".
+    pub would_run: usize,
+}
+
+impl RunSummary {
+    pub(crate) fn new(result: &RunResult, duration: Duration) -> Self {
+        RunSummary {
+            passed: result.passed,
+            failed: result.failed,
+            flaky: result.flaky,
+            xfailed: result.xfailed,
+            pending: result.pending,
+            skipped: result.skipped,
+            bailed: result.bailed,
+            duration,
+            tests: result.tests.iter().map(test_result).collect(),
+            failures: result.failures.clone(),
+            flaky_failures: result.flaky_failures.clone(),
+            no_assertions: result.no_assertions.clone(),
+            scope_timings: result.scope_timings.clone(),
+            would_run: result.would_run,
+        }
+    }
+}
+
+/// The public view of a runner record.
+pub(crate) fn test_result(record: &TestRecord) -> TestResult {
+    let name = record
+        .path
+        .rsplit_once(" > ")
+        .map_or(record.path.as_str(), |(_, name)| name);
+    TestResult {
+        name: name.to_string(),
+        path: record.path.clone(),
+        status: record.status,
+        duration: record.duration,
+        attempts: record.attempts,
+        message: record.message.clone(),
+        owner: record.owner.clone(),
+        attachments: record.attachments.clone(),
+        output: record.output.clone(),
+        steps: record.steps,
+        assertions: record.assertions,
+        ordered_steps: record.ordered_steps.clone(),
+    }
+}
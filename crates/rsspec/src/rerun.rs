@@ -0,0 +1,147 @@
+//! `--write-rerun`: a shell script that re-runs the tests that failed.
+//!
+//! The script calls the test binary once per failed test, with `--exact` and
+//! that test's full path, so `./target/rsspec_rerun.sh` iterates on exactly
+//! the failing set. Tests skipped because their scope's `before_all` failed
+//! count as failing, since their scope is what needs fixing. Each command
+//! runs even if an earlier one fails.
+
+use std::path::{Path, PathBuf};
+
+use crate::runner::{TestRecord, TestStatus, BEFORE_ALL_FAILED};
+
+/// Where the script goes: `rsspec_rerun.sh` in `CARGO_TARGET_DIR`, or else
+/// in the `target` directory the test binary was built in.
+pub(crate) fn script_path(exe: &Path) -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            exe.ancestors()
+                .find(|dir| dir.file_name().is_some_and(|name| name == "target"))
+                .map(Path::to_path_buf)
+        })
+        .unwrap_or_else(|| PathBuf::from("target"));
+    target.join("rsspec_rerun.sh")
+}
+
+/// Write the script for the failed tests among `tests`, and those a failed
+/// `before_all` skipped. With neither, a script left by an earlier run is
+/// removed so it can't re-run stale ones.
+pub(crate) fn write(tests: &[TestRecord]) {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("rsspec: cannot write --write-rerun script: {e}");
+            return;
+        }
+    };
+    let path = script_path(&exe);
+    let failed = failing(tests);
+    if failed.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return;
+    }
+
+    match save(&path, &script(&exe.to_string_lossy(), &failed)) {
+        Ok(()) => eprintln!(
+            "rsspec: wrote {} to re-run {} failed test(s)",
+            path.display(),
+            failed.len()
+        ),
+        Err(e) => eprintln!("rsspec: cannot write --write-rerun script '{}': {e}", path.display()),
+    }
+}
+
+/// Full paths of the tests to re-run: the failed ones, and those skipped
+/// because a `before_all` failed.
+fn failing(tests: &[TestRecord]) -> Vec<&str> {
+    tests
+        .iter()
+        .filter(|t| {
+            t.status == TestStatus::Failed
+                || t.status == TestStatus::Skipped
+                    && t.message.as_deref() == Some(BEFORE_ALL_FAILED)
+        })
+        .map(|t| t.path.as_str())
+        .collect()
+}
+
+fn save(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// The script text: one `binary --exact 'full path'` command per failed test.
+fn script(exe: &str, failed: &[&str]) -> String {
+    let mut out = format!(
+        "#!/bin/sh\n# Generated by rsspec --write-rerun: re-runs {} failed test(s).\n",
+        failed.len()
+    );
+    for path in failed {
+        out.push_str(&format!("{} --exact {}\n", quote(exe), quote(path)));
+    }
+    out
+}
+
+/// Single-quote `text` for a POSIX shell.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_runs_each_failure_with_quoted_paths() {
+        assert_eq!(
+            script("/work/target/debug/deps/specs-1a2b", &["cart > adds", "user's > name"]),
+            "#!/bin/sh
+# Generated by rsspec --write-rerun: re-runs 2 failed test(s).
+'/work/target/debug/deps/specs-1a2b' --exact 'cart > adds'
+'/work/target/debug/deps/specs-1a2b' --exact 'user'\\''s > name'
+"
+        );
+    }
+
+    #[test]
+    fn reruns_failures_and_tests_a_failed_before_all_skipped() {
+        let record = |path: &str, status, message: Option<&str>| TestRecord {
+            path: path.to_string(),
+            status,
+            duration: std::time::Duration::ZERO,
+            attempts: 1,
+            message: message.map(str::to_string),
+            owner: None,
+            attachments: Vec::new(),
+            output: None,
+            steps: 0,
+            assertions: 0,
+            ordered_steps: Vec::new(),
+        };
+        let tests = [
+            record("cart > adds", TestStatus::Failed, Some("boom")),
+            record("cart > removes", TestStatus::Passed, None),
+            record("db > reads", TestStatus::Skipped, Some(BEFORE_ALL_FAILED)),
+            record("db > writes", TestStatus::Skipped, Some("no database")),
+        ];
+        assert_eq!(failing(&tests), ["cart > adds", "db > reads"]);
+    }
+
+    #[test]
+    fn script_lands_in_the_binarys_target_dir() {
+        if std::env::var_os("CARGO_TARGET_DIR").is_some() {
+            return;
+        }
+        let exe = Path::new("/work/target/debug/deps/specs-1a2b");
+        assert_eq!(script_path(exe), Path::new("/work/target/rsspec_rerun.sh"));
+    }
+}
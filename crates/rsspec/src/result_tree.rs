@@ -0,0 +1,192 @@
+//! Hierarchical run results — the describe/it tree with per-test outcomes.
+//!
+//! Returned by [`run_tree_result`](crate::run_tree_result) for tools that
+//! render results in their original structure (dashboards, custom UIs)
+//! rather than as the flat counts and failure list the runner prints.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::artifacts::Attachment;
+use crate::runner::{TestNode, TestRecord, TestStatus};
+
+/// The results of a run, shaped like the suite that produced them.
+///
+/// Nodes appear in declaration order. Tests that were filtered out (by path,
+/// labels, plan or focus) are omitted, as are describes left empty by that.
+#[derive(Clone, Debug, Default)]
+pub struct ResultTree {
+    /// Top-level describes and tests.
+    pub children: Vec<ResultNode>,
+}
+
+/// A describe scope or a single test within a [`ResultTree`].
+#[derive(Clone, Debug)]
+pub enum ResultNode {
+    Describe {
+        name: String,
+        children: Vec<ResultNode>,
+    },
+    Test(TestResult),
+}
+
+/// The outcome of one test (an `it` or an ordered block).
+#[derive(Clone, Debug)]
+pub struct TestResult {
+    /// The test's own name.
+    pub name: String,
+    /// Full `describe > it` path.
+    pub path: String,
+    pub status: TestStatus,
+    /// Wall time including hooks and retries. Zero for tests that didn't run.
+    pub duration: Duration,
+    /// Times the body was attempted; more than 1 with retries or
+    /// `must_pass_repeatedly`, 0 if it never ran.
+    pub attempts: u32,
+    /// Failure message, or the reason a test was skipped.
+    pub message: Option<String>,
+    /// Effective owner (see [`ItBuilder::owner`](crate::ItBuilder::owner)).
+    pub owner: Option<String>,
+    /// Artifacts from [`attach`](crate::attach). Empty for tests that
+    /// passed unless the run used `--keep-artifacts`.
+    pub attachments: Vec<Attachment>,
+    /// What a failed (or flaky) test printed, with `--capture`.
+    pub output: Option<String>,
+    /// [`by`](crate::by) steps the test took, across all attempts.
+    pub steps: u32,
+    /// Assertions it counted with [`record_assertion`](crate::record_assertion)
+    /// (rsspec's own assertion helpers included).
+    pub assertions: u32,
+    /// How each step of an [`ordered`](crate::Context::ordered) block went,
+    /// in declaration order. Empty for `it` tests and blocks that didn't run.
+    pub ordered_steps: Vec<StepResult>,
+}
+
+/// The outcome of one step of an ordered block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepResult {
+    pub name: String,
+    /// `Passed` or `Failed` for steps that ran, `Pending` for pending steps,
+    /// and `Skipped` for steps left out by a focused sibling or not reached
+    /// after a failure.
+    pub status: TestStatus,
+    /// The panic message of a failed step, or why a step was skipped.
+    pub message: Option<String>,
+}
+
+impl ResultTree {
+    /// Shape `records` (from one run of `nodes`) after the tree that produced them.
+    pub(crate) fn build(nodes: &[TestNode], records: &[TestRecord]) -> Self {
+        let mut by_path: HashMap<&str, VecDeque<&TestRecord>> = HashMap::new();
+        for record in records {
+            by_path.entry(&record.path).or_default().push_back(record);
+        }
+        ResultTree {
+            children: build_nodes(nodes, &[], &mut by_path),
+        }
+    }
+
+    /// All tests in the tree, depth-first.
+    pub fn tests(&self) -> Vec<&TestResult> {
+        let mut out = Vec::new();
+        collect_tests(&self.children, &mut out);
+        out
+    }
+}
+
+fn build_nodes(
+    nodes: &[TestNode],
+    path: &[String],
+    by_path: &mut HashMap<&str, VecDeque<&TestRecord>>,
+) -> Vec<ResultNode> {
+    let mut out = Vec::new();
+    for node in nodes {
+        let mut node_path = path.to_vec();
+        match node {
+            TestNode::Describe { name, children, .. } => {
+                node_path.push(name.clone());
+                let children = build_nodes(children, &node_path, by_path);
+                if !children.is_empty() {
+                    out.push(ResultNode::Describe {
+                        name: name.clone(),
+                        children,
+                    });
+                }
+            }
+            TestNode::It { name, .. } | TestNode::Ordered { name, .. } => {
+                node_path.push(name.clone());
+                let full_path = node_path.join(" > ");
+                let record = by_path
+                    .get_mut(full_path.as_str())
+                    .and_then(|records| records.pop_front());
+                if let Some(record) = record {
+                    out.push(ResultNode::Test(TestResult {
+                        name: name.clone(),
+                        path: full_path,
+                        status: record.status,
+                        duration: record.duration,
+                        attempts: record.attempts,
+                        message: record.message.clone(),
+                        owner: record.owner.clone(),
+                        attachments: record.attachments.clone(),
+                        output: record.output.clone(),
+                        steps: record.steps,
+                        assertions: record.assertions,
+                        ordered_steps: record.ordered_steps.clone(),
+                    }));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn collect_tests<'a>(nodes: &'a [ResultNode], out: &mut Vec<&'a TestResult>) {
+    for node in nodes {
+        match node {
+            ResultNode::Describe { children, .. } => collect_tests(children, out),
+            ResultNode::Test(test) => out.push(test),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_tree_result_mirrors_describe_structure() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            ctx.describe("math", |ctx| {
+                ctx.it("adds", || assert_eq!(1 + 1, 2));
+                ctx.it("fails", || panic!("boom"));
+                ctx.xit("later", || {});
+            });
+            ctx.it("top level", || {}).retries(2);
+        });
+
+        let [ResultNode::Describe { name, children }, ResultNode::Test(top)] =
+            tree.children.as_slice()
+        else {
+            panic!("unexpected shape: {tree:?}");
+        };
+        assert_eq!(name, "math");
+        assert_eq!(children.len(), 3);
+        assert_eq!(top.path, "top level");
+        assert_eq!(top.attempts, 1);
+
+        let statuses: Vec<_> = tree.tests().iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                TestStatus::Passed,
+                TestStatus::Failed,
+                TestStatus::Pending,
+                TestStatus::Passed
+            ]
+        );
+        let failed = tree.tests()[1];
+        assert_eq!(failed.path, "math > fails");
+        assert_eq!(failed.message.as_deref(), Some("boom"));
+    }
+}
@@ -10,27 +10,98 @@
 //!     ✗ fails on overflow
 //! ```
 
-use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::panic::{catch_unwind, AssertUnwindSafe, Location};
+use std::sync::Arc;
 use std::time::Instant;
 
+use crate::artifacts::Attachment;
+use crate::capture::Capture;
+use crate::dependencies::Dependencies;
+use crate::diagnostics::diag;
+use crate::filter::{parse_label_filter, LabelFilter};
+use crate::parallel::{Worker, WorkerUnit, WORKER_FLAG};
+use crate::reporter::{Reporter, RunSummary};
+use crate::result_tree::{StepResult, TestResult};
+use crate::shuffle::{OrderStrategy, ShuffleOverride};
+use crate::spy::HookPhase;
+use crate::style::{bold, dim, green, red, yellow};
+
 // ============================================================================
 // Test tree types
 // ============================================================================
 
+/// A test body.
+pub enum TestFn {
+    /// Runs on the runner's thread.
+    Local(Box<dyn Fn()>),
+    /// Registered with [`Context::it_send`](crate::Context::it_send). Under a
+    /// `timeout` it runs on a worker thread that is abandoned if it hangs.
+    Send(Arc<dyn Fn() + Send + Sync>),
+}
+
+/// How a test's `timeout` applies when its body runs more than once
+/// (retries, `must_pass_repeatedly`, `repeat`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeoutMode {
+    /// One deadline for all runs together; a timeout is final.
+    #[default]
+    Total,
+    /// A fresh deadline for each run, so a run that times out is retried
+    /// like any other failure.
+    PerIteration,
+}
+
 /// A step in an ordered test sequence.
-pub(crate) struct OrderedStep {
+#[non_exhaustive]
+pub struct OrderedStep {
     pub name: String,
     pub body: Box<dyn Fn()>,
+    /// When any step of a sequence is focused, only focused steps run.
+    pub focused: bool,
+    /// Logged and passed over without running or failing the sequence.
+    pub pending: bool,
+    /// Undoes the step if a later step fails (LIFO, completed steps only).
+    pub cleanup: Option<Box<dyn Fn()>>,
+}
+
+impl OrderedStep {
+    /// A step running `body`, for [`TestNode::ordered`].
+    pub fn new(name: impl Into<String>, body: impl Fn() + 'static) -> Self {
+        OrderedStep {
+            name: name.into(),
+            body: Box::new(body),
+            focused: false,
+            pending: false,
+            cleanup: None,
+        }
+    }
 }
 
-/// A node in the BDD test tree.
-pub(crate) enum TestNode {
+/// A node in the BDD test tree, as built by [`Context`](crate::Context).
+///
+/// To assemble a tree by hand for a [`Suite`], start from the constructors
+/// ([`describe`](Self::describe), [`it`](Self::it), ...). Variants are
+/// `#[non_exhaustive]`, so they can gain fields without breaking callers
+/// that match on them.
+pub enum TestNode {
     /// A describe/context/when container.
+    #[non_exhaustive]
     Describe {
         name: String,
         focused: bool,
         pending: bool,
+        allow_focus: bool,
         labels: Vec<String>,
+        /// `labels` replace the inherited ones instead of adding to them.
+        replace_labels: bool,
+        /// Owning team, inherited by descendants that don't set their own.
+        owner: Option<String>,
+        /// Shared resources the scope's tests mutate; see `It::resources`.
+        resources: Vec<String>,
+        /// Sibling order for this subtree, if it overrides the run's.
+        shuffle: Option<ShuffleOverride>,
         before_each: Vec<Box<dyn Fn()>>,
         after_each: Vec<Box<dyn Fn()>>,
         before_all: Vec<Box<dyn Fn()>>,
@@ -39,33 +110,65 @@ pub(crate) enum TestNode {
         children: Vec<TestNode>,
     },
     /// An individual test case.
+    #[non_exhaustive]
     It {
         name: String,
         focused: bool,
         pending: bool,
         labels: Vec<String>,
         retries: Option<u32>,
+        /// Base delay before a retry, doubled after each failed attempt.
+        retry_backoff_ms: Option<u64>,
         timeout_ms: Option<u64>,
+        max_duration_ms: Option<u64>,
+        max_rss_mb: Option<u64>,
+        timeout_mode: TimeoutMode,
         must_pass_repeatedly: Option<u32>,
-        test_fn: Box<dyn Fn()>,
+        /// Run the body this many times, failing with how many runs failed.
+        repeat: Option<u32>,
+        flaky: bool,
+        xfail: Option<String>,
+        /// Set by a `skip_if` whose condition held: report the test as
+        /// skipped with this reason instead of running it.
+        skip_reason: Option<String>,
+        allow_focus: bool,
+        depends_on: Vec<String>,
+        owner: Option<String>,
+        /// Named resources (a database, a port) the test mutates. `--jobs`
+        /// never runs two workers whose tests share one at the same time.
+        resources: Vec<String>,
+        /// Where the test was defined, shown with its failure.
+        location: Option<&'static Location<'static>>,
+        test_fn: TestFn,
     },
     /// An ordered sequence of steps that run as a single test.
+    #[non_exhaustive]
     Ordered {
         name: String,
         labels: Vec<String>,
         continue_on_failure: bool,
+        /// With `continue_on_failure`, how many steps may fail before the
+        /// sequence does.
+        tolerate: usize,
         steps: Vec<OrderedStep>,
+        /// Rollback actions, run in reverse if a step fails.
+        on_failure: Vec<Box<dyn Fn()>>,
     },
 }
 
-#[cfg(test)]
 impl TestNode {
-    fn describe(name: impl Into<String>, children: Vec<TestNode>) -> Self {
+    /// A describe with no hooks around `children`.
+    pub fn describe(name: impl Into<String>, children: Vec<TestNode>) -> Self {
         TestNode::Describe {
             name: name.into(),
             focused: false,
             pending: false,
+            allow_focus: false,
             labels: Vec::new(),
+            replace_labels: false,
+            owner: None,
+            shuffle: None,
+            resources: Vec::new(),
             before_each: Vec::new(),
             after_each: Vec::new(),
             before_all: Vec::new(),
@@ -75,7 +178,8 @@ impl TestNode {
         }
     }
 
-    fn describe_with_hooks(
+    /// A describe with `before_all` and `after_all` hooks around `children`.
+    pub fn describe_with_hooks(
         name: impl Into<String>,
         before_all: Vec<Box<dyn Fn()>>,
         after_all: Vec<Box<dyn Fn()>>,
@@ -85,7 +189,12 @@ impl TestNode {
             name: name.into(),
             focused: false,
             pending: false,
+            allow_focus: false,
             labels: Vec::new(),
+            replace_labels: false,
+            owner: None,
+            shuffle: None,
+            resources: Vec::new(),
             before_each: Vec::new(),
             after_each: Vec::new(),
             before_all,
@@ -95,7 +204,8 @@ impl TestNode {
         }
     }
 
-    fn describe_with_each_hooks(
+    /// A describe with `before_each` and `after_each` hooks around `children`.
+    pub fn describe_with_each_hooks(
         name: impl Into<String>,
         before_each: Vec<Box<dyn Fn()>>,
         after_each: Vec<Box<dyn Fn()>>,
@@ -105,7 +215,12 @@ impl TestNode {
             name: name.into(),
             focused: false,
             pending: false,
+            allow_focus: false,
             labels: Vec::new(),
+            replace_labels: false,
+            owner: None,
+            shuffle: None,
+            resources: Vec::new(),
             before_each,
             after_each,
             before_all: Vec::new(),
@@ -115,29 +230,72 @@ impl TestNode {
         }
     }
 
-    fn it(name: impl Into<String>, f: impl Fn() + 'static) -> Self {
+    /// A test running `f`.
+    #[track_caller]
+    pub fn it(name: impl Into<String>, f: impl Fn() + 'static) -> Self {
         TestNode::It {
             name: name.into(),
             focused: false,
             pending: false,
             labels: Vec::new(),
             retries: None,
+            retry_backoff_ms: None,
             timeout_ms: None,
+            max_duration_ms: None,
+            max_rss_mb: None,
+            timeout_mode: TimeoutMode::Total,
             must_pass_repeatedly: None,
-            test_fn: Box::new(f),
+            repeat: None,
+            flaky: false,
+            xfail: None,
+            skip_reason: None,
+            allow_focus: false,
+            depends_on: Vec::new(),
+            owner: None,
+            resources: Vec::new(),
+            location: Some(Location::caller()),
+            test_fn: TestFn::Local(Box::new(f)),
         }
     }
 
-    fn fit(name: impl Into<String>, f: impl Fn() + 'static) -> Self {
+    /// A focused test running `f`, like [`Context::fit`](crate::Context::fit).
+    #[track_caller]
+    pub fn fit(name: impl Into<String>, f: impl Fn() + 'static) -> Self {
         TestNode::It {
             name: name.into(),
             focused: true,
             pending: false,
             labels: Vec::new(),
             retries: None,
+            retry_backoff_ms: None,
             timeout_ms: None,
+            max_duration_ms: None,
+            max_rss_mb: None,
+            timeout_mode: TimeoutMode::Total,
             must_pass_repeatedly: None,
-            test_fn: Box::new(f),
+            repeat: None,
+            flaky: false,
+            xfail: None,
+            skip_reason: None,
+            allow_focus: false,
+            depends_on: Vec::new(),
+            owner: None,
+            resources: Vec::new(),
+            location: Some(Location::caller()),
+            test_fn: TestFn::Local(Box::new(f)),
+        }
+    }
+
+    /// An ordered sequence running `steps` as one test, stopping at the
+    /// first failure.
+    pub fn ordered(name: impl Into<String>, steps: Vec<OrderedStep>) -> Self {
+        TestNode::Ordered {
+            name: name.into(),
+            labels: Vec::new(),
+            continue_on_failure: false,
+            tolerate: 0,
+            steps,
+            on_failure: Vec::new(),
         }
     }
 }
@@ -147,13 +305,33 @@ impl TestNode {
 /// Must be called with `&*e` (not `&e`) when `e: Box<dyn Any + Send>`,
 /// because `&Box<dyn Any>` coerces to a trait object for the Box itself
 /// rather than deref-ing through to the inner type.
-fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+///
+/// Besides string payloads, boxed errors and types registered via
+/// [`register_panic_payload`](crate::register_panic_payload) are rendered
+/// with their `Display` impl.
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
     if let Some(s) = payload.downcast_ref::<&str>() {
         s.to_string()
     } else if let Some(s) = payload.downcast_ref::<String>() {
         s.clone()
+    } else if let Some(e) = payload.downcast_ref::<Box<dyn std::error::Error + Send + Sync>>() {
+        e.to_string()
+    } else if let Some(e) = payload.downcast_ref::<Box<dyn std::error::Error + Send>>() {
+        e.to_string()
+    } else if let Some(msg) = crate::describe_registered_payload(payload) {
+        msg
     } else {
-        "unknown panic".to_string()
+        // `Any` can't name a payload's type, only those it was checked against.
+        let registered = crate::registered_payload_types();
+        let checked = if registered.is_empty() {
+            String::new()
+        } else {
+            format!(" (registered: {})", registered.join(", "))
+        };
+        format!(
+            "unknown panic payload of an unregistered type{checked}; register its type \
+             with rsspec::register_panic_payload to see its message"
+        )
     }
 }
 
@@ -167,6 +345,16 @@ struct HookChain<'a> {
     after_each: Vec<&'a dyn Fn()>,
     just_before_each: Vec<&'a dyn Fn()>,
     labels: Vec<&'a str>,
+    /// Set when the focus inherited from ancestor describes is exempt from
+    /// the fail-on-focus check: the innermost focused ancestor, or one of
+    /// its descendants, called `allow_focus`.
+    allow_focus: bool,
+    /// Owner of the innermost describe that set one.
+    owner: Option<&'a str>,
+    /// Sibling order override of the innermost describe that set one.
+    shuffle: Option<ShuffleOverride>,
+    /// The suite's resolved `depends_on` declarations.
+    dependencies: Option<&'a Dependencies>,
 }
 
 impl<'a> HookChain<'a> {
@@ -176,10 +364,29 @@ impl<'a> HookChain<'a> {
             after_each,
             just_before_each,
             labels,
+            replace_labels,
+            focused,
+            allow_focus,
+            owner,
+            shuffle,
             ..
         } = node
         {
             let mut chain = self.clone();
+            chain.shuffle = shuffle.or(chain.shuffle);
+            if *replace_labels {
+                chain.labels.clear();
+            }
+            // A nested `fdescribe` is focus of its own, exempt only if it
+            // opts out itself.
+            if *focused {
+                chain.allow_focus = *allow_focus;
+            } else {
+                chain.allow_focus |= *allow_focus;
+            }
+            if let Some(owner) = owner {
+                chain.owner = Some(owner.as_str());
+            }
             for hook in before_each {
                 chain.before_each.push(hook.as_ref());
             }
@@ -200,78 +407,381 @@ impl<'a> HookChain<'a> {
 }
 
 // ============================================================================
-// ANSI color helpers
+// Runner
 // ============================================================================
 
-fn use_color() -> bool {
-    if std::env::var("NO_COLOR").is_ok() {
-        return false;
-    }
-    std::io::IsTerminal::is_terminal(&std::io::stdout())
+/// Totals and failures of a finished run, returned by
+/// [`run_and_report`](crate::run_and_report).
+#[derive(Debug, Default)]
+pub struct RunResult {
+    /// Tests that passed, including `flaky` tests that passed on a retry.
+    pub passed: usize,
+    /// Failed tests plus failed scope hooks.
+    pub failed: usize,
+    /// Tests marked pending (`xit` and the like), or that called `pending!`.
+    pub pending: usize,
+    /// Tests that didn't run or stopped early for another reason: `skip!`,
+    /// `skip_if`, `skip_scope`, focus elsewhere, a failed `before_all` or
+    /// dependency, or `--dry-run`.
+    pub skipped: usize,
+    /// Flaky tests that still failed after their retries. Not counted in `failed`.
+    pub flaky: usize,
+    /// Tests marked `xfail` that failed as expected.
+    pub xfailed: usize,
+    /// Test and hook failures, in the order they happened.
+    pub failures: Vec<Failure>,
+    /// Quarantined failures of `flaky` tests.
+    pub flaky_failures: Vec<Failure>,
+    /// Paths of tests that passed without a counted assertion, collected
+    /// when `RSSPEC_WARN_NO_ASSERT` is set.
+    pub no_assertions: Vec<String>,
+    /// `before_all`/`after_all` times of the describes that have them.
+    pub scope_timings: Vec<ScopeTiming>,
+    /// Tests a `--dry-run` selected. They're recorded as skipped.
+    pub would_run: usize,
+    /// Final status of every test reached by the run, in run order. See
+    /// [`tests`](Self::tests) for the public view.
+    pub(crate) tests: Vec<TestRecord>,
+    /// The run stopped early because `--bail` was reached. Reporters see it
+    /// as [`RunSummary::bailed`].
+    pub(crate) bailed: bool,
+    /// How many of `tests` have been emitted as JSON events.
+    reported: usize,
 }
 
-fn green(s: &str) -> String {
-    if use_color() {
-        format!("\x1b[32m{s}\x1b[0m")
-    } else {
-        s.to_string()
-    }
+/// Final status of a single test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    /// Failed after its retries but marked `flaky`.
+    Flaky,
+    /// Failed as expected by `xfail`.
+    XFailed,
+    Skipped,
+    Pending,
 }
 
-fn red(s: &str) -> String {
-    if use_color() {
-        format!("\x1b[31m{s}\x1b[0m")
-    } else {
-        s.to_string()
-    }
+/// The outcome of one test, as recorded in [`RunResult::tests`].
+#[derive(Clone, Debug)]
+pub(crate) struct TestRecord {
+    /// Full `describe > it` path.
+    pub path: String,
+    pub status: TestStatus,
+    /// Wall time including hooks and retries. Zero for tests that didn't run.
+    pub duration: std::time::Duration,
+    /// Times the body was attempted (retries, `must_pass_repeatedly`).
+    pub attempts: u32,
+    /// Failure message or skip reason.
+    pub message: Option<String>,
+    /// Effective owner, for tests that ran.
+    pub owner: Option<String>,
+    /// Artifacts from [`attach`](crate::attach), kept for failed tests.
+    pub attachments: Vec<Attachment>,
+    /// Captured stdout and stderr, kept for failed tests under `--capture`.
+    pub output: Option<String>,
+    /// [`by`](crate::by) steps taken, across all attempts.
+    pub steps: u32,
+    /// Assertions counted with [`record_assertion`](crate::record_assertion).
+    pub assertions: u32,
+    /// Step outcomes of an ordered block that ran.
+    pub ordered_steps: Vec<StepResult>,
 }
 
-fn yellow(s: &str) -> String {
-    if use_color() {
-        format!("\x1b[33m{s}\x1b[0m")
-    } else {
-        s.to_string()
+impl RunResult {
+    /// Every test reached by the run, in run order.
+    pub fn tests(&self) -> Vec<TestResult> {
+        self.tests.iter().map(crate::reporter::test_result).collect()
+    }
+
+    /// Record a test's final status and bump the matching counter. Returns
+    /// the record so callers can attach details.
+    pub(crate) fn record(&mut self, path: &str, status: TestStatus) -> &mut TestRecord {
+        match status {
+            TestStatus::Passed => self.passed += 1,
+            TestStatus::Failed => self.failed += 1,
+            TestStatus::Flaky => self.flaky += 1,
+            TestStatus::XFailed => self.xfailed += 1,
+            TestStatus::Skipped => self.skipped += 1,
+            TestStatus::Pending => self.pending += 1,
+        }
+        self.tests.push(TestRecord {
+            path: path.to_string(),
+            status,
+            duration: std::time::Duration::ZERO,
+            attempts: 0,
+            message: None,
+            owner: None,
+            attachments: Vec::new(),
+            output: None,
+            steps: 0,
+            assertions: 0,
+            ordered_steps: Vec::new(),
+        });
+        self.tests.last_mut().unwrap()
+    }
+
+    /// Status of the most recent test whose full path is `path`.
+    fn status_of(&self, path: &str) -> Option<TestStatus> {
+        self.tests.iter().rev().find(|t| t.path == path).map(|t| t.status)
     }
 }
 
-fn bold(s: &str) -> String {
-    if use_color() {
-        format!("\x1b[1m{s}\x1b[0m")
-    } else {
-        s.to_string()
+/// A single failure recorded during a run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// Full `describe > it` path. Scope-level hook failures carry a suffix
+    /// such as ` (before_all)`.
+    pub path: String,
+    /// The panic message.
+    pub message: String,
+    /// Where the failing test is defined, as `file:line`.
+    pub location: Option<String>,
+    /// Where the failing panic was raised, when `RUST_BACKTRACE` (or
+    /// `RUST_LIB_BACKTRACE`) enables backtraces.
+    pub backtrace: Option<String>,
+}
+
+/// How long a describe's `before_all` and `after_all` hooks took, and how
+/// many tests they were shared by. Printed in the summary with `--timing`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScopeTiming {
+    /// Full `describe > ...` path.
+    pub path: String,
+    pub before_all: std::time::Duration,
+    pub after_all: std::time::Duration,
+    /// Tests in the scope that ran, not counting skipped or pending ones.
+    pub tests: usize,
+}
+
+impl Failure {
+    pub(crate) fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Failure {
+            path: path.into(),
+            message: message.into(),
+            location: None,
+            backtrace: None,
+        }
     }
 }
 
-fn dim(s: &str) -> String {
-    if use_color() {
-        format!("\x1b[2m{s}\x1b[0m")
-    } else {
-        s.to_string()
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
     }
 }
 
-// ============================================================================
-// Runner
-// ============================================================================
+/// Retries applied to a `flaky` test that doesn't set `retries` explicitly.
+pub(crate) const FLAKY_DEFAULT_RETRIES: u32 = 3;
 
-/// Results from running a test tree.
-#[derive(Default)]
-pub(crate) struct RunResult {
-    pub passed: usize,
-    pub failed: usize,
-    pub pending: usize,
-    pub skipped: usize,
-    pub failures: Vec<String>,
-}
+/// Message of the tests skipped because an enclosing `before_all` panicked.
+pub(crate) const BEFORE_ALL_FAILED: &str = "before_all failed";
 
-/// Configuration parsed from command-line args.
-pub(crate) struct RunConfig {
+/// How to run suites with [`run_suites`]: filters, order, output and exit
+/// codes.
+///
+/// Usually parsed from the command line with [`from_args`](Self::from_args).
+/// To configure a run in code, start from `RunConfig::default()` and chain
+/// the builder methods, or pass flags to [`parse_args`](Self::parse_args)
+/// for options without one.
+///
+/// ```rust
+/// use rsspec::{RunConfig, Suite, TestNode};
+///
+/// let suites = [Suite::new("math", vec![TestNode::it("adds", || assert_eq!(1 + 1, 2))])];
+/// let config = RunConfig::default().filter("adds").shuffle().seed(7);
+/// let result = rsspec::run_suites(&suites, &config, &mut *config.reporter());
+/// assert_eq!(result.failed, 0);
+/// ```
+#[derive(Default, Clone)]
+pub struct RunConfig {
     /// Filter string — only run tests whose full path contains this.
-    pub filter: Option<String>,
+    pub(crate) filter: Option<String>,
     /// Only list tests, don't run them.
-    pub list: bool,
+    pub(crate) list: bool,
+    /// Print the selected tests as JSON lines instead of running them.
+    pub(crate) plan_dump: bool,
+    /// Walk the tree with every filter applied but run no tests or hooks.
+    pub(crate) dry_run: bool,
+    /// Hold back each test's stdout/stderr and show it only if it fails.
+    pub(crate) capture: bool,
+    /// `filter` must equal a test's full path instead of being contained in it.
+    pub(crate) exact: bool,
+    /// Print one character per test instead of the tree.
+    pub(crate) quiet: bool,
+    /// Print the effective configuration to stderr before running.
+    pub(crate) show_config: bool,
+    /// Print `##RSSPEC_TEST_BEGIN path##`/`##RSSPEC_TEST_END path##` around
+    /// each test body, for per-test coverage slicing.
+    pub(crate) coverage_markers: bool,
+    /// Print aggregate test counts (`--list-tags`/`--list-owners`) instead of running.
+    pub(crate) inventory: Option<InventoryView>,
     /// Include ignored/pending tests in the run.
-    pub include_ignored: bool,
+    pub(crate) include_ignored: bool,
+    /// Order in which `run_suites` executes suites.
+    pub(crate) order: SuiteOrder,
+    /// Keep the process alive after the run and re-run on each stdin line.
+    pub(crate) watch: bool,
+    /// Group failures with identical messages in the summary.
+    pub(crate) dedupe_failures: bool,
+    /// List each describe's `before_all`/`after_all` time in the summary.
+    pub(crate) timing: bool,
+    /// Show each test's `by` step and assertion counts next to it.
+    pub(crate) show_steps: bool,
+    /// Timeout for tests and ordered blocks that don't set their own
+    /// (`--default-timeout`, `RSSPEC_DEFAULT_TIMEOUT_MS`). Like a test's own
+    /// timeout, it only abandons `it_send` bodies; anything else is checked
+    /// once it returns.
+    pub(crate) default_timeout_ms: Option<u64>,
+    /// Fail tests that pass without a counted assertion.
+    pub(crate) strict_assertions: bool,
+    /// Report `flaky` tests that still fail after their retries as failed.
+    pub(crate) strict_flaky: bool,
+    /// Discard retry notices, steps and other [`crate::diagnostics`] output.
+    pub(crate) no_diagnostics: bool,
+    /// Run only the test at this 1-based position in the run order.
+    pub(crate) at: Option<usize>,
+    /// Randomize the order of sibling tests and describes.
+    pub(crate) shuffle: bool,
+    /// Seed for `shuffle`. Chosen at random (and printed) when unset.
+    pub(crate) seed: Option<u64>,
+    /// Run siblings in reverse declaration order. Exclusive with `shuffle`.
+    pub(crate) reverse: bool,
+    /// Replaces the default seeded Fisher-Yates shuffle.
+    order_strategy: Option<OrderStrategy>,
+    /// Extra summary line emitted for log-scraping tools.
+    pub(crate) summary_format: SummaryFormat,
+    /// Colored tree (default), a JSON event stream, or a TAP document on stdout.
+    pub(crate) output_format: OutputFormat,
+    /// Write a JUnit XML report of the run to this path.
+    pub(crate) junit: Option<String>,
+    /// Write `target/rsspec_rerun.sh` re-running the failed tests.
+    pub(crate) write_rerun: bool,
+    /// Where [`attach`](crate::attach) saves artifact bytes.
+    pub(crate) artifact_dir: Option<String>,
+    /// Keep artifacts of tests that passed, not just failed ones.
+    pub(crate) keep_artifacts: bool,
+    /// Process exit code when tests failed. Defaults to 1.
+    pub(crate) fail_exit_code: Option<i32>,
+    /// Exit code when the only failures are setup or precondition failures
+    /// (`before_all`/`after_all`, bad `--at`/`--plan`), not tests. Defaults
+    /// to the fail exit code.
+    pub(crate) setup_exit_code: Option<i32>,
+    /// `--focus` patterns: when any are given, only tests whose full path
+    /// matches one of them run.
+    pub(crate) focus_patterns: Vec<PathPattern>,
+    /// `--skip` patterns: tests whose full path matches one are left out,
+    /// after `--focus` has selected.
+    pub(crate) skip_patterns: Vec<PathPattern>,
+    /// Path substrings read from `--focus-file`. Matching tests are treated
+    /// as focused, like `fit`.
+    pub(crate) focus_paths: Vec<String>,
+    /// Only run tests owned by this team (comma-separated for several).
+    pub(crate) owner: Option<String>,
+    /// Name of a [`Plan`] to run, selected with `--plan`.
+    pub(crate) plan: Option<String>,
+    /// Terms of the selected plan, resolved by `run_suites`.
+    plan_terms: Option<Vec<String>>,
+    /// `RSSPEC_LABEL_FILTER`, parsed once by `run_suites`.
+    label_filter: Option<Arc<LabelFilter>>,
+    /// Exact full path to run, resolved from `at` by `run_suites`.
+    only_path: Option<String>,
+    /// `depends_on` dependencies of the selected tests that the selection
+    /// left out, resolved by `run_suites`. They run anyway.
+    required_paths: Vec<String>,
+    /// Run tests with a `max_rss_mb` limit in a memory-limited child process.
+    /// Only set for `harness = false` runs on supported platforms.
+    isolate_memory_limits: bool,
+    /// Set by `--jobs` without a count, which `RUST_TEST_THREADS` may then
+    /// supply in place of the core count.
+    bare_jobs: bool,
+    /// Run top-level describes in up to this many worker processes. A
+    /// top-level node is the unit of work: its tests run in order in one
+    /// worker, and the suite's top-level hooks run once per worker.
+    pub(crate) jobs: Option<usize>,
+    /// Stop scheduling tests after this many have failed.
+    pub(crate) bail: Option<usize>,
+    /// Set in a `--jobs` worker: the top-level node (with its `depends_on`
+    /// group) it runs.
+    worker: Option<WorkerUnit>,
+    /// The arguments this config was parsed from, passed on to workers.
+    args: Vec<String>,
+}
+
+/// How `run_suites` orders suites before executing them.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SuiteOrder {
+    /// The order the suites were passed in.
+    #[default]
+    Declared,
+    /// Alphabetically by suite name.
+    Name,
+    /// By source file (see [`Suite::file`]), then declared order within a file.
+    /// Suites without a file run last.
+    File,
+}
+
+impl SuiteOrder {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "declared" => Some(SuiteOrder::Declared),
+            "name" => Some(SuiteOrder::Name),
+            "file" => Some(SuiteOrder::File),
+            _ => None,
+        }
+    }
+}
+
+/// Breakdown printed by an inventory run, after the totals.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum InventoryView {
+    /// Test counts per label (`--list-tags`).
+    Tags,
+    /// Test counts per owner (`--list-owners`).
+    Owners,
+}
+
+/// What the runner prints to stdout while tests run.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum OutputFormat {
+    /// The colored describe/it tree and summary.
+    #[default]
+    Pretty,
+    /// One JSON object per event (see [`crate::json`]) and a final summary object.
+    Json,
+    /// A TAP version 13 document (see [`crate::tap`]).
+    Tap,
+}
+
+impl OutputFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pretty" => Some(OutputFormat::Pretty),
+            "json" => Some(OutputFormat::Json),
+            "tap" => Some(OutputFormat::Tap),
+            _ => None,
+        }
+    }
+}
+
+/// Shape of the final summary output.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SummaryFormat {
+    /// The `PASS`/`FAIL` block only.
+    #[default]
+    Default,
+    /// Also end with a cargo-nextest style `Summary [...] N tests run: ...` line.
+    Nextest,
+}
+
+impl SummaryFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "default" => Some(SummaryFormat::Default),
+            "nextest" => Some(SummaryFormat::Nextest),
+            _ => None,
+        }
+    }
 }
 
 /// Args that are exclusively used by libtest (cargo test's built-in harness).
@@ -291,9 +801,17 @@ const LIBTEST_ONLY_ARGS: &[&str] = &[
 /// Check if a list of CLI args contains libtest-specific arguments.
 ///
 /// Returns `Some(arg)` with the first offending arg if detected, `None` otherwise.
+///
+/// `--test-threads` is also accepted by `harness = false` binaries, so it only
+/// counts off the main thread: libtest always runs a test on a thread of its
+/// own, named after the test.
 pub(crate) fn detect_libtest_args(args: &[String]) -> Option<String> {
+    let on_main_thread = std::thread::current().name() == Some("main");
     for arg in args {
         let arg_name = arg.split('=').next().unwrap_or(arg);
+        if arg_name == "--test-threads" && on_main_thread {
+            continue;
+        }
         if LIBTEST_ONLY_ARGS.contains(&arg_name) {
             return Some(arg.clone());
         }
@@ -306,1057 +824,5610 @@ impl RunConfig {
     ///
     /// Only use this for `harness = false` targets. For `#[test]` functions,
     /// `run()` auto-detects the context and skips arg parsing.
-    pub(crate) fn from_args() -> Self {
+    pub fn from_args() -> Self {
         let args: Vec<String> = std::env::args().collect();
-        let mut filter = None;
-        let mut list = false;
-        let mut include_ignored = false;
+        let threads = std::env::var("RUST_TEST_THREADS").ok();
+        let default_timeout = std::env::var("RSSPEC_DEFAULT_TIMEOUT_MS").ok();
+        Self::parse(&args[1..])
+            .test_threads_fallback(threads.as_deref())
+            .default_timeout_fallback(default_timeout.as_deref())
+    }
 
-        let mut i = 1;
-        while i < args.len() {
-            match args[i].as_str() {
-                "--list" => list = true,
-                "--include-ignored" | "--ignored" => include_ignored = true,
-                arg if !arg.starts_with('-') => {
-                    filter = Some(arg.to_string());
-                }
-                _ => {}
+    /// With a bare `--jobs`, take the worker count from `RUST_TEST_THREADS`
+    /// (`threads`) instead of the core count. Without `--jobs` the variable
+    /// is ignored, so a CI setting meant for libtest targets doesn't start
+    /// worker processes.
+    fn test_threads_fallback(mut self, threads: Option<&str>) -> Self {
+        if let (true, Some(v)) = (self.bare_jobs, threads) {
+            match v.parse::<usize>() {
+                Ok(n) if n >= 1 => self.jobs = Some(n),
+                _ => eprintln!("rsspec: invalid RUST_TEST_THREADS '{v}' — expected a number >= 1"),
             }
-            i += 1;
         }
+        self
+    }
 
-        RunConfig {
-            filter,
-            list,
-            include_ignored,
+    /// Use `RSSPEC_DEFAULT_TIMEOUT_MS` (`ms`) when `--default-timeout`
+    /// wasn't given.
+    fn default_timeout_fallback(mut self, ms: Option<&str>) -> Self {
+        if let (None, Some(v)) = (self.default_timeout_ms, ms) {
+            match v.parse::<u64>() {
+                Ok(n) if n >= 1 => self.default_timeout_ms = Some(n),
+                _ => eprintln!(
+                    "rsspec: invalid RSSPEC_DEFAULT_TIMEOUT_MS '{v}' — expected milliseconds >= 1"
+                ),
+            }
         }
+        self
     }
-}
 
-/// A named suite for multi-suite runs.
-pub(crate) struct Suite {
-    pub name: String,
-    pub nodes: Vec<TestNode>,
-}
+    /// Parse from a list of command-line flags, as in the README's
+    /// "Command-Line Options" table. Invalid values are reported on stderr
+    /// and ignored.
+    ///
+    /// ```rust
+    /// let config = rsspec::RunConfig::parse_args(["--shuffle", "--seed", "7", "cart"]);
+    /// ```
+    pub fn parse_args<S: Into<String>>(args: impl IntoIterator<Item = S>) -> Self {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        Self::parse(&args)
+    }
 
-impl Suite {
-    pub fn new(name: impl Into<String>, nodes: Vec<TestNode>) -> Self {
-        Suite {
-            name: name.into(),
-            nodes,
-        }
+    /// Only run tests whose full path contains `filter`.
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_string());
+        self
     }
-}
 
-/// Run a single test tree and print BDD-formatted output.
-#[cfg(test)]
-fn run_tree(nodes: &[TestNode], config: &RunConfig) -> RunResult {
-    let focus_mode = tree_has_focus(nodes);
-    let mut result = RunResult::default();
-    let start = Instant::now();
+    /// Run pending (`xit`) tests too, and ignore focus.
+    pub fn include_ignored(mut self) -> Self {
+        self.include_ignored = true;
+        self
+    }
 
-    if config.list {
-        list_tree(nodes, &[], config);
-        return result;
+    /// Randomize the order of sibling tests and describes, with a random
+    /// seed unless [`seed`](Self::seed) sets one. Replaces
+    /// [`reverse`](Self::reverse).
+    pub fn shuffle(mut self) -> Self {
+        self.shuffle = true;
+        self.reverse = false;
+        self
     }
 
-    println!();
-    let hooks = HookChain::default();
-    run_nodes(nodes, 0, &[], &hooks, focus_mode, false, config, &mut result);
-    print_summary(&result, start.elapsed());
+    /// Seed for [`shuffle`](Self::shuffle), to reproduce an order.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 
-    result
-}
+    /// Run siblings in reverse declaration order. Replaces
+    /// [`shuffle`](Self::shuffle).
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self.shuffle = false;
+        self
+    }
 
-/// Run multiple named suites, printing a header per suite and a combined summary.
-pub(crate) fn run_suites(suites: &[Suite], config: &RunConfig) -> RunResult {
-    let focus_mode = suites.iter().any(|s| tree_has_focus(&s.nodes));
-    let mut result = RunResult::default();
-    let start = Instant::now();
+    /// Only run tests owned by `owner` (comma-separated for several).
+    pub fn owner(mut self, owner: &str) -> Self {
+        self.owner = Some(owner.to_string());
+        self
+    }
 
-    if config.list {
-        for suite in suites {
-            list_tree(&suite.nodes, &[], config);
-        }
-        return result;
+    /// Run the plan registered as `name` with
+    /// [`Context::plan`](crate::Context::plan).
+    pub fn plan(mut self, name: &str) -> Self {
+        self.plan = Some(name.to_string());
+        self
     }
 
-    println!();
+    /// Stop scheduling tests after `failures` have failed.
+    pub fn bail(mut self, failures: usize) -> Self {
+        self.bail = Some(failures.max(1));
+        self
+    }
 
-    for suite in suites {
-        if !suite.name.is_empty() {
-            println!("{}", dim(&format!("--- {} ---", suite.name)));
-            println!();
-        }
+    /// Walk the tree with every filter applied, recording the selected tests
+    /// as skipped, without running any tests or hooks.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
 
-        let hooks = HookChain::default();
-        run_nodes(
-            &suite.nodes,
-            0,
-            &[],
-            &hooks,
-            focus_mode,
-            false,
-            config,
-            &mut result,
-        );
+    /// Hold back each test's stdout and stderr and show it only if it fails.
+    pub fn capture(mut self) -> Self {
+        self.capture = true;
+        self
+    }
 
-        if suites.len() > 1 {
-            println!();
-        }
+    /// List each describe's `before_all`/`after_all` time in the summary.
+    pub fn timing(mut self) -> Self {
+        self.timing = true;
+        self
     }
 
-    print_summary(&result, start.elapsed());
+    /// Write a JUnit XML report of the run to `path`.
+    pub fn junit(mut self, path: &str) -> Self {
+        self.junit = Some(path.to_string());
+        self
+    }
 
-    result
-}
+    /// Parse from an argument list, excluding the program name.
+    fn parse(args: &[String]) -> Self {
+        let mut config = RunConfig {
+            args: args.to_vec(),
+            ..RunConfig::default()
+        };
 
-/// Check if any tests in this subtree will actually execute, considering
-/// focus mode, label filters, path filters, and pending status.
-///
-/// Used to skip `before_all`/`after_all` when all children are filtered out.
-#[allow(clippy::too_many_arguments)]
-fn has_runnable_tests(
-    nodes: &[TestNode],
-    path: &[String],
-    hooks: &HookChain,
-    focus_mode: bool,
-    force_focused: bool,
-    config: &RunConfig,
-) -> bool {
-    for node in nodes {
-        match node {
-            TestNode::Describe {
-                name,
-                focused,
-                pending,
-                children,
-                ..
-            } => {
-                if *pending {
-                    continue;
+        // libtest's `--nocapture` wins over `--capture` wherever it appears.
+        let mut nocapture = false;
+        let mut i = 0;
+        while i < args.len() {
+            // Options taking a value accept both `--opt value` and `--opt=value`.
+            let (flag, inline_value) = match args[i].split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (args[i].as_str(), None),
+            };
+            let mut value = || {
+                inline_value.clone().or_else(|| {
+                    i += 1;
+                    args.get(i).cloned()
+                })
+            };
+
+            match flag {
+                "--list" => config.list = true,
+                "--plan-dump" => config.plan_dump = true,
+                "--dry-run" => config.dry_run = true,
+                "--capture" => config.capture = true,
+                "--nocapture" => nocapture = true,
+                "--exact" => config.exact = true,
+                "-q" | "--quiet" => config.quiet = true,
+                "--coverage-markers" => config.coverage_markers = true,
+                "--show-config" => config.show_config = true,
+                "--list-tags" => config.inventory = Some(InventoryView::Tags),
+                "--list-owners" => config.inventory = Some(InventoryView::Owners),
+                "--include-ignored" | "--ignored" => config.include_ignored = true,
+                "--watch" => config.watch = true,
+                "--dedupe-failures" => config.dedupe_failures = true,
+                "--timing" => config.timing = true,
+                "--show-steps" => config.show_steps = true,
+                "--strict-assertions" => config.strict_assertions = true,
+                "--strict-flaky" => config.strict_flaky = true,
+                "--no-diagnostics" => config.no_diagnostics = true,
+                "--at" => {
+                    let v = value().unwrap_or_default();
+                    match v.parse::<usize>() {
+                        Ok(n) if n >= 1 => config.at = Some(n),
+                        _ => eprintln!("rsspec: invalid --at '{v}' — expected a position >= 1"),
+                    }
                 }
-                let mut child_path = path.to_vec();
-                child_path.push(name.clone());
-                let child_hooks = hooks.with_describe(node);
-                let child_force_focused = force_focused || *focused;
-                if has_runnable_tests(
-                    children,
-                    &child_path,
-                    &child_hooks,
-                    focus_mode,
-                    child_force_focused,
-                    config,
-                ) {
-                    return true;
+                "--shuffle" => config.shuffle = true,
+                "--reverse" => config.reverse = true,
+                "--seed" => {
+                    let v = value().unwrap_or_default();
+                    match v.parse::<u64>() {
+                        Ok(seed) => {
+                            config.shuffle = true;
+                            config.seed = Some(seed);
+                        }
+                        Err(_) => eprintln!("rsspec: invalid --seed '{v}' — expected an integer"),
+                    }
                 }
-            }
-            TestNode::It {
-                name,
-                focused,
-                pending,
-                labels,
-                ..
-            } => {
-                if *pending {
-                    continue;
+                "--fail-exit-code" | "--setup-exit-code" => {
+                    let v = value().unwrap_or_default();
+                    match v.parse::<i32>() {
+                        Ok(code) if flag == "--fail-exit-code" => config.fail_exit_code = Some(code),
+                        Ok(code) => config.setup_exit_code = Some(code),
+                        Err(_) => eprintln!("rsspec: invalid {flag} '{v}' — expected an integer"),
+                    }
                 }
-                let full_path = {
-                    let mut p = path.to_vec();
-                    p.push(name.clone());
-                    p.join(" > ")
-                };
-                if let Some(ref f) = config.filter {
-                    if !full_path.to_lowercase().contains(&f.to_lowercase()) {
-                        continue;
+                "--focus" | "--skip" => {
+                    let v = value().unwrap_or_default();
+                    match PathPattern::new(&v) {
+                        Ok(pattern) if flag == "--focus" => config.focus_patterns.push(pattern),
+                        Ok(pattern) => config.skip_patterns.push(pattern),
+                        Err(e) => eprintln!("rsspec: invalid {flag} '{v}' — {e}"),
                     }
                 }
-                let effectively_focused = *focused || force_focused;
-                if focus_mode && !effectively_focused && !config.include_ignored {
-                    continue;
+                "--focus-file" => {
+                    let file = value().unwrap_or_default();
+                    match std::fs::read_to_string(&file) {
+                        Ok(contents) => config.focus_paths.extend(parse_focus_file(&contents)),
+                        Err(e) => eprintln!("rsspec: cannot read --focus-file '{file}': {e}"),
+                    }
                 }
-                let all_labels: Vec<&str> = hooks
-                    .labels
-                    .iter()
-                    .copied()
-                    .chain(labels.iter().map(|s| s.as_str()))
-                    .collect();
-                if !crate::check_labels(&all_labels) {
-                    continue;
+                "--plan" => config.plan = value(),
+                "--junit" => config.junit = value(),
+                "--write-rerun" => config.write_rerun = true,
+                "--artifact-dir" => config.artifact_dir = value(),
+                "--keep-artifacts" => config.keep_artifacts = true,
+                "--owner" => config.owner = value(),
+                "--summary-format" => {
+                    let v = value().unwrap_or_default();
+                    match SummaryFormat::parse(&v) {
+                        Some(format) => config.summary_format = format,
+                        None => eprintln!(
+                            "rsspec: invalid --summary-format '{v}' — expected default or nextest"
+                        ),
+                    }
                 }
-                return true;
-            }
-            TestNode::Ordered {
-                name, labels, ..
-            } => {
-                let full_path = {
-                    let mut p = path.to_vec();
-                    p.push(name.clone());
-                    p.join(" > ")
-                };
-                if let Some(ref f) = config.filter {
-                    if !full_path.to_lowercase().contains(&f.to_lowercase()) {
-                        continue;
+                "--output-format" => {
+                    let v = value().unwrap_or_default();
+                    match OutputFormat::parse(&v) {
+                        Some(format) => config.output_format = format,
+                        None => eprintln!(
+                            "rsspec: invalid --output-format '{v}' — expected pretty, json, or tap"
+                        ),
                     }
                 }
-                if focus_mode && !force_focused && !config.include_ignored {
-                    continue;
+                "--order" => {
+                    let v = value().unwrap_or_default();
+                    match SuiteOrder::parse(&v) {
+                        Some(order) => config.order = order,
+                        None => eprintln!(
+                            "rsspec: invalid --order '{v}' — expected declared, name, or file"
+                        ),
+                    }
                 }
-                let all_labels: Vec<&str> = hooks
-                    .labels
-                    .iter()
-                    .copied()
-                    .chain(labels.iter().map(|s| s.as_str()))
-                    .collect();
-                if !crate::check_labels(&all_labels) {
-                    continue;
+                // libtest's spelling of `--jobs`, for `cargo test -- --test-threads=N`.
+                "--test-threads" => {
+                    let v = value().unwrap_or_default();
+                    match v.parse::<usize>() {
+                        Ok(n) if n >= 1 => {
+                            config.jobs = Some(n);
+                            config.bare_jobs = false;
+                        }
+                        _ => eprintln!("rsspec: invalid {flag} '{v}' — expected a number >= 1"),
+                    }
+                }
+                "--default-timeout" => {
+                    let v = value().unwrap_or_default();
+                    match v.parse::<u64>() {
+                        Ok(n) if n >= 1 => config.default_timeout_ms = Some(n),
+                        _ => eprintln!("rsspec: invalid {flag} '{v}' — expected milliseconds >= 1"),
+                    }
+                }
+                "--jobs" | "--bail" => {
+                    // The count is optional: a bare `--jobs` uses every
+                    // available core, a bare `--bail` stops at the first failure.
+                    let v = inline_value.clone().or_else(|| {
+                        let next = args.get(i + 1).filter(|a| a.parse::<usize>().is_ok())?;
+                        i += 1;
+                        Some(next.clone())
+                    });
+                    if flag == "--jobs" {
+                        config.bare_jobs = v.is_none();
+                    }
+                    let n = match v {
+                        None if flag == "--bail" => Some(1),
+                        None => Some(std::thread::available_parallelism().map_or(1, |n| n.get())),
+                        Some(v) => match v.parse::<usize>() {
+                            Ok(n) if n >= 1 => Some(n),
+                            _ => {
+                                eprintln!("rsspec: invalid {flag} '{v}' — expected a number >= 1");
+                                None
+                            }
+                        },
+                    };
+                    if flag == "--bail" {
+                        config.bail = n.or(config.bail);
+                    } else {
+                        config.jobs = n.or(config.jobs);
+                    }
+                }
+                WORKER_FLAG => config.worker = value().as_deref().and_then(WorkerUnit::parse),
+                arg if !arg.starts_with('-') => {
+                    config.filter = Some(arg.to_string());
                 }
-                return true;
+                _ => {}
             }
+            i += 1;
+        }
+
+        if config.reverse && config.shuffle {
+            eprintln!("rsspec: --reverse can't be combined with --shuffle — ignoring --reverse");
+            config.reverse = false;
+        }
+        if nocapture {
+            config.capture = false;
         }
+
+        config
     }
-    false
-}
 
-#[allow(clippy::too_many_arguments)]
-fn run_nodes(
-    nodes: &[TestNode],
-    depth: usize,
-    path: &[String],
-    hooks: &HookChain,
-    focus_mode: bool,
-    force_focused: bool,
-    config: &RunConfig,
-    result: &mut RunResult,
-) {
-    for node in nodes {
-        run_node(node, depth, path, hooks, focus_mode, force_focused, config, result);
+    /// Configuration for the current process: the parent of memory-isolated
+    /// tests, or a child spawned to run one of them.
+    pub(crate) fn for_harness_free_process(self) -> Self {
+        match crate::isolate::child_test() {
+            Some(path) => RunConfig {
+                only_path: Some(path),
+                // The parent already applied focus; don't let it skip the test.
+                include_ignored: true,
+                ..RunConfig::default()
+            },
+            None => RunConfig {
+                isolate_memory_limits: crate::isolate::SUPPORTED,
+                ..self
+            },
+        }
     }
-}
-
-#[allow(clippy::too_many_arguments)]
-fn run_node(
-    node: &TestNode,
-    depth: usize,
-    path: &[String],
-    hooks: &HookChain,
-    focus_mode: bool,
-    force_focused: bool,
-    config: &RunConfig,
-    result: &mut RunResult,
-) {
-    match node {
-        TestNode::Describe {
-            name,
-            focused,
-            pending,
-            children,
-            before_all,
-            after_all,
-            ..
-        } => {
-            let indent = "  ".repeat(depth);
-            println!("{indent}{}", bold(name));
 
-            let mut child_path = path.to_vec();
-            child_path.push(name.clone());
+    /// Process exit code for a finished run, or `None` if it passed.
+    ///
+    /// Test failures use `fail_exit_code` (default 1). If every failure came
+    /// from setup rather than a test, `setup_exit_code` is used instead, so CI
+    /// can tell broken infrastructure from broken code.
+    pub(crate) fn exit_code(&self, result: &RunResult) -> Option<i32> {
+        if result.failed == 0 {
+            return None;
+        }
+        let fail_code = self.fail_exit_code.unwrap_or(1);
+        let test_failed = result.tests.iter().any(|t| t.status == TestStatus::Failed);
+        if test_failed {
+            Some(fail_code)
+        } else {
+            Some(self.setup_exit_code.unwrap_or(fail_code))
+        }
+    }
 
-            // If this describe is pending, mark all children as pending
-            if *pending {
-                run_nodes_pending(children, depth + 1, result);
-                return;
-            }
+    /// The built-in reporter for the configured output format: the colored
+    /// tree unless `--output-format` chose JSON or TAP.
+    pub fn reporter(&self) -> Box<dyn Reporter> {
+        match self.output_format {
+            OutputFormat::Pretty => Box::new(TreeReporter::new(self)),
+            OutputFormat::Json => Box::new(crate::json::JsonReporter),
+            OutputFormat::Tap => Box::new(crate::tap::TapReporter),
+        }
+    }
 
-            let child_hooks = hooks.with_describe(node);
-            let child_force_focused = force_focused || *focused;
+    /// Shuffle with a custom strategy instead of the default seeded
+    /// Fisher-Yates. Enables `shuffle`.
+    ///
+    /// `strategy` is called once per group of siblings with their indices
+    /// (`0..n`, in declaration order) and a seed derived from the run seed
+    /// and the group's path, and reorders the indices in place.
+    pub fn order_strategy(mut self, strategy: impl Fn(&mut Vec<usize>, u64) + 'static) -> Self {
+        self.shuffle = true;
+        self.order_strategy = Some(std::rc::Rc::new(strategy));
+        self
+    }
 
-            // Skip before_all/after_all when no children will actually run
-            // (e.g. all filtered by labels or focus mode). This avoids running
-            // expensive setup for nothing.
-            let any_runnable = has_runnable_tests(
-                children,
-                &child_path,
-                &child_hooks,
-                focus_mode,
-                child_force_focused,
-                config,
-            );
-            let has_hooks = !before_all.is_empty() || !after_all.is_empty();
+    /// Order in which to run the siblings `nodes` under `path`: declaration
+    /// order, reversed with `--reverse`, or permuted by the order strategy
+    /// when shuffling, unless the enclosing describes pin it. Siblings
+    /// holding a `depends_on` dependency then move ahead of its dependents.
+    fn sibling_order(&self, nodes: &[TestNode], path: &[String], hooks: &HookChain) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..nodes.len()).collect();
+        let run_seed = match hooks.shuffle {
+            Some(ShuffleOverride::Pinned) => None,
+            Some(ShuffleOverride::Seed(seed)) => Some(seed),
+            None => Some(self.seed.unwrap_or_default()),
+        };
+        if let Some(run_seed) = run_seed {
+            if self.reverse {
+                indices.reverse();
+            } else if self.shuffle && nodes.len() > 1 {
+                let seed = crate::shuffle::group_seed(run_seed, path);
+                match &self.order_strategy {
+                    Some(strategy) => strategy(&mut indices, seed),
+                    None => crate::shuffle::fisher_yates(&mut indices, seed),
+                }
+            }
+        }
+        match hooks.dependencies {
+            Some(dependencies) => dependencies.order(nodes, path, indices),
+            None => indices,
+        }
+    }
 
-            if !any_runnable && has_hooks {
-                // Still recurse children so pending/skipped counts are correct,
-                // but skip the before_all/after_all hooks.
-                run_nodes(
-                    children,
-                    depth + 1,
-                    &child_path,
-                    &child_hooks,
-                    focus_mode,
-                    child_force_focused,
-                    config,
-                    result,
-                );
-                return;
+    /// The configuration a run ended up with after flags, environment
+    /// variables and defaults were resolved, one setting per line.
+    fn effective_config(&self) -> String {
+        fn or_none(value: Option<impl std::fmt::Display>) -> String {
+            value.map_or_else(|| "<none>".to_string(), |v| v.to_string())
+        }
+        fn patterns(list: &[PathPattern]) -> String {
+            if list.is_empty() {
+                return "<none>".to_string();
             }
+            list.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+        }
+        fn env(name: &str) -> String {
+            format!("{name}={}", std::env::var(name).as_deref().unwrap_or("<unset>"))
+        }
 
-            // Run before_all once at scope entry.
-            // If it panics, skip children but still run after_all.
-            let before_all_ok = catch_unwind(AssertUnwindSafe(|| {
-                for hook in before_all {
-                    hook();
-                }
-            }));
+        let order = if self.shuffle {
+            format!("shuffled (seed {})", or_none(self.seed))
+        } else if self.reverse {
+            "reversed".to_string()
+        } else {
+            "declared".to_string()
+        };
+        let plan = match (&self.plan, &self.plan_terms) {
+            (Some(name), Some(terms)) => format!("{name} ({})", terms.join(", ")),
+            (plan, _) => or_none(plan.as_ref()),
+        };
+        let at = match (self.at, &self.only_path) {
+            (Some(n), Some(path)) => format!("{n} ({path})"),
+            (at, _) => or_none(at),
+        };
+        let filter = match &self.filter {
+            Some(filter) if self.exact => format!("{filter} (exact)"),
+            filter => or_none(filter.as_ref()),
+        };
+        let mut output_format = format!("{:?}", self.output_format).to_lowercase();
+        if self.quiet && self.output_format == OutputFormat::Pretty {
+            output_format.push_str(" (quiet)");
+        }
+        let fail_code = self.fail_exit_code.unwrap_or(1);
+        let settings = [
+            ("filter", filter),
+            ("focus", patterns(&self.focus_patterns)),
+            ("skip", patterns(&self.skip_patterns)),
+            ("label filter", env("RSSPEC_LABEL_FILTER")),
+            ("owner", or_none(self.owner.as_ref())),
+            ("plan", plan),
+            ("focus file", format!("{} paths", self.focus_paths.len())),
+            ("at", at),
+            ("include ignored", self.include_ignored.to_string()),
+            ("dry run", self.dry_run.to_string()),
+            ("capture", self.capture.to_string()),
+            ("order", order),
+            ("suite order", format!("{:?}", self.order).to_lowercase()),
+            ("jobs", self.jobs.unwrap_or(1).to_string()),
+            ("bail", or_none(self.bail)),
+            ("output format", output_format),
+            ("summary format", format!("{:?}", self.summary_format).to_lowercase()),
+            ("timing", self.timing.to_string()),
+            ("show steps", self.show_steps.to_string()),
+            ("default timeout", or_none(self.default_timeout_ms.map(|ms| format!("{ms}ms")))),
+            ("strict assertions", self.strict_assertions.to_string()),
+            ("strict flaky", self.strict_flaky.to_string()),
+            ("junit", or_none(self.junit.as_ref())),
+            ("write rerun", self.write_rerun.to_string()),
+            ("artifact dir", or_none(self.artifact_dir.as_ref())),
+            ("keep artifacts", self.keep_artifacts.to_string()),
+            ("fail exit code", fail_code.to_string()),
+            ("setup exit code", self.setup_exit_code.unwrap_or(fail_code).to_string()),
+            ("fail on focus", env("RSSPEC_FAIL_ON_FOCUS")),
+            ("warn no assert", env("RSSPEC_WARN_NO_ASSERT")),
+            ("debug", env("RSSPEC_DEBUG")),
+        ];
+        let mut out = String::from("rsspec: effective configuration\n");
+        for (name, value) in settings {
+            out.push_str(&format!("  {:<16} {value}\n", format!("{name}:")));
+        }
+        out
+    }
 
-            if let Err(e) = &before_all_ok {
-                let msg = panic_message(&**e);
-                let full_path = child_path.join(" > ");
-                println!("{indent}  {} before_all failed: {}", red("✗"), red(&msg));
-                result.failed += 1;
-                result.failures.push(format!("{full_path} (before_all): {msg}"));
+    /// Whether a test's full path passes the path-based selection options.
+    fn selects_path(&self, full_path: &str) -> bool {
+        if let Some(ref f) = self.filter {
+            let matches = if self.exact {
+                full_path == f
             } else {
-                run_nodes(
-                    children,
-                    depth + 1,
-                    &child_path,
-                    &child_hooks,
-                    focus_mode,
-                    child_force_focused,
-                    config,
-                    result,
-                );
+                full_path.to_lowercase().contains(&f.to_lowercase())
+            };
+            if !matches {
+                return false;
+            }
+        }
+        if let Some(ref only) = self.only_path {
+            if full_path != only {
+                return false;
             }
+        }
+        if !self.focus_patterns.is_empty()
+            && !self.focus_patterns.iter().any(|p| p.matches(full_path))
+        {
+            return false;
+        }
+        !self.skip_patterns.iter().any(|p| p.matches(full_path))
+    }
 
-            // Run after_all once at scope exit — even if before_all failed
-            if let Err(e) = catch_unwind(AssertUnwindSafe(|| {
-                for hook in after_all {
-                    hook();
-                }
-            })) {
-                let msg = panic_message(&*e);
-                let full_path = child_path.join(" > ");
-                println!("{indent}  {} after_all failed: {}", red("✗"), red(&msg));
-                result.failed += 1;
-                result.failures.push(format!("{full_path} (after_all): {msg}"));
+    /// Whether `full_path` is a dependency the selection left out, which
+    /// runs regardless of filters, focus and labels.
+    fn requires(&self, full_path: &str) -> bool {
+        self.required_paths.iter().any(|p| p == full_path)
+    }
+
+    /// Whether `full_path` matches a `--focus-file` entry (case-insensitive).
+    fn focus_file_matches(&self, full_path: &str) -> bool {
+        if self.focus_paths.is_empty() {
+            return false;
+        }
+        let path = full_path.to_lowercase();
+        self.focus_paths.iter().any(|p| path.contains(&p.to_lowercase()))
+    }
+
+    /// Whether a test's effective labels and owner pass `RSSPEC_LABEL_FILTER`,
+    /// `--owner`, and the selected plan, if any.
+    fn selects_test(&self, full_path: &str, labels: &[&str], owner: Option<&str>) -> bool {
+        if let Some(filter) = &self.label_filter {
+            if !filter.matches(labels) {
+                return false;
             }
         }
-        TestNode::It {
-            name,
-            focused,
-            pending,
-            labels,
-            retries,
-            timeout_ms,
-            must_pass_repeatedly,
-            test_fn,
-        } => {
-            let indent = "  ".repeat(depth);
-            let full_path = {
-                let mut p = path.to_vec();
-                p.push(name.clone());
-                p.join(" > ")
+        if let Some(wanted) = &self.owner {
+            let Some(owner) = owner else {
+                return false;
             };
-
-            // Filter check
-            if let Some(ref f) = config.filter {
-                if !full_path.to_lowercase().contains(&f.to_lowercase()) {
-                    return;
-                }
+            if !wanted.split(',').any(|w| w.trim() == owner) {
+                return false;
             }
+        }
+        match &self.plan_terms {
+            Some(terms) => plan_matches(terms, full_path, labels),
+            None => true,
+        }
+    }
+}
 
-            // Pending
-            if *pending {
-                println!("{indent}{} {}", yellow("-"), dim(name));
-                result.pending += 1;
-                return;
-            }
+/// A `--focus` or `--skip` pattern, matched anywhere in a test's full
+/// `describe > it` path: a regex with the `regex` feature, otherwise a
+/// plain (case-sensitive) substring.
+#[derive(Clone, Debug)]
+pub(crate) struct PathPattern {
+    source: String,
+    #[cfg(feature = "regex")]
+    regex: regex::Regex,
+}
 
-            // Focus mode: skip non-focused
-            let effectively_focused = *focused || force_focused;
-            if focus_mode && !effectively_focused && !config.include_ignored {
-                result.skipped += 1;
-                return;
-            }
+impl PathPattern {
+    pub(crate) fn new(pattern: &str) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("expected a pattern".to_string());
+        }
+        Ok(PathPattern {
+            source: pattern.to_string(),
+            #[cfg(feature = "regex")]
+            regex: regex::Regex::new(pattern).map_err(|e| format!("invalid regex: {e}"))?,
+        })
+    }
 
-            // Fail-on-focus CI check
-            if effectively_focused && focus_mode {
-                crate::check_fail_on_focus();
-            }
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        #[cfg(feature = "regex")]
+        return self.regex.is_match(path);
+        #[cfg(not(feature = "regex"))]
+        path.contains(&self.source)
+    }
+}
 
-            // Label check (merge accumulated + own)
-            let all_labels: Vec<&str> = hooks
-                .labels
-                .iter()
-                .copied()
-                .chain(labels.iter().map(|s| s.as_str()))
-                .collect();
-            if !crate::check_labels(&all_labels) {
-                return;
-            }
+impl std::fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
 
-            // Execute the test
-            let start = Instant::now();
+/// Entries of a focus file: one path substring per line. Blank lines and
+/// lines starting with `#` are ignored.
+fn parse_focus_file(contents: &str) -> impl Iterator<Item = String> + '_ {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
 
-            let test_body = || {
-                // Run before_each + just_before_each + test body, catching any panic
-                // so that after_each and cleanups are guaranteed to run.
-                let body_result = catch_unwind(AssertUnwindSafe(|| {
-                    for hook in &hooks.before_each {
-                        hook();
-                    }
-                    for hook in &hooks.just_before_each {
-                        hook();
-                    }
-                    test_fn();
-                }));
+/// A named, reusable test selection, registered with
+/// [`Context::plan`](crate::Context::plan) and run with `--plan <name>`.
+#[derive(Clone, Debug)]
+pub(crate) struct Plan {
+    pub name: String,
+    /// Each term selects tests carrying it as a label or containing it in
+    /// their full path (case-insensitive).
+    pub terms: Vec<String>,
+}
 
-                // after_each (innermost first) — each individually protected
-                let mut after_each_panic = None;
-                for hook in hooks.after_each.iter().rev() {
-                    if let Err(e) = catch_unwind(AssertUnwindSafe(hook)) {
-                        eprintln!("  warning: after_each hook panicked");
-                        if after_each_panic.is_none() {
-                            after_each_panic = Some(e);
-                        }
-                    }
-                }
+/// Whether any plan term matches one of `labels` or is a substring of `full_path`.
+fn plan_matches(terms: &[String], full_path: &str, labels: &[&str]) -> bool {
+    let path = full_path.to_lowercase();
+    terms
+        .iter()
+        .any(|term| labels.contains(&term.as_str()) || path.contains(&term.to_lowercase()))
+}
 
-                // Deferred cleanups
-                crate::run_deferred_cleanups();
+/// A named suite for multi-suite runs with [`run_suites`].
+pub struct Suite {
+    pub name: String,
+    pub nodes: Vec<TestNode>,
+    /// Source file the suite was declared in, used by `--order file`.
+    pub file: Option<String>,
+    /// Named selections available to `--plan`.
+    pub(crate) plans: Vec<Plan>,
+    /// When set, every test is reported as skipped with this reason instead
+    /// of running (e.g. a required capability is missing).
+    pub(crate) skip_reason: Option<String>,
+    /// Hooks and settings registered outside any describe, as a describe
+    /// without children that encloses `nodes`.
+    pub(crate) root: TestNode,
+    /// `depends_on` declarations in `nodes`, resolved on first use.
+    dependencies: std::cell::OnceCell<Dependencies>,
+}
 
-                // Propagate the first failure: body takes priority over after_each
-                if let Err(e) = body_result {
-                    std::panic::resume_unwind(e);
-                }
-                if let Some(e) = after_each_panic {
-                    std::panic::resume_unwind(e);
-                }
-            };
+impl Suite {
+    /// A suite named `name` (printed as a header before its tests) running
+    /// the trees in `nodes`.
+    pub fn new(name: impl Into<String>, nodes: Vec<TestNode>) -> Self {
+        Suite {
+            name: name.into(),
+            nodes,
+            file: None,
+            plans: Vec::new(),
+            skip_reason: None,
+            root: TestNode::describe("", Vec::new()),
+            dependencies: std::cell::OnceCell::new(),
+        }
+    }
 
-            // Apply decorators compositionally so combinations behave as expected:
-            // retries -> must_pass_repeatedly -> timeout (outermost)
-            let with_retries = || {
-                if let Some(n) = *retries {
-                    crate::with_retries(n, test_body);
-                } else {
-                    test_body();
-                }
-            };
+    /// Record the source file the suite was declared in (typically `file!()`).
+    pub fn file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
 
-            let with_must_pass_repeatedly = || {
-                if let Some(n) = *must_pass_repeatedly {
-                    crate::must_pass_repeatedly(n, with_retries);
-                } else {
-                    with_retries();
-                }
-            };
+    /// The hook chain around the suite's top-level nodes.
+    fn hooks(&self) -> HookChain<'_> {
+        let mut chain = HookChain::default().with_describe(&self.root);
+        chain.dependencies = Some(self.dependencies());
+        chain
+    }
 
-            let outcome = if let Some(ms) = *timeout_ms {
-                run_with_timeout(ms, &with_must_pass_repeatedly)
-            } else {
-                catch_unwind(AssertUnwindSafe(with_must_pass_repeatedly))
-            };
+    /// The suite's `depends_on` declarations, resolved to full paths.
+    fn dependencies(&self) -> &Dependencies {
+        self.dependencies.get_or_init(|| Dependencies::resolve(&self.nodes))
+    }
 
-            // Check if the test called skip!() — report as skipped, not passed
-            if outcome.is_ok() {
-                if let Some(reason) = crate::take_skip_reason() {
-                    println!("{indent}{} {} {}", yellow("-"), dim(name), dim(&format!("({reason})")));
-                    result.skipped += 1;
-                } else {
-                    report_outcome(&indent, name, &full_path, outcome, start, result);
-                }
-            } else {
-                // Clear any skip flag set before the panic
-                let _ = crate::take_skip_reason();
-                report_outcome(&indent, name, &full_path, outcome, start, result);
-            }
+    /// The suite's top-level `before_all` hooks.
+    fn before_all(&self) -> &[Box<dyn Fn()>] {
+        match &self.root {
+            TestNode::Describe { before_all, .. } => before_all,
+            _ => &[],
         }
-        TestNode::Ordered {
-            name,
-            labels,
-            continue_on_failure,
-            steps,
-        } => {
-            let indent = "  ".repeat(depth);
-            let full_path = {
-                let mut p = path.to_vec();
-                p.push(name.clone());
-                p.join(" > ")
-            };
+    }
+
+    /// The suite's top-level `after_all` hooks.
+    fn after_all(&self) -> &[Box<dyn Fn()>] {
+        match &self.root {
+            TestNode::Describe { after_all, .. } => after_all,
+            _ => &[],
+        }
+    }
+}
+
+/// Parse the value of `RSSPEC_LABEL_FILTER`; unset or empty selects every
+/// test.
+fn label_filter(value: Option<&str>) -> Result<Option<LabelFilter>, String> {
+    match value {
+        Some(filter) if !filter.is_empty() => parse_label_filter(filter)
+            .map(Some)
+            .map_err(|e| format!("'{filter}' is invalid — {e}")),
+        _ => Ok(None),
+    }
+}
+
+/// Order suites according to `--order`. Sorting is stable, so ties keep
+/// their declared order.
+fn order_suites(suites: &[Suite], order: SuiteOrder) -> Vec<&Suite> {
+    let mut ordered: Vec<&Suite> = suites.iter().collect();
+    match order {
+        SuiteOrder::Declared => {}
+        SuiteOrder::Name => ordered.sort_by(|a, b| a.name.cmp(&b.name)),
+        SuiteOrder::File => {
+            ordered.sort_by(|a, b| (a.file.is_none(), &a.file).cmp(&(b.file.is_none(), &b.file)))
+        }
+    }
+    ordered
+}
+
+/// Run a single test tree and print BDD-formatted output.
+#[cfg(test)]
+fn run_tree(nodes: &[TestNode], config: &RunConfig) -> RunResult {
+    let focus_mode = tree_has_focus(nodes) || !config.focus_paths.is_empty();
+    let mut result = RunResult::default();
+    let start = Instant::now();
+
+    if config.list {
+        list_tree(nodes, &[], config);
+        return result;
+    }
+
+    let mut reporter = config.reporter();
+    let reporter = &mut *reporter;
+    reporter.run_started(None);
+    let dependencies = Dependencies::resolve(nodes);
+    let hooks = HookChain {
+        dependencies: Some(&dependencies),
+        ..HookChain::default()
+    };
+    run_nodes(nodes, &[], &hooks, focus_mode, false, config, reporter, &mut result);
+    reporter.run_finished(&RunSummary::new(&result, start.elapsed()));
+
+    result
+}
+
+/// Run multiple named suites, reporting a header per suite and a combined summary.
+///
+/// This is the engine under [`run`](crate::run): it returns the result
+/// without exiting and doesn't run the [`at_exit`](crate::at_exit)
+/// functions, since the caller may run more suites. A `main` that ends with
+/// `run_suites` rather than `run` has no `at_exit` step.
+pub fn run_suites(
+    suites: &[Suite],
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+) -> RunResult {
+    let focus_mode =
+        suites.iter().any(|s| tree_has_focus(&s.nodes)) || !config.focus_paths.is_empty();
+    let mut result = RunResult::default();
+    let start = Instant::now();
+
+    // Fix the seed up front so `--at` and the run itself agree on the order.
+    let seeded_config;
+    let config = if config.shuffle && config.seed.is_none() && !config.list {
+        seeded_config = RunConfig {
+            seed: Some(crate::shuffle::random_seed()),
+            ..config.clone()
+        };
+        &seeded_config
+    } else {
+        config
+    };
+
+    // `RSSPEC_LABEL_FILTER`: parse it once, and fail the run if it's malformed.
+    let label_config;
+    let config = match label_filter(std::env::var("RSSPEC_LABEL_FILTER").ok().as_deref()) {
+        Ok(None) => config,
+        Ok(Some(filter)) => {
+            label_config = RunConfig {
+                label_filter: Some(Arc::new(filter)),
+                ..config.clone()
+            };
+            &label_config
+        }
+        Err(msg) => {
+            eprintln!("rsspec: RSSPEC_LABEL_FILTER {msg}");
+            result.failed += 1;
+            result.failures.push(Failure::new("RSSPEC_LABEL_FILTER", msg));
+            return result;
+        }
+    };
+
+    // `--plan NAME`: resolve the plan's terms across all suites.
+    let plan_config;
+    let config = match &config.plan {
+        Some(name) => {
+            let plans: Vec<&Plan> = suites
+                .iter()
+                .flat_map(|s| &s.plans)
+                .filter(|p| &p.name == name)
+                .collect();
+            if plans.is_empty() {
+                let mut known: Vec<&str> = suites
+                    .iter()
+                    .flat_map(|s| &s.plans)
+                    .map(|p| p.name.as_str())
+                    .collect();
+                known.sort_unstable();
+                known.dedup();
+                let msg = format!("unknown plan '{name}' (defined: {})", known.join(", "));
+                eprintln!("rsspec: --plan {msg}");
+                result.failed += 1;
+                result.failures.push(Failure::new("--plan", msg));
+                return result;
+            }
+            plan_config = RunConfig {
+                plan_terms: Some(plans.iter().flat_map(|p| p.terms.clone()).collect()),
+                ..config.clone()
+            };
+            &plan_config
+        }
+        None => config,
+    };
+
+    // `--at N`: materialize the run order, then narrow the run to one path.
+    let at_config;
+    let config = match config.at {
+        Some(n) if !config.list => {
+            let mut paths = Vec::new();
+            for suite in order_suites(suites, config.order) {
+                collect_runnable_paths(
+                    &suite.nodes,
+                    &suite.hooks(),
+                    focus_mode,
+                    config,
+                    &mut paths,
+                );
+            }
+            let Some(path) = paths.get(n - 1) else {
+                let msg = format!("position {n} is out of range ({} tests eligible)", paths.len());
+                eprintln!("rsspec: --at {msg}");
+                result.failed += 1;
+                result.failures.push(Failure::new("--at", msg));
+                return result;
+            };
+            eprintln!("rsspec: --at {n} → {path}");
+            at_config = RunConfig {
+                only_path: Some(path.clone()),
+                ..config.clone()
+            };
+            &at_config
+        }
+        _ => config,
+    };
+
+    // Dependencies of the selected tests run even if the selection left them out.
+    let required_config;
+    let config = match required_dependencies(suites, focus_mode, config) {
+        required if required.is_empty() => config,
+        required => {
+            required_config = RunConfig {
+                required_paths: required,
+                ..config.clone()
+            };
+            &required_config
+        }
+    };
+
+    // Workers inherit the flag but the parent already printed the config.
+    if config.show_config && config.worker.is_none() {
+        eprint!("{}", config.effective_config());
+    }
+
+    if config.list {
+        for suite in suites {
+            list_tree(&suite.nodes, &[], config);
+        }
+        return result;
+    }
+
+    if config.plan_dump {
+        for suite in order_suites(suites, config.order) {
+            if suite.skip_reason.is_some() {
+                continue;
+            }
+            let hooks = suite.hooks();
+            let _ = visit_tests(&suite.nodes, &[], &hooks, focus_mode, false, config, &mut |test| {
+                println!("{}", crate::json::plan_line(&test));
+                ControlFlow::Continue(())
+            });
+        }
+        return result;
+    }
+
+    if let Some(view) = config.inventory {
+        let mut inventory = Inventory::default();
+        for suite in suites {
+            inventory.collect(&suite.nodes, &[], &suite.hooks(), false, false, config);
+        }
+        inventory.print(view);
+        return result;
+    }
+
+    // Unknown or ambiguous `depends_on` names and cycles fail the run up front.
+    let errors: Vec<&String> = suites.iter().flat_map(|s| &s.dependencies().errors).collect();
+    if !errors.is_empty() {
+        for msg in errors {
+            eprintln!("rsspec: depends_on: {msg}");
+            result.failed += 1;
+            result.failures.push(Failure::new("depends_on", msg.clone()));
+        }
+        return result;
+    }
+
+    if let Some(unit) = &config.worker {
+        return run_worker(suites, unit, focus_mode, config, reporter);
+    }
+
+    reporter.run_started(config.seed.filter(|_| config.shuffle));
+
+    // Where each suite's records start in `result.tests` and
+    // `result.failures`, for the JUnit report.
+    let mut suite_starts = Vec::new();
+    for suite in order_suites(suites, config.order) {
+        if result.bailed {
+            break;
+        }
+        suite_starts.push((suite.name.as_str(), result.tests.len(), result.failures.len()));
+        reporter.suite_started(&suite.name);
+
+        let hooks = suite.hooks();
+        if let Some(reason) = &suite.skip_reason {
+            skip_runnable(&suite.nodes, &hooks, focus_mode, config, reason, &mut result);
+        } else if let Some(jobs) = config
+            .jobs
+            .filter(|&n| n > 1 && config.only_path.is_none() && !config.dry_run)
+        {
+            // Each worker runs the top-level hooks around its own node.
+            let index = suites.iter().position(|s| std::ptr::eq(s, suite)).unwrap_or_default();
+            run_nodes_parallel(suites, index, jobs, focus_mode, config, reporter, &mut result);
+        } else {
+            let nodes = &suite.nodes;
+            let order = config.sibling_order(nodes, &[], &hooks);
+            let top_level: Vec<&TestNode> = order.iter().map(|&i| &nodes[i]).collect();
+            if suite_before_all(suite, &top_level, focus_mode, config, reporter, &mut result) {
+                run_nodes(nodes, &[], &hooks, focus_mode, false, config, reporter, &mut result);
+            }
+            suite_after_all(suite, &top_level, focus_mode, config, reporter, &mut result);
+        }
+
+        report_new_tests(reporter, &mut result);
+    }
+
+    reporter.run_finished(&RunSummary::new(&result, start.elapsed()));
+
+    if let Some(path) = &config.junit {
+        let reports: Vec<_> = suite_starts
+            .iter()
+            .enumerate()
+            .map(|(i, &(name, begin, failures_begin))| {
+                let (end, failures_end) = suite_starts
+                    .get(i + 1)
+                    .map_or((result.tests.len(), result.failures.len()), |&(_, t, f)| (t, f));
+                let tests = &result.tests[begin..end];
+                let hook_failures = result.failures[failures_begin..failures_end]
+                    .iter()
+                    .filter(|f| !tests.iter().any(|t| t.path == f.path))
+                    .collect();
+                crate::junit::SuiteReport {
+                    name,
+                    tests,
+                    hook_failures,
+                }
+            })
+            .collect();
+        if let Err(e) = crate::junit::write(path, &reports, start.elapsed()) {
+            eprintln!("rsspec: cannot write --junit report '{path}': {e}");
+        }
+    }
+    if config.write_rerun {
+        crate::rerun::write(&result.tests);
+    }
+
+    result
+}
+
+/// `depends_on` dependencies of the tests `config` selects, and theirs in
+/// turn, that the selection doesn't include itself.
+fn required_dependencies(suites: &[Suite], focus_mode: bool, config: &RunConfig) -> Vec<String> {
+    let mut required = Vec::new();
+    for suite in suites {
+        let dependencies = suite.dependencies();
+        if dependencies.is_empty() || suite.skip_reason.is_some() {
+            continue;
+        }
+        let mut selected = Vec::new();
+        collect_runnable_paths(&suite.nodes, &suite.hooks(), focus_mode, config, &mut selected);
+        let mut needed: Vec<&String> = selected.iter().flat_map(|p| dependencies.of(p)).collect();
+        while let Some(path) = needed.pop() {
+            if selected.contains(path) || required.contains(path) {
+                continue;
+            }
+            required.push(path.clone());
+            needed.extend(dependencies.of(path));
+        }
+    }
+    required
+}
+
+/// Run `suite`'s top-level `before_all` hooks around `nodes` (all of the
+/// suite's, or a `--jobs` worker's group), unless none of their tests will
+/// run. If one panics or calls `skip_scope`, those tests are recorded as
+/// skipped and this returns false.
+fn suite_before_all(
+    suite: &Suite,
+    nodes: &[&TestNode],
+    focus_mode: bool,
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+    result: &mut RunResult,
+) -> bool {
+    let hooks = suite.hooks();
+    let before_all = suite.before_all();
+    if before_all.is_empty() || !suite_hooks_run(suite, nodes, focus_mode, config) {
+        return true;
+    }
+    let _ = crate::take_scope_skip_reason();
+    let outcome = catch_unwind(AssertUnwindSafe(|| {
+        for hook in before_all {
+            crate::spy::record(HookPhase::BeforeAll);
+            hook();
+            if let Some(reason) = crate::take_scope_skip_reason() {
+                return Some(reason);
+            }
+        }
+        None
+    }));
+    let reason = match outcome {
+        Ok(None) => return true,
+        Ok(Some(reason)) => reason,
+        Err(e) => {
+            let _ = crate::take_scope_skip_reason();
+            let msg = panic_message(&*e);
+            let scope = suite_scope(suite);
+            reporter.hook_failed(scope, "before_all", &msg);
+            result.failed += 1;
+            result.failures.push(Failure::new(format!("{scope} (before_all)"), msg));
+            BEFORE_ALL_FAILED.to_string()
+        }
+    };
+    for node in nodes {
+        skip_runnable(std::slice::from_ref(*node), &hooks, focus_mode, config, &reason, result);
+    }
+    report_new_tests(reporter, result);
+    false
+}
+
+/// Run `suite`'s top-level `after_all` hooks after `nodes`, even if a
+/// `before_all` hook failed.
+fn suite_after_all(
+    suite: &Suite,
+    nodes: &[&TestNode],
+    focus_mode: bool,
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+    result: &mut RunResult,
+) {
+    let after_all = suite.after_all();
+    if after_all.is_empty() || !suite_hooks_run(suite, nodes, focus_mode, config) {
+        return;
+    }
+    if let Err(e) = catch_unwind(AssertUnwindSafe(|| {
+        for hook in after_all {
+            crate::spy::record(HookPhase::AfterAll);
+            hook();
+        }
+    })) {
+        let msg = panic_message(&*e);
+        let scope = suite_scope(suite);
+        reporter.hook_failed(scope, "after_all", &msg);
+        result.failed += 1;
+        result.failures.push(Failure::new(format!("{scope} (after_all)"), msg));
+    }
+}
+
+/// Whether `suite`'s top-level hooks run around `nodes`: as for a describe,
+/// not on a dry run or when none of their tests will run.
+fn suite_hooks_run(
+    suite: &Suite,
+    nodes: &[&TestNode],
+    focus_mode: bool,
+    config: &RunConfig,
+) -> bool {
+    let hooks = suite.hooks();
+    !config.dry_run
+        && nodes.iter().any(|node| {
+            let node = std::slice::from_ref(*node);
+            has_runnable_tests(node, &[], &hooks, focus_mode, false, config)
+        })
+}
+
+/// How failures of `suite`'s top-level hooks are labeled.
+fn suite_scope(suite: &Suite) -> &str {
+    if suite.name.is_empty() {
+        "top level"
+    } else {
+        &suite.name
+    }
+}
+
+/// `--jobs`: run each top-level node of `suites[index]` that has tests to
+/// run in a worker process, up to `jobs` at a time. Output is printed and
+/// results merged in declaration (or shuffled) order as workers finish.
+/// Nodes with nothing to run (pending or filtered out) are handled in-process.
+/// Nodes linked by `depends_on` form one group and share a worker.
+///
+/// A node that shares a [`resource`](crate::Context::resource) with a
+/// started one waits for it to be collected. Later nodes may start ahead of
+/// it, but they keep a slot free for it and never take its resources.
+///
+/// Workers print with their own built-in reporter; the parent copies that
+/// output through and only reports crashed workers itself.
+fn run_nodes_parallel(
+    suites: &[Suite],
+    index: usize,
+    jobs: usize,
+    focus_mode: bool,
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+    result: &mut RunResult,
+) {
+    let suite = &suites[index];
+    let nodes = &suite.nodes;
+    let hooks = suite.hooks();
+    let order = suite.dependencies().groups(nodes, &config.sibling_order(nodes, &[], &hooks));
+    let remote: Vec<bool> = order
+        .iter()
+        .map(|group| {
+            group.iter().any(|&i| {
+                has_runnable_tests(&nodes[i..=i], &[], &hooks, focus_mode, false, config)
+            })
+        })
+        .collect();
+    // Resources declared outside any describe are held by every node.
+    let shared = subtree_resources(&suite.root);
+    let resources: Vec<Vec<&str>> = order
+        .iter()
+        .map(|group| {
+            let mut held: Vec<&str> =
+                group.iter().flat_map(|&i| subtree_resources(&nodes[i])).collect();
+            held.extend(&shared);
+            held.sort_unstable();
+            held.dedup();
+            held
+        })
+        .collect();
+    let seed = config.seed.filter(|_| config.shuffle);
+
+    // The worker started for each group in `order`, until collected.
+    let mut workers: Vec<Option<std::io::Result<Worker>>> = order.iter().map(|_| None).collect();
+    let mut busy = 0;
+    for (k, group) in order.iter().enumerate() {
+        if result.bailed {
+            for worker in workers.into_iter().flatten().flatten() {
+                Worker::kill(worker);
+            }
+            return;
+        }
+        // Keep up to `jobs` workers busy, starting them in run order.
+        let mut waiting: Vec<&str> = Vec::new();
+        for next in k..order.len() {
+            if busy >= jobs || (!waiting.is_empty() && busy + 1 >= jobs) {
+                break;
+            }
+            if !remote[next] || workers[next].is_some() {
+                continue;
+            }
+            let held = resources[next].as_slice();
+            let in_use = (k..order.len())
+                .any(|p| workers[p].is_some() && shares_resource(held, &resources[p]));
+            if in_use || shares_resource(held, &waiting) {
+                waiting.extend(held);
+                waiting.sort_unstable();
+                continue;
+            }
+            // A worker is named by its group's first node and finds the rest.
+            workers[next] = Some(Worker::spawn(&config.args, index, order[next][0], seed));
+            busy += 1;
+        }
+
+        if !remote[k] {
+            for &i in group {
+                run_node(&nodes[i], &[], &hooks, focus_mode, false, config, reporter, result);
+            }
+            report_new_tests(reporter, result);
+            check_bail(config, result);
+            continue;
+        }
+
+        let worker = workers[k].take();
+        busy -= usize::from(worker.is_some());
+        let outcome = match worker {
+            Some(Ok(worker)) => worker.finish(),
+            Some(Err(e)) => Err(format!("could not start worker: {e}")),
+            None => Err("worker was never started".to_string()),
+        };
+        match outcome {
+            Ok((output, worker_result)) => {
+                print!("{output}");
+                merge_worker_result(result, worker_result);
+            }
+            Err(msg) => {
+                let name = match &nodes[group[0]] {
+                    TestNode::Describe { name, .. }
+                    | TestNode::It { name, .. }
+                    | TestNode::Ordered { name, .. } => name,
+                };
+                reporter.hook_failed(name, "worker", &msg);
+                result.failed += 1;
+                result.failures.push(Failure::new(format!("{name} (worker)"), msg));
+            }
+        }
+        check_bail(config, result);
+    }
+}
+
+/// Whether two sorted resource lists have a name in common.
+fn shares_resource(a: &[&str], b: &[&str]) -> bool {
+    a.iter().any(|name| b.binary_search(name).is_ok())
+}
+
+/// Add a worker's results to the run. The worker already printed its events.
+fn merge_worker_result(result: &mut RunResult, worker: RunResult) {
+    result.passed += worker.passed;
+    result.failed += worker.failed;
+    result.pending += worker.pending;
+    result.skipped += worker.skipped;
+    result.flaky += worker.flaky;
+    result.xfailed += worker.xfailed;
+    result.failures.extend(worker.failures);
+    result.flaky_failures.extend(worker.flaky_failures);
+    result.no_assertions.extend(worker.no_assertions);
+    result.scope_timings.extend(worker.scope_timings);
+    result.tests.extend(worker.tests);
+    result.reported = result.tests.len();
+}
+
+/// In a `--jobs` worker: run the top-level node the parent assigned, with
+/// the nodes it shares a `depends_on` group with, without headers or a
+/// summary, and write the results back for them.
+fn run_worker(
+    suites: &[Suite],
+    unit: &WorkerUnit,
+    focus_mode: bool,
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+) -> RunResult {
+    let mut result = RunResult::default();
+    let suite = suites.get(unit.suite);
+    match suite.filter(|s| unit.node < s.nodes.len()) {
+        Some(suite) => {
+            // The suite's top-level hooks run around each worker's group.
+            let hooks = suite.hooks();
+            let order = config.sibling_order(&suite.nodes, &[], &hooks);
+            let groups = suite.dependencies().groups(&suite.nodes, &order);
+            let group = groups.into_iter().find(|g| g.contains(&unit.node)).unwrap_or_default();
+            let nodes: Vec<&TestNode> = group.iter().map(|&i| &suite.nodes[i]).collect();
+            if suite_before_all(suite, &nodes, focus_mode, config, reporter, &mut result) {
+                for node in &nodes {
+                    run_node(node, &[], &hooks, focus_mode, false, config, reporter, &mut result);
+                }
+            }
+            suite_after_all(suite, &nodes, focus_mode, config, reporter, &mut result);
+            report_new_tests(reporter, &mut result);
+        }
+        None => {
+            result.failed += 1;
+            result
+                .failures
+                .push(Failure::new(WORKER_FLAG, "no such suite or top-level node"));
+        }
+    }
+    if let Err(e) = crate::parallel::write_results(unit, &result) {
+        eprintln!("rsspec: worker cannot write results: {e}");
+    }
+    result
+}
+
+/// Run the suites, then re-run them each time a line arrives on `input` (or,
+/// on Linux, the process receives SIGUSR1), until `input` reaches EOF.
+/// Returns the result of the last run.
+///
+/// rsspec can't recompile the test binary, so this is meant to be driven by
+/// an external file watcher (or a developer pressing Enter) writing to stdin
+/// or signalling the process.
+pub(crate) fn run_suites_watch(
+    suites: &[Suite],
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+    input: impl std::io::BufRead + Send + 'static,
+) -> RunResult {
+    rerun_signal::install();
+    // Lines are read on their own thread so a signal can wake the loop too.
+    let (lines, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in input.lines() {
+            if line.is_err() || lines.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    let mut result = run_suites(suites, config, reporter);
+    loop {
+        eprintln!("rsspec: watching — press Enter to re-run, Ctrl-D to exit");
+        if !wait_for_rerun(&received) {
+            return result;
+        }
+        result = run_suites(suites, config, reporter);
+    }
+}
+
+/// Block until a line arrives or SIGUSR1 is received; false once stdin
+/// reaches EOF.
+fn wait_for_rerun(received: &std::sync::mpsc::Receiver<()>) -> bool {
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::time::Duration;
+
+    loop {
+        if rerun_signal::take() {
+            return true;
+        }
+        match received.recv_timeout(Duration::from_millis(50)) {
+            Ok(()) => return true,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return rerun_signal::take(),
+        }
+    }
+}
+
+/// SIGUSR1 as a `--watch` re-run trigger. Elsewhere than Linux only stdin is.
+#[cfg(target_os = "linux")]
+mod rerun_signal {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Set by the handler; a signal handler can't safely do more.
+    static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_signal(_: libc::c_int) {
+        RECEIVED.store(true, Ordering::SeqCst);
+    }
+
+    pub(super) fn install() {
+        let handler: extern "C" fn(libc::c_int) = on_signal;
+        unsafe { libc::signal(libc::SIGUSR1, handler as libc::sighandler_t) };
+    }
+
+    /// Whether SIGUSR1 arrived since the last call.
+    pub(super) fn take() -> bool {
+        RECEIVED.swap(false, Ordering::SeqCst)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod rerun_signal {
+    pub(super) fn install() {}
+
+    pub(super) fn take() -> bool {
+        false
+    }
+}
+
+/// Check if any tests in this subtree will actually execute, considering
+/// focus mode, label filters, path filters, pending status and `skip_if`.
+///
+/// Used to skip `before_all`/`after_all` when all children are filtered out.
+fn has_runnable_tests(
+    nodes: &[TestNode],
+    path: &[String],
+    hooks: &HookChain,
+    focus_mode: bool,
+    force_focused: bool,
+    config: &RunConfig,
+) -> bool {
+    visit_tests(nodes, path, hooks, focus_mode, force_focused, config, &mut |test| {
+        if test.pending || test.skipped {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    })
+    .is_break()
+}
+
+/// Full paths of every test in `nodes` that will actually execute, in run order.
+fn collect_runnable_paths(
+    nodes: &[TestNode],
+    hooks: &HookChain,
+    focus_mode: bool,
+    config: &RunConfig,
+    out: &mut Vec<String>,
+) {
+    let _ = visit_runnable(nodes, &[], hooks, focus_mode, false, config, &mut |path| {
+        out.push(path);
+        ControlFlow::Continue(())
+    });
+}
+
+/// Report every test that would run in `nodes` as skipped with `reason`,
+/// without running any hooks.
+fn skip_runnable(
+    nodes: &[TestNode],
+    hooks: &HookChain,
+    focus_mode: bool,
+    config: &RunConfig,
+    reason: &str,
+    result: &mut RunResult,
+) {
+    let _ = visit_runnable(nodes, &[], hooks, focus_mode, false, config, &mut |path| {
+        result.record(&path, TestStatus::Skipped).message = Some(reason.to_string());
+        ControlFlow::Continue(())
+    });
+}
+
+/// Walk the tests in this subtree that will actually execute, in run order,
+/// calling `visit` with each one's full path. Stops early if `visit` breaks.
+#[allow(clippy::too_many_arguments)]
+fn visit_runnable(
+    nodes: &[TestNode],
+    path: &[String],
+    hooks: &HookChain,
+    focus_mode: bool,
+    force_focused: bool,
+    config: &RunConfig,
+    visit: &mut dyn FnMut(String) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    visit_tests(nodes, path, hooks, focus_mode, force_focused, config, &mut |test| {
+        if test.pending {
+            ControlFlow::Continue(())
+        } else {
+            visit(test.path)
+        }
+    })
+}
+
+/// A test the runner will reach, as seen by [`visit_tests`].
+pub(crate) struct PlannedTest {
+    pub path: String,
+    /// Effective labels, inherited ones first.
+    pub labels: Vec<String>,
+    /// Reported as pending rather than run.
+    pub pending: bool,
+    /// Reported as skipped by `skip_if` without running, hooks included.
+    pub skipped: bool,
+}
+
+/// Like [`visit_runnable`], but also visits the tests that will be reported
+/// as pending, and with their labels.
+#[allow(clippy::too_many_arguments)]
+fn visit_tests(
+    nodes: &[TestNode],
+    path: &[String],
+    hooks: &HookChain,
+    focus_mode: bool,
+    force_focused: bool,
+    config: &RunConfig,
+    visit: &mut dyn FnMut(PlannedTest) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    for i in config.sibling_order(nodes, path, hooks) {
+        let node = &nodes[i];
+        match node {
+            TestNode::Describe {
+                name,
+                focused,
+                pending,
+                children,
+                ..
+            } => {
+                let mut child_path = path.to_vec();
+                child_path.push(name.clone());
+                let child_hooks = hooks.with_describe(node);
+                if *pending {
+                    visit_pending(children, &child_path, &child_hooks, visit)?;
+                    continue;
+                }
+                let child_force_focused = force_focused || *focused;
+                visit_tests(
+                    children,
+                    &child_path,
+                    &child_hooks,
+                    focus_mode,
+                    child_force_focused,
+                    config,
+                    visit,
+                )?;
+            }
+            TestNode::It {
+                name,
+                focused,
+                pending,
+                labels,
+                owner,
+                skip_reason,
+                ..
+            } => {
+                let full_path = {
+                    let mut p = path.to_vec();
+                    p.push(name.clone());
+                    p.join(" > ")
+                };
+                let required = config.requires(&full_path);
+                if !required && !config.selects_path(&full_path) {
+                    continue;
+                }
+                let all_labels: Vec<String> = hooks
+                    .labels
+                    .iter()
+                    .copied()
+                    .chain(labels.iter().map(|s| s.as_str()))
+                    .map(str::to_string)
+                    .collect();
+                if *pending {
+                    visit(PlannedTest {
+                        path: full_path,
+                        labels: all_labels,
+                        pending: true,
+                        skipped: false,
+                    })?;
+                    continue;
+                }
+                let effectively_focused =
+                    *focused || force_focused || config.focus_file_matches(&full_path);
+                if focus_mode && !effectively_focused && !config.include_ignored && !required {
+                    continue;
+                }
+                let label_refs: Vec<&str> = all_labels.iter().map(String::as_str).collect();
+                let owner = owner.as_deref().or(hooks.owner);
+                if !required && !config.selects_test(&full_path, &label_refs, owner) {
+                    continue;
+                }
+                visit(PlannedTest {
+                    path: full_path,
+                    labels: all_labels,
+                    pending: false,
+                    skipped: skip_reason.is_some(),
+                })?;
+            }
+            TestNode::Ordered {
+                name, labels, steps, ..
+            } => {
+                let full_path = {
+                    let mut p = path.to_vec();
+                    p.push(name.clone());
+                    p.join(" > ")
+                };
+                let required = config.requires(&full_path);
+                if !required && !config.selects_path(&full_path) {
+                    continue;
+                }
+                let effectively_focused = force_focused
+                    || has_focused_step(steps)
+                    || config.focus_file_matches(&full_path);
+                if focus_mode && !effectively_focused && !config.include_ignored && !required {
+                    continue;
+                }
+                let all_labels: Vec<&str> = hooks
+                    .labels
+                    .iter()
+                    .copied()
+                    .chain(labels.iter().map(|s| s.as_str()))
+                    .collect();
+                if !required && !config.selects_test(&full_path, &all_labels, hooks.owner) {
+                    continue;
+                }
+                visit(PlannedTest {
+                    path: full_path,
+                    labels: all_labels.iter().map(|s| s.to_string()).collect(),
+                    pending: false,
+                    skipped: false,
+                })?;
+            }
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+/// Visit every test under a pending describe as pending, in declaration
+/// order, the way [`run_nodes_pending`] reports them.
+fn visit_pending(
+    nodes: &[TestNode],
+    path: &[String],
+    hooks: &HookChain,
+    visit: &mut dyn FnMut(PlannedTest) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    for node in nodes {
+        let mut node_path = path.to_vec();
+        match node {
+            TestNode::Describe { name, children, .. } => {
+                node_path.push(name.clone());
+                visit_pending(children, &node_path, &hooks.with_describe(node), visit)?;
+            }
+            TestNode::It { name, labels, .. } | TestNode::Ordered { name, labels, .. } => {
+                node_path.push(name.clone());
+                let inherited = hooks.labels.iter().copied().map(str::to_string);
+                visit(PlannedTest {
+                    path: node_path.join(" > "),
+                    labels: inherited.chain(labels.iter().cloned()).collect(),
+                    pending: true,
+                    skipped: false,
+                })?;
+            }
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_nodes(
+    nodes: &[TestNode],
+    path: &[String],
+    hooks: &HookChain,
+    focus_mode: bool,
+    force_focused: bool,
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+    result: &mut RunResult,
+) {
+    for i in config.sibling_order(nodes, path, hooks) {
+        if result.bailed {
+            break;
+        }
+        let node = &nodes[i];
+        run_node(node, path, hooks, focus_mode, force_focused, config, reporter, result);
+        report_new_tests(reporter, result);
+        check_bail(config, result);
+    }
+}
+
+/// Set `result.bailed` once `--bail N` tests have failed. Nothing new is
+/// scheduled after that; scopes already entered still run their `after_all`.
+fn check_bail(config: &RunConfig, result: &mut RunResult) {
+    let Some(limit) = config.bail else { return };
+    if !result.bailed {
+        let failed = result.tests.iter().filter(|t| t.status == TestStatus::Failed).count();
+        result.bailed = failed >= limit;
+    }
+}
+
+/// Pass the tests recorded since the last call to the reporter.
+fn report_new_tests(reporter: &mut dyn Reporter, result: &mut RunResult) {
+    for record in &result.tests[result.reported..] {
+        reporter.it_finished(&crate::reporter::test_result(record));
+    }
+    result.reported = result.tests.len();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_node(
+    node: &TestNode,
+    path: &[String],
+    hooks: &HookChain,
+    focus_mode: bool,
+    force_focused: bool,
+    config: &RunConfig,
+    reporter: &mut dyn Reporter,
+    result: &mut RunResult,
+) {
+    match node {
+        TestNode::Describe {
+            name,
+            focused,
+            pending,
+            children,
+            before_all,
+            after_all,
+            ..
+        } => {
+            let mut child_path = path.to_vec();
+            child_path.push(name.clone());
+            let full_path = child_path.join(" > ");
+            reporter.describe_entered(&full_path, name);
+
+            // If this describe is pending, mark all children as pending
+            if *pending {
+                run_nodes_pending(children, &child_path, reporter, result);
+                reporter.describe_finished(&full_path);
+                return;
+            }
+
+            let child_hooks = hooks.with_describe(node);
+            let child_force_focused = force_focused || *focused;
+
+            // Skip before_all/after_all when no children will actually run
+            // (e.g. all filtered by labels or focus mode). This avoids running
+            // expensive setup for nothing.
+            let any_runnable = has_runnable_tests(
+                children,
+                &child_path,
+                &child_hooks,
+                focus_mode,
+                child_force_focused,
+                config,
+            );
+            let has_hooks = !before_all.is_empty() || !after_all.is_empty();
+
+            if config.dry_run || (!any_runnable && has_hooks) {
+                // Still recurse children so pending/skipped counts are correct,
+                // but skip the before_all/after_all hooks.
+                run_nodes(
+                    children,
+                    &child_path,
+                    &child_hooks,
+                    focus_mode,
+                    child_force_focused,
+                    config,
+                    reporter,
+                    result,
+                );
+                reporter.describe_finished(&full_path);
+                return;
+            }
+
+            // Run before_all once at scope entry.
+            // If it panics, skip children but still run after_all.
+            // A stray `skip_scope` from outside a before_all is dropped.
+            let _ = crate::take_scope_skip_reason();
+            let first_test = result.tests.len();
+            let before_all_start = Instant::now();
+            let before_all_ok = catch_unwind(AssertUnwindSafe(|| {
+                for hook in before_all {
+                    crate::spy::record(HookPhase::BeforeAll);
+                    hook();
+                    if let Some(reason) = crate::take_scope_skip_reason() {
+                        return Some(reason);
+                    }
+                }
+                None
+            }));
+            let before_all_time = before_all_start.elapsed();
+
+            if let Err(e) = &before_all_ok {
+                let _ = crate::take_scope_skip_reason();
+                let msg = panic_message(&**e);
+                reporter.hook_failed(&full_path, "before_all", &msg);
+                result.failed += 1;
+                result.failures.push(Failure::new(format!("{full_path} (before_all)"), msg));
+
+                // The setup failure is the one failure; tests in the scope are
+                // reported as skipped rather than failing one by one.
+                let _ = visit_runnable(
+                    children,
+                    &child_path,
+                    &child_hooks,
+                    focus_mode,
+                    child_force_focused,
+                    config,
+                    &mut |path| {
+                        result.record(&path, TestStatus::Skipped).message =
+                            Some(BEFORE_ALL_FAILED.to_string());
+                        ControlFlow::Continue(())
+                    },
+                );
+                report_new_tests(reporter, result);
+            } else if let Ok(Some(reason)) = &before_all_ok {
+                // `skip_scope`: the scope doesn't apply, which isn't a failure.
+                let _ = visit_runnable(
+                    children,
+                    &child_path,
+                    &child_hooks,
+                    focus_mode,
+                    child_force_focused,
+                    config,
+                    &mut |path| {
+                        result.record(&path, TestStatus::Skipped).message = Some(reason.clone());
+                        ControlFlow::Continue(())
+                    },
+                );
+                report_new_tests(reporter, result);
+            } else {
+                run_nodes(
+                    children,
+                    &child_path,
+                    &child_hooks,
+                    focus_mode,
+                    child_force_focused,
+                    config,
+                    reporter,
+                    result,
+                );
+            }
+
+            // Run after_all once at scope exit — even if before_all failed
+            let after_all_start = Instant::now();
+            if let Err(e) = catch_unwind(AssertUnwindSafe(|| {
+                for hook in after_all {
+                    crate::spy::record(HookPhase::AfterAll);
+                    hook();
+                }
+            })) {
+                let msg = panic_message(&*e);
+                reporter.hook_failed(&full_path, "after_all", &msg);
+                result.failed += 1;
+                result.failures.push(Failure::new(format!("{full_path} (after_all)"), msg));
+            }
+            if has_hooks {
+                let tests = result.tests[first_test..]
+                    .iter()
+                    .filter(|t| !matches!(t.status, TestStatus::Skipped | TestStatus::Pending))
+                    .count();
+                result.scope_timings.push(ScopeTiming {
+                    path: full_path.clone(),
+                    before_all: before_all_time,
+                    after_all: after_all_start.elapsed(),
+                    tests,
+                });
+            }
+            reporter.describe_finished(&full_path);
+        }
+        TestNode::It {
+            name,
+            focused,
+            pending,
+            labels,
+            retries,
+            retry_backoff_ms,
+            timeout_ms,
+            max_duration_ms,
+            max_rss_mb,
+            timeout_mode,
+            must_pass_repeatedly,
+            repeat,
+            flaky,
+            xfail,
+            skip_reason,
+            allow_focus,
+            depends_on: _,
+            owner,
+            resources: _,
+            location,
+            test_fn,
+        } => {
+            let full_path = {
+                let mut p = path.to_vec();
+                p.push(name.clone());
+                p.join(" > ")
+            };
+
+            // Filter check
+            let required = config.requires(&full_path);
+            if !required && !config.selects_path(&full_path) {
+                return;
+            }
+
+            // Pending
+            if *pending {
+                result.record(&full_path, TestStatus::Pending);
+                return;
+            }
+
+            // Focus mode: skip non-focused
+            let source_focused = *focused || force_focused;
+            let effectively_focused = source_focused || config.focus_file_matches(&full_path);
+            if focus_mode && !effectively_focused && !config.include_ignored && !required {
+                result.record(&full_path, TestStatus::Skipped);
+                return;
+            }
+
+            // Fail-on-focus CI check, unless this focus is marked intentional:
+            // a `fit` by itself, focus from a describe by that describe.
+            // Focus from --focus-file lives outside the source, so it's exempt.
+            let exempt = *allow_focus || (!*focused && hooks.allow_focus);
+            if source_focused && focus_mode && !exempt {
+                crate::check_fail_on_focus();
+            }
+
+            // Label check (merge accumulated + own)
+            let all_labels: Vec<&str> = hooks
+                .labels
+                .iter()
+                .copied()
+                .chain(labels.iter().map(|s| s.as_str()))
+                .collect();
+            let owner = owner.as_deref().or(hooks.owner);
+            if !required && !config.selects_test(&full_path, &all_labels, owner) {
+                return;
+            }
+
+            if let Some(reason) = skip_reason {
+                result.record(&full_path, TestStatus::Skipped).message = Some(reason.clone());
+                return;
+            }
+
+            // Counted before the dependency check: nothing passes in a dry run.
+            if config.dry_run {
+                record_dry_run(&full_path, result);
+                return;
+            }
+
+            // Dependencies must have run earlier in this run and passed
+            let depends_on = hooks.dependencies.map_or(&[][..], |d| d.of(&full_path));
+            if let Some(reason) = unmet_dependency(depends_on, result) {
+                result.record(&full_path, TestStatus::Skipped).message = Some(reason);
+                return;
+            }
+
+            // Execute the test
+            crate::set_current_test(Some(crate::TestInfo {
+                name: name.clone(),
+                path: full_path.clone(),
+                labels: all_labels.iter().map(|s| s.to_string()).collect(),
+                owner: owner.map(str::to_string),
+                attachments: Vec::new(),
+            }));
+            let start = Instant::now();
+            let attempts = std::cell::Cell::new(0u32);
+            crate::take_assertion_count();
+            crate::take_step_count();
+            crate::take_noted();
+            crate::install_panic_hook();
+            crate::take_backtrace();
+
+            let test_body = || {
+                attempts.set(attempts.get() + 1);
+                // Notes from a failed attempt don't carry over into a retry.
+                crate::take_context_notes();
+                // Run before_each + just_before_each + test body, catching any panic
+                // so that after_each and cleanups are guaranteed to run.
+                let body_result = catch_unwind(AssertUnwindSafe(|| {
+                    crate::capture_logs(|| {
+                        for hook in &hooks.before_each {
+                            crate::spy::record(HookPhase::BeforeEach);
+                            hook();
+                        }
+                        for hook in &hooks.just_before_each {
+                            crate::spy::record(HookPhase::JustBeforeEach);
+                            hook();
+                        }
+                        let body_start = Instant::now();
+                        let _marker = CoverageMarker::begin(config, &full_path);
+                        match test_fn {
+                            TestFn::Local(body) => body(),
+                            TestFn::Send(body) => run_send_body(body),
+                        }
+                        if let Some(budget) = *max_duration_ms {
+                            check_max_duration(body_start.elapsed(), budget);
+                        }
+                    })
+                }));
+
+                // after_each (innermost first) — each individually protected
+                crate::set_body_panicked(body_result.is_err());
+                let mut after_each_panic = None;
+                for hook in hooks.after_each.iter().rev() {
+                    crate::spy::record(HookPhase::AfterEach);
+                    if let Err(e) = catch_unwind(AssertUnwindSafe(hook)) {
+                        diag!("  warning: after_each hook panicked");
+                        if after_each_panic.is_none() {
+                            after_each_panic = Some(e);
+                        }
+                    }
+                }
+
+                // Deferred cleanups
+                crate::run_deferred_cleanups();
+
+                // Propagate the first failure: body takes priority over after_each
+                if let Err(e) = body_result {
+                    std::panic::resume_unwind(e);
+                }
+                if let Some(e) = after_each_panic {
+                    std::panic::resume_unwind(e);
+                }
+            };
+
+            // Apply decorators compositionally so combinations behave as expected:
+            // retries -> repeat -> must_pass_repeatedly -> timeout (outermost)
+            // With `TimeoutMode::PerIteration` the timeout moves innermost
+            // instead, so each run gets a fresh deadline and a timeout is retried.
+            // Tests without their own timeout get `--default-timeout`, if set.
+            let timeout_ms = &timeout_ms.or(config.default_timeout_ms);
+            let per_iteration = *timeout_mode == TimeoutMode::PerIteration;
+            let attempt_timeout = timeout_ms.filter(|_| per_iteration);
+            let attempt = || match attempt_timeout {
+                Some(ms) => {
+                    if let Err(e) = run_with_timeout(ms, &test_body) {
+                        std::panic::resume_unwind(e);
+                    }
+                }
+                None => test_body(),
+            };
+
+            // Flaky tests get a default retry budget unless one was set explicitly.
+            let retries = retries.or(flaky.then_some(FLAKY_DEFAULT_RETRIES));
+            let with_retries = || {
+                if let Some(n) = retries {
+                    crate::with_retries(n, retry_backoff_ms.unwrap_or(0), attempt);
+                } else {
+                    attempt();
+                }
+            };
+
+            let with_repeat = || {
+                if let Some(n) = *repeat {
+                    crate::repeat(n, with_retries);
+                } else {
+                    with_retries();
+                }
+            };
+
+            let with_must_pass_repeatedly = || {
+                if let Some(n) = *must_pass_repeatedly {
+                    crate::must_pass_repeatedly(n, with_repeat);
+                } else {
+                    with_repeat();
+                }
+            };
+
+            let isolated = max_rss_mb.is_some() && config.isolate_memory_limits;
+            let capture = config.capture.then(Capture::start).flatten();
+            let outcome = match (*max_rss_mb, *timeout_ms) {
+                // The child re-runs this test, hooks and decorators included.
+                (Some(mb), _) if isolated => {
+                    attempts.set(1);
+                    crate::isolate::run_in_child(&full_path, mb)
+                }
+                (_, Some(ms)) if attempt_timeout.is_none() => {
+                    run_with_timeout(ms, &with_must_pass_repeatedly)
+                }
+                _ => catch_unwind(AssertUnwindSafe(with_must_pass_repeatedly)),
+            };
+            let output = capture.map(Capture::finish);
+            let attachments = crate::take_attachments();
+            crate::set_current_test(None);
+            let notes = crate::take_context_notes();
+            let assertions = crate::take_assertion_count();
+            let steps = crate::take_step_count();
+            // An isolated test counted its assertions in the child process.
+            let noted = crate::take_noted();
+            let checked = assertions > 0 || noted || isolated;
+            // A panic the test caught itself leaves a backtrace behind too,
+            // so only a failure that came out of a panic keeps one.
+            let backtrace = crate::take_backtrace().filter(|_| outcome.is_err());
+
+            // Check if the test called skip!() — report as skipped, not passed
+            if outcome.is_ok() {
+                let pending = crate::take_pending_reason();
+                if let Some(reason) = crate::take_skip_reason() {
+                    let record = result.record(&full_path, TestStatus::Skipped);
+                    record.duration = start.elapsed();
+                    record.message = Some(reason);
+                } else if let Some(reason) = pending {
+                    let record = result.record(&full_path, TestStatus::Pending);
+                    record.duration = start.elapsed();
+                    record.message = Some(reason);
+                } else if let Some(reason) = xfail {
+                    // Expected to fail but passed: the bug is fixed, so the
+                    // marker is stale and must be removed.
+                    let xpass: Box<dyn std::any::Any + Send> = Box::new(format!(
+                        "XPASS — expected failure ({reason}) passed; remove xfail"
+                    ));
+                    report_outcome(&full_path, Err(xpass), &[], start, result);
+                } else {
+                    let outcome = outcome.and_then(|()| require_assertions(config, checked));
+                    report_outcome(&full_path, outcome, &notes, start, result);
+                }
+            } else {
+                // Clear any skip or pending flag set before the panic
+                let _ = crate::take_skip_reason();
+                let _ = crate::take_pending_reason();
+                match outcome {
+                    Err(e) if xfail.is_some() => {
+                        let record = result.record(&full_path, TestStatus::XFailed);
+                        record.duration = start.elapsed();
+                        record.message = Some(panic_message(&*e));
+                    }
+                    Err(e) if *flaky && !config.strict_flaky => report_flaky(&full_path, e, start, result),
+                    _ => report_outcome(&full_path, outcome, &notes, start, result),
+                }
+            }
+            // Every branch above recorded this test last.
+            if let Some(record) = result.tests.last_mut() {
+                record.attempts = attempts.get();
+                record.owner = owner.map(str::to_string);
+                record.steps = steps;
+                record.assertions = assertions;
+                keep_attachments(record, attachments, config);
+                keep_output(record, output);
+            }
+            annotate_failure(result, *location, backtrace);
+            note_missing_assertions(result, checked);
+            crate::check_thread_local_invariants(&full_path);
+        }
+        TestNode::Ordered {
+            name,
+            labels,
+            continue_on_failure,
+            tolerate,
+            steps,
+            on_failure,
+        } => {
+            let full_path = {
+                let mut p = path.to_vec();
+                p.push(name.clone());
+                p.join(" > ")
+            };
+
+            // Filter check
+            let required = config.requires(&full_path);
+            if !required && !config.selects_path(&full_path) {
+                return;
+            }
+
+            // Focus mode: skip non-focused ordered tests unless include_ignored
+            // is set. A focused step focuses its sequence.
+            let focused_steps = has_focused_step(steps);
+            let effectively_focused =
+                force_focused || focused_steps || config.focus_file_matches(&full_path);
+            if focus_mode && !effectively_focused && !config.include_ignored && !required {
+                result.record(&full_path, TestStatus::Skipped);
+                return;
+            }
+
+            // Fail-on-focus CI check for focused steps and for ordered tests
+            // inside focused containers. A focused step is focus of its own,
+            // which a describe's `allow_focus` doesn't cover.
+            if focus_mode && (focused_steps || (force_focused && !hooks.allow_focus)) {
+                crate::check_fail_on_focus();
+            }
+
+            // Label check
+            let all_labels: Vec<&str> = hooks
+                .labels
+                .iter()
+                .copied()
+                .chain(labels.iter().map(|s| s.as_str()))
+                .collect();
+            if !required && !config.selects_test(&full_path, &all_labels, hooks.owner) {
+                return;
+            }
+
+            if config.dry_run {
+                record_dry_run(&full_path, result);
+                return;
+            }
+
+            crate::set_current_test(Some(crate::TestInfo {
+                name: name.clone(),
+                path: full_path.clone(),
+                labels: all_labels.iter().map(|s| s.to_string()).collect(),
+                owner: hooks.owner.map(str::to_string),
+                attachments: Vec::new(),
+            }));
+            let start = Instant::now();
+            crate::take_assertion_count();
+            crate::take_step_count();
+            crate::take_noted();
+            crate::install_panic_hook();
+            crate::take_backtrace();
+
+            let steps_started = std::cell::Cell::new(false);
+            // Steps that ran to completion, whose cleanups undo them on failure.
+            let completed = std::cell::RefCell::new(Vec::new());
+            // How each step went, for the reporters.
+            let outcomes = std::cell::RefCell::new(Vec::new());
+            let capture = config.capture.then(Capture::start).flatten();
+            let sequence = || {
+                // Run before_each + just_before_each + steps, catching any panic
+                // so that after_each and cleanups are guaranteed to run.
+                let body_result = catch_unwind(AssertUnwindSafe(|| {
+                    crate::capture_logs(|| {
+                        for hook in &hooks.before_each {
+                            crate::spy::record(HookPhase::BeforeEach);
+                            hook();
+                        }
+                        for hook in &hooks.just_before_each {
+                            crate::spy::record(HookPhase::JustBeforeEach);
+                            hook();
+                        }
+
+                        let mut failures: Vec<String> = Vec::new();
+                        let total = steps.len();
+                        let _marker = CoverageMarker::begin(config, &full_path);
+
+                        // Every step gets an outcome, so a failure shows what
+                        // came before it and what it kept from running.
+                        let outcome = |step: &OrderedStep, status, message: Option<String>| {
+                            outcomes.borrow_mut().push(StepResult {
+                                name: step.name.clone(),
+                                status,
+                                message,
+                            });
+                        };
+                        for (i, step) in steps.iter().enumerate() {
+                            if step.pending {
+                                outcome(step, TestStatus::Pending, None);
+                                continue;
+                            }
+                            if focused_steps && !step.focused {
+                                let reason = Some("not focused".to_string());
+                                outcome(step, TestStatus::Skipped, reason);
+                                continue;
+                            }
+                            steps_started.set(true);
+                            match catch_unwind(AssertUnwindSafe(|| (step.body)())) {
+                                Ok(()) => {
+                                    outcome(step, TestStatus::Passed, None);
+                                    completed.borrow_mut().push(step);
+                                }
+                                Err(e) => {
+                                    let msg = panic_message(&*e);
+                                    outcome(step, TestStatus::Failed, Some(msg.clone()));
+                                    if !*continue_on_failure {
+                                        for rest in &steps[i + 1..] {
+                                            let reason = Some("an earlier step failed".to_string());
+                                            outcome(rest, TestStatus::Skipped, reason);
+                                        }
+                                        std::panic::resume_unwind(e);
+                                    }
+                                    failures.push(format!("{}: {msg}", step.name));
+                                }
+                            }
+                        }
+
+                        let summary = format!("{} of {total} ordered steps failed", failures.len());
+                        let list: String = failures.iter().map(|f| format!("\n  {f}")).collect();
+                        if failures.len() > *tolerate {
+                            let tolerated = if *tolerate > 0 {
+                                format!(" (tolerating {tolerate})")
+                            } else {
+                                String::new()
+                            };
+                            panic!("{summary}{tolerated}:{list}");
+                        } else if !failures.is_empty() {
+                            diag!("  tolerated: {summary}:{list}");
+                        }
+                    })
+                }));
+
+                // Roll back (LIFO) before teardown when a step failed: first
+                // the cleanups of the steps that completed, then `on_failure`.
+                // A rollback panic is reported but doesn't replace the step failure.
+                if body_result.is_err() && steps_started.get() {
+                    for step in completed.borrow().iter().rev() {
+                        let Some(cleanup) = &step.cleanup else { continue };
+                        if let Err(e) = catch_unwind(AssertUnwindSafe(cleanup)) {
+                            let msg = panic_message(&*e);
+                            diag!("  warning: cleanup of step '{}' panicked: {msg}", step.name);
+                        }
+                    }
+                    for rollback in on_failure.iter().rev() {
+                        if let Err(e) = catch_unwind(AssertUnwindSafe(rollback)) {
+                            let msg = panic_message(&*e);
+                            diag!("  warning: on_failure rollback panicked: {msg}");
+                        }
+                    }
+                }
+
+                // after_each (innermost first) — each individually protected
+                crate::set_body_panicked(body_result.is_err());
+                let mut after_each_panic = None;
+                for hook in hooks.after_each.iter().rev() {
+                    crate::spy::record(HookPhase::AfterEach);
+                    if let Err(e) = catch_unwind(AssertUnwindSafe(hook)) {
+                        diag!("  warning: after_each hook panicked");
+                        if after_each_panic.is_none() {
+                            after_each_panic = Some(e);
+                        }
+                    }
+                }
+
+                crate::run_deferred_cleanups();
+
+                // Propagate the first failure: body takes priority over after_each
+                if let Err(e) = body_result {
+                    std::panic::resume_unwind(e);
+                }
+                if let Some(e) = after_each_panic {
+                    std::panic::resume_unwind(e);
+                }
+            };
+            // A default timeout covers the whole sequence, hooks included.
+            let outcome = match config.default_timeout_ms {
+                Some(ms) => run_with_timeout(ms, &sequence),
+                None => catch_unwind(AssertUnwindSafe(sequence)),
+            };
+
+            let output = capture.map(Capture::finish);
+            let attachments = crate::take_attachments();
+            crate::set_current_test(None);
+            let notes = crate::take_context_notes();
+            let assertions = crate::take_assertion_count();
+            let steps = crate::take_step_count();
+            let noted = crate::take_noted();
+            let checked = assertions > 0 || noted;
+            let backtrace = crate::take_backtrace().filter(|_| outcome.is_err());
+
+            let outcome = outcome.and_then(|()| require_assertions(config, checked));
+            report_outcome(&full_path, outcome, &notes, start, result);
+            if let Some(record) = result.tests.last_mut() {
+                record.attempts = 1;
+                record.owner = hooks.owner.map(str::to_string);
+                record.steps = steps;
+                record.assertions = assertions;
+                record.ordered_steps = outcomes.take();
+                keep_attachments(record, attachments, config);
+                keep_output(record, output);
+            }
+            annotate_failure(result, None, backtrace);
+            note_missing_assertions(result, checked);
+            crate::check_thread_local_invariants(&full_path);
+        }
+    }
+}
+
+/// Message of the tests a `--dry-run` selected.
+const DRY_RUN: &str = "dry run";
+
+/// Count a test `--dry-run` selected, without running it or its hooks.
+fn record_dry_run(path: &str, result: &mut RunResult) {
+    result.record(path, TestStatus::Skipped).message = Some(DRY_RUN.to_string());
+    result.would_run += 1;
+}
+
+/// `--strict-assertions`: fail a test that passed without a counted
+/// assertion, unless it called [`noted`](crate::noted).
+fn require_assertions(config: &RunConfig, checked: bool) -> std::thread::Result<()> {
+    if config.strict_assertions && !checked {
+        return Err(Box::new("test passed but made no assertions".to_string()));
+    }
+    Ok(())
+}
+
+/// Add where the test is defined and the panic's `backtrace` to the failure
+/// of the test just recorded, if it failed.
+fn annotate_failure(
+    result: &mut RunResult,
+    location: Option<&Location>,
+    backtrace: Option<String>,
+) {
+    if result.tests.last().is_none_or(|t| t.status != TestStatus::Failed) {
+        return;
+    }
+    if let Some(failure) = result.failures.last_mut() {
+        failure.location = location.map(|at| format!("{}:{}", at.file(), at.line()));
+        failure.backtrace = backtrace;
+    }
+}
+
+/// With `RSSPEC_WARN_NO_ASSERT`, remember the test just recorded if it
+/// passed without a single counted assertion (or [`noted`](crate::noted)).
+fn note_missing_assertions(result: &mut RunResult, checked: bool) {
+    if checked || !crate::warn_no_assert_enabled() {
+        return;
+    }
+    if let Some(record) = result.tests.last() {
+        if record.status == TestStatus::Passed {
+            result.no_assertions.push(record.path.clone());
+        }
+    }
+}
+
+/// Keep `attachments` on the record of a test that didn't pass (or always,
+/// with `--keep-artifacts`); otherwise delete what was saved for them.
+fn keep_attachments(record: &mut TestRecord, attachments: Vec<Attachment>, config: &RunConfig) {
+    let failed = matches!(
+        record.status,
+        TestStatus::Failed | TestStatus::Flaky | TestStatus::XFailed
+    );
+    if failed || config.keep_artifacts {
+        record.attachments = attachments;
+    } else {
+        crate::artifacts::discard(attachments);
+    }
+}
+
+/// Keep the `--capture` output of a test that failed (or failed and is
+/// flaky) on its record, to be shown under its error.
+fn keep_output(record: &mut TestRecord, output: Option<String>) {
+    if matches!(record.status, TestStatus::Failed | TestStatus::Flaky) {
+        record.output = output.filter(|text| !text.is_empty());
+    }
+}
+
+/// Brackets a test body with `--coverage-markers` lines on stdout. The end
+/// marker is printed on drop, so a panicking body still closes its slice.
+struct CoverageMarker<'a>(Option<&'a str>);
+
+impl<'a> CoverageMarker<'a> {
+    fn begin(config: &RunConfig, path: &'a str) -> Self {
+        let path = config.coverage_markers.then_some(path);
+        if let Some(path) = path {
+            Self::print("BEGIN", path);
+        }
+        CoverageMarker(path)
+    }
+
+    fn print(edge: &str, path: &str) {
+        // Flushed right away, so tools tailing stdout see the marker while
+        // the body runs rather than when the buffer fills. Not captured by
+        // `--capture`, which would otherwise hide it.
+        crate::capture::print_uncaptured(&format!("##RSSPEC_TEST_{edge} {path}##"));
+    }
+}
+
+impl Drop for CoverageMarker<'_> {
+    fn drop(&mut self) {
+        if let Some(path) = self.0 {
+            Self::print("END", path);
+        }
+    }
+}
+
+/// Fail the current attempt if the test body ran longer than `budget_ms`.
+fn check_max_duration(elapsed: std::time::Duration, budget_ms: u64) {
+    if elapsed.as_millis() > u128::from(budget_ms) {
+        panic!("took {}ms, budget {budget_ms}ms", elapsed.as_millis());
+    }
+}
+
+/// Return a skip reason if any of `depends_on`, full paths resolved by
+/// [`Dependencies`], hasn't run or didn't pass.
+///
+/// Sibling ordering runs dependencies first, so one that hasn't run was left
+/// out of this process (a `--jobs` worker only runs its own group).
+fn unmet_dependency(depends_on: &[String], result: &RunResult) -> Option<String> {
+    depends_on.iter().find_map(|dep| match result.status_of(dep) {
+        Some(TestStatus::Passed) => None,
+        Some(_) => Some(format!("dependency '{dep}' did not pass")),
+        None => Some(format!("dependency '{dep}' has not run")),
+    })
+}
+
+/// Mark all descendant It nodes as pending (for xdescribe).
+fn run_nodes_pending(
+    nodes: &[TestNode],
+    path: &[String],
+    reporter: &mut dyn Reporter,
+    result: &mut RunResult,
+) {
+    for node in nodes {
+        let mut node_path = path.to_vec();
+        match node {
+            TestNode::Describe { name, children, .. } => {
+                node_path.push(name.clone());
+                let full_path = node_path.join(" > ");
+                reporter.describe_entered(&full_path, name);
+                run_nodes_pending(children, &node_path, reporter, result);
+                reporter.describe_finished(&full_path);
+            }
+            TestNode::It { name, .. } | TestNode::Ordered { name, .. } => {
+                node_path.push(name.clone());
+                result.record(&node_path.join(" > "), TestStatus::Pending);
+                report_new_tests(reporter, result);
+            }
+        }
+    }
+}
+
+fn report_outcome(
+    full_path: &str,
+    outcome: Result<(), Box<dyn std::any::Any + Send>>,
+    notes: &[String],
+    start: Instant,
+    result: &mut RunResult,
+) {
+    let elapsed = start.elapsed();
+    match outcome {
+        Ok(()) => {
+            result.record(full_path, TestStatus::Passed).duration = elapsed;
+        }
+        Err(e) => {
+            let mut msg = panic_message(&*e);
+            // Context notes, most recent first
+            for note in notes {
+                msg.push_str(&format!("\n  note: {note}"));
+            }
+            let record = result.record(full_path, TestStatus::Failed);
+            record.duration = elapsed;
+            record.message = Some(msg.clone());
+            result.failures.push(Failure::new(full_path, msg));
+        }
+    }
+}
+
+/// Report a flaky test that failed after exhausting its retries. The failure
+/// is quarantined: listed in the summary but not counted as a real failure.
+fn report_flaky(
+    full_path: &str,
+    e: Box<dyn std::any::Any + Send>,
+    start: Instant,
+    result: &mut RunResult,
+) {
+    let msg = panic_message(&*e);
+    let elapsed = start.elapsed();
+    let record = result.record(full_path, TestStatus::Flaky);
+    record.duration = elapsed;
+    record.message = Some(msg.clone());
+    result.flaky_failures.push(Failure::new(full_path, msg));
+}
+
+thread_local! {
+    /// Deadline of the innermost [`run_with_timeout`], for `Send` bodies.
+    static DEADLINE: std::cell::Cell<Option<Instant>> = const { std::cell::Cell::new(None) };
+}
+
+/// Panic payload of a `Send` body abandoned at its deadline.
+pub(crate) struct TimedOut;
+
+/// Run a `Send` test body. Under a timeout it runs on a worker thread for
+/// whatever is left of the deadline, and is abandoned if it doesn't finish.
+fn run_send_body(body: &Arc<dyn Fn() + Send + Sync>) {
+    let Some(deadline) = DEADLINE.with(|d| d.get()) else {
+        return body();
+    };
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        std::panic::resume_unwind(Box::new(TimedOut));
+    }
+    match crate::run_on_thread(Arc::clone(body), remaining) {
+        Some(Ok(())) => {}
+        Some(Err(e)) => std::panic::resume_unwind(e),
+        None => std::panic::resume_unwind(Box::new(TimedOut)),
+    }
+}
+
+/// Run a closure with a timeout.
+///
+/// The closure runs on the current thread and an overrun is detected once
+/// it returns, since the thread can't be aborted. A `Send` test body inside
+/// it (see [`run_send_body`]) runs on a worker thread instead and is
+/// abandoned at the deadline, so a hung `it_send` test fails rather than
+/// hanging the run.
+fn run_with_timeout(
+    ms: u64,
+    f: &dyn Fn(),
+) -> Result<(), Box<dyn std::any::Any + Send>> {
+    use std::time::Duration;
+
+    let start = Instant::now();
+    let deadline = Duration::from_millis(ms);
+
+    // Run the closure on the current thread
+    // (Cleanups are already handled inside test_body before any panic re-raises.)
+    let outer = DEADLINE.with(|d| d.replace(Some(start + deadline)));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        f();
+    }));
+    DEADLINE.with(|d| d.set(outer));
+
+    // These failures come from the deadline rather than a panic, so a
+    // backtrace from a panic the test caught doesn't belong to them.
+    if matches!(&result, Err(e) if e.is::<TimedOut>()) {
+        crate::take_backtrace();
+        return Err(Box::new(format!(
+            "test timed out after {ms}ms (its thread was abandoned and may still be running)"
+        )));
+    }
+
+    // Check if the closure exceeded the deadline
+    if start.elapsed() > deadline {
+        // If the test also panicked, include the original error
+        if let Err(e) = result {
+            let msg = panic_message(&*e);
+            Err(Box::new(format!("test timed out after {ms}ms (original error: {msg})")))
+        } else {
+            crate::take_backtrace();
+            Err(Box::new(format!("test timed out after {ms}ms")))
+        }
+    } else {
+        result
+    }
+}
+
+/// The default [`Reporter`]: the colored, indented tree and summary.
+pub(crate) struct TreeReporter {
+    /// Print a character per test (`--quiet`) instead of the tree.
+    quiet: bool,
+    /// Characters printed on the current `--quiet` line.
+    dots: usize,
+    dedupe_failures: bool,
+    timing: bool,
+    show_steps: bool,
+    dry_run: bool,
+    summary_format: SummaryFormat,
+    bail: Option<usize>,
+    /// Full paths of the describes currently entered, outermost first.
+    scopes: Vec<String>,
+    suites_started: usize,
+}
+
+impl TreeReporter {
+    pub(crate) fn new(config: &RunConfig) -> Self {
+        TreeReporter {
+            quiet: config.quiet,
+            dots: 0,
+            dedupe_failures: config.dedupe_failures,
+            timing: config.timing,
+            show_steps: config.show_steps,
+            dry_run: config.dry_run,
+            summary_format: config.summary_format,
+            bail: config.bail,
+            scopes: Vec::new(),
+            suites_started: 0,
+        }
+    }
+
+    fn indent(&self) -> String {
+        "  ".repeat(self.scopes.len())
+    }
+
+    /// `--quiet`: print the character for `test`, wrapping lines like libtest.
+    fn print_dot(&mut self, test: &TestResult) {
+        let message = test.message.as_deref().unwrap_or_default();
+        let dot = match test.status {
+            TestStatus::Passed => green("."),
+            TestStatus::Failed => red("F"),
+            TestStatus::Flaky => yellow("~"),
+            TestStatus::XFailed => yellow("x"),
+            TestStatus::Pending => yellow("*"),
+            TestStatus::Skipped if self.dry_run && message == DRY_RUN => dim("·"),
+            TestStatus::Skipped if message.is_empty() => return,
+            TestStatus::Skipped => dim("s"),
+        };
+        print!("{dot}");
+        self.dots += 1;
+        if self.dots == QUIET_LINE_WIDTH {
+            println!();
+            self.dots = 0;
+        }
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+    }
+}
+
+/// Characters per `--quiet` line, as in libtest.
+const QUIET_LINE_WIDTH: usize = 88;
+
+impl Reporter for TreeReporter {
+    fn run_started(&mut self, seed: Option<u64>) {
+        self.suites_started = 0;
+        println!();
+        if let Some(seed) = seed {
+            println!("{}", dim(&format!("Randomized with seed {seed}")));
+            println!();
+        }
+    }
+
+    fn suite_started(&mut self, name: &str) {
+        if self.quiet {
+            return;
+        }
+        if self.suites_started > 0 {
+            println!();
+        }
+        self.suites_started += 1;
+        if !name.is_empty() {
+            println!("{}", dim(&format!("--- {name} ---")));
+            println!();
+        }
+    }
+
+    fn describe_entered(&mut self, path: &str, name: &str) {
+        if !self.quiet {
+            println!("{}{}", self.indent(), bold(name));
+        }
+        self.scopes.push(path.to_string());
+    }
+
+    fn describe_finished(&mut self, _path: &str) {
+        self.scopes.pop();
+    }
+
+    fn it_finished(&mut self, test: &TestResult) {
+        if self.quiet {
+            self.print_dot(test);
+            return;
+        }
+        let indent = self.indent();
+        // Relative to the innermost describe entered; tests reported
+        // without their describes (e.g. a skipped suite) show the full path.
+        let name = self
+            .scopes
+            .last()
+            .and_then(|scope| test.path.strip_prefix(scope.as_str()))
+            .and_then(|rest| rest.strip_prefix(" > "))
+            .unwrap_or(&test.path);
+        let ms = test.duration.as_millis();
+        let mut time_str = if ms > 100 {
+            format!(" {}", dim(&format!("({ms}ms)")))
+        } else {
+            String::new()
+        };
+        if self.show_steps && test.attempts > 0 {
+            time_str.push_str(&format!(" {}", dim(&step_counts(test))));
+        }
+        let message = test.message.as_deref().unwrap_or_default();
+        let error = crate::messages::messages().error;
+        match test.status {
+            TestStatus::Passed => {
+                println!("{indent}{} {name}{time_str}", green("✓"));
+                print_steps(&indent, &test.ordered_steps);
+            }
+            TestStatus::Failed => {
+                println!("{indent}{} {}{time_str}", red("✗"), red(name));
+                print_steps(&indent, &test.ordered_steps);
+                println!("{indent}  {}", red(&format!("{error} {message}")));
+                print_output(&indent, test.output.as_deref());
+            }
+            TestStatus::Flaky => {
+                println!("{indent}{} {} {}{time_str}", yellow("~"), yellow(name), dim("(flaky)"));
+                println!("{indent}  {}", yellow(&format!("{error} {message}")));
+                print_output(&indent, test.output.as_deref());
+            }
+            TestStatus::XFailed => println!("{indent}{} {name} {}", yellow("✗"), dim("(xfail)")),
+            TestStatus::Pending if message.is_empty() => {
+                println!("{indent}{} {}", yellow("-"), dim(name));
+            }
+            TestStatus::Pending => {
+                let reason = yellow(&format!("({message})"));
+                println!("{indent}{} {} {reason}", yellow("-"), dim(name));
+            }
+            TestStatus::Skipped if self.dry_run && message == DRY_RUN => {
+                println!("{indent}{} {name}", dim("·"));
+            }
+            // Tests skipped by focus mode carry no reason and stay quiet.
+            TestStatus::Skipped if message.is_empty() => {}
+            TestStatus::Skipped => {
+                println!("{indent}{} {} {}", yellow("-"), dim(name), dim(&format!("({message})")));
+            }
+        }
+    }
+
+    fn hook_failed(&mut self, path: &str, hook: &str, message: &str) {
+        // Listed with the failures in the summary.
+        if self.quiet {
+            return;
+        }
+        let indent = self.indent();
+        if self.scopes.last().is_some_and(|scope| scope == path) {
+            println!("{indent}{} {hook} failed: {}", red("✗"), red(message));
+        } else {
+            println!("{indent}{} {}: {}", red("✗"), red(path), red(message));
+        }
+    }
+
+    fn run_finished(&mut self, summary: &RunSummary) {
+        if self.dots > 0 {
+            println!();
+        }
+        print_summary(summary, self);
+    }
+}
+
+/// Print an ordered block's steps under its line, numbered, with ✓ or ✗ and
+/// the panic message, or why a step didn't run.
+fn print_steps(indent: &str, steps: &[StepResult]) {
+    let total = steps.len();
+    for (i, step) in steps.iter().enumerate() {
+        let n = format!("[{}/{total}]", i + 1);
+        let name = &step.name;
+        let message = step.message.as_deref().unwrap_or_default();
+        match step.status {
+            TestStatus::Passed => println!("{indent}  {} {n} {name}", green("✓")),
+            TestStatus::Failed => {
+                println!("{indent}  {} {n} {}", red("✗"), red(&format!("{name}: {message}")));
+            }
+            TestStatus::Pending => println!("{indent}  {} {n} {}", yellow("-"), dim(name)),
+            _ => {
+                let reason = dim(&format!("({message})"));
+                println!("{indent}  {} {n} {} {reason}", yellow("-"), dim(name));
+            }
+        }
+    }
+}
+
+/// Print a failed test's `--capture` output under its error line.
+fn print_output(indent: &str, output: Option<&str>) {
+    let Some(output) = output else {
+        return;
+    };
+    println!("{indent}  {}", dim(crate::messages::messages().output));
+    for line in output.lines() {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("{indent}    {line}");
+        }
+    }
+}
+
+fn print_summary(result: &RunSummary, config: &TreeReporter) {
+    let elapsed = result.duration;
+    let elapsed_str = format!("{:.3}s", elapsed.as_secs_f64());
+    let m = crate::messages::messages();
+
+    if config.dry_run {
+        println!();
+        println!("{}", bold(&format!("{} {}", result.would_run, m.would_run)));
+        return;
+    }
+
+    let mut parts: Vec<String> = [
+        (result.passed > 0).then(|| green(&format!("{} {}", result.passed, m.passed))),
+        (result.failed > 0).then(|| red(&format!("{} {}", result.failed, m.failed))),
+        (result.flaky > 0).then(|| yellow(&format!("{} {}", result.flaky, m.flaky))),
+        (result.xfailed > 0).then(|| yellow(&format!("{} {}", result.xfailed, m.xfailed))),
+        (result.pending > 0).then(|| yellow(&format!("{} {}", result.pending, m.pending))),
+        (result.skipped > 0).then(|| dim(&format!("{} {}", result.skipped, m.skipped))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    // Avoid an empty summary line when all tests are filtered out
+    if parts.is_empty() {
+        parts.push(dim(&format!("0 {}", m.matched)));
+    }
+
+    let summary = format!("{} ({})", parts.join(", "), dim(&elapsed_str));
+
+    println!();
+    if result.failed > 0 {
+        println!("{}", red(m.fail));
+        println!("{summary}");
+        println!();
+        println!("{}", m.failures);
+        if config.dedupe_failures {
+            for (i, (message, paths)) in group_failures(&result.failures).iter().enumerate() {
+                if let [path] = paths.as_slice() {
+                    println!("  {}. {path}: {message}", i + 1);
+                } else {
+                    println!("  {}. {} tests failed with: {message}", i + 1, paths.len());
+                    for path in paths {
+                        println!("       {}", dim(path));
+                    }
+                }
+            }
+        } else {
+            for (i, failure) in result.failures.iter().enumerate() {
+                println!("  {}. {}", i + 1, failure);
+                if let Some(location) = &failure.location {
+                    println!("     {}", dim(&format!("at {location}")));
+                }
+                if let Some(backtrace) = &failure.backtrace {
+                    for line in backtrace.lines() {
+                        println!("     {}", dim(line));
+                    }
+                }
+            }
+        }
+        println!();
+
+        let attached: Vec<&TestResult> = result
+            .tests
+            .iter()
+            .filter(|t| t.status == TestStatus::Failed && !t.attachments.is_empty())
+            .collect();
+        if !attached.is_empty() {
+            println!("{}", m.artifacts);
+            for test in attached {
+                println!("  {}", test.path);
+                for attachment in &test.attachments {
+                    let path = attachment.path.display().to_string();
+                    println!("       {}: {}", attachment.name, dim(&path));
+                }
+            }
+            println!();
+        }
+
+        let by_owner = failures_by_owner(&result.tests);
+        if by_owner.iter().any(|(owner, _)| owner.is_some()) {
+            println!("{}", m.failures_by_owner);
+            for (owner, paths) in by_owner {
+                println!("  {} ({})", owner.unwrap_or("(no owner)"), paths.len());
+                for path in paths {
+                    println!("       {}", dim(path));
+                }
+            }
+            println!();
+        }
+    } else {
+        println!("{}", green(m.pass));
+        println!("{summary}");
+    }
+
+    if result.bailed {
+        let limit = config.bail.unwrap_or(1);
+        let noun = if limit == 1 { "failure" } else { "failures" };
+        let notice = format!("Bailed after {limit} {noun}; remaining tests were not run.");
+        println!("{}", yellow(&notice));
+        println!();
+    }
+
+    if !result.flaky_failures.is_empty() {
+        if result.failed == 0 {
+            println!();
+        }
+        println!("{}", m.flaky_failures);
+        for (i, failure) in result.flaky_failures.iter().enumerate() {
+            println!("  {}. {}", i + 1, failure);
+        }
+        println!();
+    }
+
+    if !result.no_assertions.is_empty() {
+        if result.failed == 0 && result.flaky_failures.is_empty() {
+            println!();
+        }
+        println!("{}", yellow(m.no_assertions));
+        for path in &result.no_assertions {
+            println!("  {}", yellow(path));
+        }
+        println!();
+    }
+
+    if config.timing && !result.scope_timings.is_empty() {
+        if result.failed == 0 && result.flaky_failures.is_empty() && result.no_assertions.is_empty()
+        {
+            println!();
+        }
+        println!("{}", m.scope_timings);
+        for line in scope_timing_lines(&result.scope_timings) {
+            println!("  {line}");
+        }
+        println!();
+    }
+
+    if config.summary_format == SummaryFormat::Nextest {
+        println!("{}", nextest_summary_line(result));
+    }
+}
+
+/// `--show-steps`: e.g. `(3 steps, 1 assertion)`.
+fn step_counts(test: &TestResult) -> String {
+    let plural = |n: u32, what: &str| match n {
+        1 => format!("1 {what}"),
+        n => format!("{n} {what}s"),
+    };
+    format!("({}, {})", plural(test.steps, "step"), plural(test.assertions, "assertion"))
+}
+
+/// One line per scope for `--timing`, costliest hooks first, e.g.
+/// `db: before_all 2.100s, after_all 0.300s, 12 tests`.
+fn scope_timing_lines(timings: &[ScopeTiming]) -> Vec<String> {
+    let mut timings: Vec<&ScopeTiming> = timings.iter().collect();
+    timings.sort_by_key(|t| std::cmp::Reverse(t.before_all + t.after_all));
+    timings
+        .iter()
+        .map(|t| {
+            format!(
+                "{}: before_all {:.3}s, after_all {:.3}s, {} {}",
+                t.path,
+                t.before_all.as_secs_f64(),
+                t.after_all.as_secs_f64(),
+                t.tests,
+                if t.tests == 1 { "test" } else { "tests" },
+            )
+        })
+        .collect()
+}
+
+/// A summary line shaped like cargo-nextest's, e.g.
+/// `     Summary [   1.234s] 42 tests run: 40 passed, 2 failed, 0 skipped`.
+///
+/// Flaky and xfailed tests don't fail the run, so they count as passed;
+/// pending tests count as skipped.
+fn nextest_summary_line(result: &RunSummary) -> String {
+    let passed = result.passed + result.flaky + result.xfailed;
+    let run = passed + result.failed;
+    let mut line = format!(
+        "{:>12} [{:>8.3}s] {run} {} run: {passed} passed",
+        "Summary",
+        result.duration.as_secs_f64(),
+        if run == 1 { "test" } else { "tests" },
+    );
+    if result.flaky > 0 {
+        line.push_str(&format!(" ({} flaky)", result.flaky));
+    }
+    if result.failed > 0 {
+        line.push_str(&format!(", {} failed", result.failed));
+    }
+    line.push_str(&format!(", {} skipped", result.skipped + result.pending));
+    line
+}
+
+/// Group failures by identical message, in order of first occurrence.
+fn group_failures(failures: &[Failure]) -> Vec<(&str, Vec<&str>)> {
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for failure in failures {
+        match groups.iter_mut().find(|(msg, _)| *msg == failure.message) {
+            Some((_, paths)) => paths.push(&failure.path),
+            None => groups.push((&failure.message, vec![&failure.path])),
+        }
+    }
+    groups
+}
+
+/// Paths of failed tests grouped by owner, in order of first occurrence.
+fn failures_by_owner(tests: &[TestResult]) -> Vec<(Option<&str>, Vec<&str>)> {
+    let mut groups: Vec<(Option<&str>, Vec<&str>)> = Vec::new();
+    for test in tests.iter().filter(|t| t.status == TestStatus::Failed) {
+        let owner = test.owner.as_deref();
+        match groups.iter_mut().find(|(o, _)| *o == owner) {
+            Some((_, paths)) => paths.push(&test.path),
+            None => groups.push((owner, vec![&test.path])),
+        }
+    }
+    groups
+}
+
+fn list_tree(nodes: &[TestNode], path: &[String], config: &RunConfig) {
+    for node in nodes {
+        match node {
+            TestNode::Describe { name, children, .. } => {
+                let mut child_path = path.to_vec();
+                child_path.push(name.clone());
+                list_tree(children, &child_path, config);
+            }
+            TestNode::It { name, pending, .. } => {
+                let full_path = {
+                    let mut p = path.to_vec();
+                    p.push(name.clone());
+                    p.join(" > ")
+                };
+
+                if !config.selects_path(&full_path) {
+                    continue;
+                }
+
+                if *pending {
+                    println!("{full_path} (pending)");
+                } else {
+                    println!("{full_path}");
+                }
+            }
+            TestNode::Ordered { name, .. } => {
+                let full_path = {
+                    let mut p = path.to_vec();
+                    p.push(name.clone());
+                    p.join(" > ")
+                };
+
+                if !config.selects_path(&full_path) {
+                    continue;
+                }
+
+                println!("{full_path}");
+            }
+        }
+    }
+}
+
+/// Render the structure of a tree as an indented outline, one line per
+/// describe, test and ordered step, in declaration order. Tests start with
+/// `- `; focus and pending markers are kept, nothing else (labels, hooks,
+/// decorators) is.
+pub(crate) fn outline(nodes: &[TestNode]) -> String {
+    fn render(nodes: &[TestNode], depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let marker = |focused: bool, pending: bool| match (focused, pending) {
+            (_, true) => " (pending)",
+            (true, false) => " (focused)",
+            (false, false) => "",
+        };
+        for node in nodes {
+            match node {
+                TestNode::Describe {
+                    name,
+                    focused,
+                    pending,
+                    children,
+                    ..
+                } => {
+                    out.push_str(&format!("{indent}{name}{}\n", marker(*focused, *pending)));
+                    render(children, depth + 1, out);
+                }
+                TestNode::It {
+                    name,
+                    focused,
+                    pending,
+                    ..
+                } => {
+                    out.push_str(&format!("{indent}- {name}{}\n", marker(*focused, *pending)));
+                }
+                TestNode::Ordered { name, steps, .. } => {
+                    out.push_str(&format!("{indent}- {name} (ordered)\n"));
+                    for (i, step) in steps.iter().enumerate() {
+                        let step_marker = marker(step.focused, step.pending);
+                        let n = i + 1;
+                        out.push_str(&format!("{indent}    {n}. {}{step_marker}\n", step.name));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    render(nodes, 0, &mut out);
+    out
+}
+
+/// Aggregate counts over a test tree, for `--list-tags`/`--list-owners`.
+///
+/// Counts every test matching the path filter, whether or not focus mode
+/// would run it, so pending and focused tests show up in the totals.
+#[derive(Default, Debug)]
+struct Inventory {
+    total: usize,
+    pending: usize,
+    focused: usize,
+    unlabeled: usize,
+    unowned: usize,
+    by_label: BTreeMap<String, usize>,
+    by_owner: BTreeMap<String, usize>,
+}
+
+impl Inventory {
+    fn collect(
+        &mut self,
+        nodes: &[TestNode],
+        path: &[String],
+        hooks: &HookChain,
+        focused: bool,
+        pending: bool,
+        config: &RunConfig,
+    ) {
+        for node in nodes {
+            match node {
+                TestNode::Describe {
+                    name,
+                    focused: describe_focused,
+                    pending: describe_pending,
+                    children,
+                    ..
+                } => {
+                    let mut child_path = path.to_vec();
+                    child_path.push(name.clone());
+                    self.collect(
+                        children,
+                        &child_path,
+                        &hooks.with_describe(node),
+                        focused || *describe_focused,
+                        pending || *describe_pending,
+                        config,
+                    );
+                }
+                TestNode::It {
+                    name,
+                    focused: it_focused,
+                    pending: it_pending,
+                    labels,
+                    owner,
+                    ..
+                } => self.add(
+                    path,
+                    name,
+                    hooks,
+                    labels,
+                    owner.as_deref().or(hooks.owner),
+                    focused || *it_focused,
+                    pending || *it_pending,
+                    config,
+                ),
+                TestNode::Ordered { name, labels, .. } => {
+                    self.add(path, name, hooks, labels, hooks.owner, focused, pending, config)
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add(
+        &mut self,
+        path: &[String],
+        name: &str,
+        hooks: &HookChain,
+        labels: &[String],
+        owner: Option<&str>,
+        focused: bool,
+        pending: bool,
+        config: &RunConfig,
+    ) {
+        let full_path = path
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(name))
+            .collect::<Vec<_>>()
+            .join(" > ");
+        if !config.selects_path(&full_path) {
+            return;
+        }
+
+        self.total += 1;
+        self.pending += usize::from(pending);
+        self.focused += usize::from(focused);
+
+        let mut all_labels: Vec<&str> = hooks
+            .labels
+            .iter()
+            .copied()
+            .chain(labels.iter().map(|s| s.as_str()))
+            .collect();
+        all_labels.sort_unstable();
+        all_labels.dedup();
+        if all_labels.is_empty() {
+            self.unlabeled += 1;
+        }
+        for label in all_labels {
+            *self.by_label.entry(label.to_string()).or_default() += 1;
+        }
+
+        match owner {
+            Some(owner) => *self.by_owner.entry(owner.to_string()).or_default() += 1,
+            None => self.unowned += 1,
+        }
+    }
+
+    fn print(&self, view: InventoryView) {
+        println!("{} tests", self.total);
+        println!("  pending:   {}", self.pending);
+        println!("  focused:   {}", self.focused);
+        println!("  unlabeled: {}", self.unlabeled);
+        println!("  no owner:  {}", self.unowned);
+        println!();
+
+        let (title, counts) = match view {
+            InventoryView::Tags => ("Labels", &self.by_label),
+            InventoryView::Owners => ("Owners", &self.by_owner),
+        };
+        println!("{title}:");
+        if counts.is_empty() {
+            println!("  {}", dim("(none)"));
+        }
+        let width = counts.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+        for (key, count) in counts {
+            println!("  {key:<width$}  {count}");
+        }
+    }
+}
+
+fn tree_has_focus(nodes: &[TestNode]) -> bool {
+    nodes.iter().any(|node| match node {
+        TestNode::It { focused, .. } => *focused,
+        TestNode::Describe {
+            focused, children, ..
+        } => *focused || tree_has_focus(children),
+        TestNode::Ordered { steps, .. } => has_focused_step(steps),
+    })
+}
+
+/// Every resource declared anywhere in `node`'s subtree, sorted. A `--jobs`
+/// worker runs a whole top-level node, so it holds all of them.
+fn subtree_resources(node: &TestNode) -> Vec<&str> {
+    fn collect<'a>(node: &'a TestNode, out: &mut Vec<&'a str>) {
+        match node {
+            TestNode::It { resources, .. } => out.extend(resources.iter().map(String::as_str)),
+            TestNode::Describe {
+                resources,
+                children,
+                ..
+            } => {
+                out.extend(resources.iter().map(String::as_str));
+                for child in children {
+                    collect(child, out);
+                }
+            }
+            TestNode::Ordered { .. } => {}
+        }
+    }
+    let mut out = Vec::new();
+    collect(node, &mut out);
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// Whether a sequence has a focused step, which focuses the whole sequence.
+fn has_focused_step(steps: &[OrderedStep]) -> bool {
+    steps.iter().any(|step| step.focused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// `node` with the named fields of its `variant` set, for settings that
+    /// have no constructor: `with_fields!(TestNode::it(..), It { repeat: Some(3) })`.
+    macro_rules! with_fields {
+        ($node:expr, $variant:ident { $($field:ident: $value:expr),* $(,)? }) => {{
+            let mut node = $node;
+            let TestNode::$variant { $($field,)* .. } = &mut node else {
+                unreachable!("not a {}", stringify!($variant));
+            };
+            $(*$field = $value;)*
+            node
+        }};
+    }
+
+    /// A pending test, whose body must not run.
+    fn pending_it(name: &str) -> TestNode {
+        with_fields!(TestNode::it(name, || panic!("pending test ran")), It { pending: true })
+    }
+
+    /// A test skipped with `reason`, as by `skip_if`, whose body must not run.
+    fn skipped_it(name: &str, reason: &str) -> TestNode {
+        let node = TestNode::it(name, || panic!("skipped test ran"));
+        with_fields!(node, It { skip_reason: Some(reason.to_string()) })
+    }
+
+    /// A test with a `Send` body, as registered by `Context::it_send`.
+    fn send_it(name: &str, body: impl Fn() + Send + Sync + 'static) -> TestNode {
+        let body = TestFn::Send(Arc::new(body));
+        with_fields!(TestNode::it(name, || {}), It { test_fn: body })
+    }
+
+    #[test]
+    fn ordered_is_skipped_when_focus_mode_is_active() {
+        static ORDERED_RAN: AtomicBool = AtomicBool::new(false);
+        ORDERED_RAN.store(false, Ordering::SeqCst);
+
+        let nodes = vec![TestNode::describe(
+            "root",
+            vec![
+                TestNode::fit("focused", || {}),
+                TestNode::ordered(
+                    "ordered",
+                    vec![OrderedStep::new("step", || ORDERED_RAN.store(true, Ordering::SeqCst))],
+                ),
+            ],
+        )];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.failed, 0);
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.skipped, 1);
+        assert!(!ORDERED_RAN.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn focused_and_pending_steps_select_what_runs() {
+        use std::sync::Mutex;
+        static RAN: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        fn step(name: &'static str, focused: bool, pending: bool) -> OrderedStep {
+            OrderedStep {
+                focused,
+                pending,
+                ..OrderedStep::new(name, move || RAN.lock().unwrap().push(name))
+            }
+        }
+
+        let nodes = vec![
+            TestNode::ordered("pending", vec![step("a", false, false), step("b", false, true)]),
+            TestNode::ordered("plain", vec![step("c", false, false)]),
+        ];
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!(result.passed, 2);
+        assert_eq!(*RAN.lock().unwrap(), vec!["a", "c"]);
+        RAN.lock().unwrap().clear();
+
+        let nodes = vec![
+            TestNode::ordered(
+                "focused",
+                vec![step("d", false, false), step("e", true, false), step("f", false, false)],
+            ),
+            TestNode::ordered("unfocused", vec![step("g", false, false)]),
+            TestNode::it("unfocused test", || {}),
+        ];
+        assert!(tree_has_focus(&nodes));
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!((result.passed, result.skipped), (1, 2));
+        assert_eq!(*RAN.lock().unwrap(), vec!["e"]);
+    }
+
+    #[test]
+    fn ordered_tolerates_up_to_n_failed_steps() {
+        fn sequence(name: &str, failing: &[&'static str]) -> TestNode {
+            let steps = ["fetch", "parse", "store"]
+                .into_iter()
+                .map(|step| {
+                    let fails = failing.contains(&step);
+                    OrderedStep::new(step, move || assert!(!fails, "{step} broke"))
+                })
+                .collect();
+            let node = TestNode::ordered(name, steps);
+            with_fields!(node, Ordered { continue_on_failure: true, tolerate: 1 })
+        }
+        let nodes = vec![
+            sequence("one flaky step", &["parse"]),
+            sequence("two broken steps", &["fetch", "store"]),
+        ];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(
+            result.failures[0].message,
+            "2 of 3 ordered steps failed (tolerating 1):\n  \
+             fetch: fetch broke\n  store: store broke"
+        );
+        fn outcomes(test: &TestRecord) -> Vec<(&str, TestStatus)> {
+            test.ordered_steps.iter().map(|s| (s.name.as_str(), s.status)).collect()
+        }
+        assert_eq!(
+            outcomes(&result.tests[0]),
+            [
+                ("fetch", TestStatus::Passed),
+                ("parse", TestStatus::Failed),
+                ("store", TestStatus::Passed)
+            ]
+        );
+
+        // Without continue_on_failure, the steps after a failure never run.
+        let fail_fast = with_fields!(sequence("fail fast", &["parse"]), Ordered {
+            continue_on_failure: false,
+            tolerate: 0
+        });
+        let result = run_tree(&[fail_fast], &RunConfig::default());
+        assert_eq!(
+            outcomes(&result.tests[0]),
+            [
+                ("fetch", TestStatus::Passed),
+                ("parse", TestStatus::Failed),
+                ("store", TestStatus::Skipped)
+            ]
+        );
+        let reason = result.tests[0].ordered_steps[2].message.as_deref();
+        assert_eq!(reason, Some("an earlier step failed"));
+
+        // Pending steps are listed too, and don't stop the sequence.
+        let steps = vec![
+            OrderedStep::new("one", || {}),
+            OrderedStep {
+                pending: true,
+                ..OrderedStep::new("two", || panic!("pending step ran"))
+            },
+            OrderedStep::new("three", || {}),
+        ];
+        let result = run_tree(&[TestNode::ordered("with pending", steps)], &RunConfig::default());
+        assert_eq!(
+            outcomes(&result.tests[0]),
+            [
+                ("one", TestStatus::Passed),
+                ("two", TestStatus::Pending),
+                ("three", TestStatus::Passed)
+            ]
+        );
+    }
+
+    #[test]
+    fn ordered_rollbacks_run_in_reverse_only_on_failure() {
+        use std::sync::Mutex;
+        static LOG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        fn log(entry: &'static str) -> Box<dyn Fn()> {
+            Box::new(move || LOG.lock().unwrap().push(entry))
+        }
+        fn provision(name: &str, fail: bool) -> TestNode {
+            let steps = vec![
+                OrderedStep::new("create vm", log("create vm")),
+                OrderedStep::new("attach disk", move || assert!(!fail, "no disks left")),
+            ];
+            let rollbacks = vec![
+                log("delete vm"),
+                Box::new(|| panic!("rollback broke")),
+                log("release ip"),
+            ];
+            with_fields!(TestNode::ordered(name, steps), Ordered { on_failure: rollbacks })
+        }
+        let nodes = vec![TestNode::describe_with_each_hooks(
+            "provisioning",
+            vec![],
+            vec![log("after_each")],
+            vec![provision("succeeds", false), provision("fails", true)],
+        )];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(result.failures[0].message, "no disks left");
+        assert_eq!(
+            *LOG.lock().unwrap(),
+            vec![
+                "create vm",
+                "after_each",
+                "create vm",
+                "release ip",
+                "delete vm",
+                "after_each"
+            ]
+        );
+    }
+
+    #[test]
+    fn ordered_step_cleanups_undo_completed_steps_in_reverse() {
+        use std::sync::Mutex;
+        static LOG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        fn log(entry: &'static str) -> Box<dyn Fn()> {
+            Box::new(move || LOG.lock().unwrap().push(entry))
+        }
+        fn step(name: &str, fail: bool, cleanup: &'static str) -> OrderedStep {
+            OrderedStep {
+                cleanup: Some(log(cleanup)),
+                ..OrderedStep::new(name, move || assert!(!fail, "step failed"))
+            }
+        }
+        fn sequence(name: &str, fail_third: bool) -> TestNode {
+            let steps = vec![
+                step("one", false, "undo one"),
+                step("two", false, "undo two"),
+                step("three", fail_third, "undo three"),
+            ];
+            let rollbacks = vec![log("on_failure")];
+            with_fields!(TestNode::ordered(name, steps), Ordered { on_failure: rollbacks })
+        }
+        let nodes = vec![sequence("passes", false), sequence("fails", true)];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(
+            *LOG.lock().unwrap(),
+            vec!["undo two", "undo one", "on_failure"]
+        );
+    }
+
+    // C3 regression: skip!() should report as skipped, not passed
+    #[test]
+    fn skip_reports_as_skipped_not_passed() {
+        let nodes = vec![TestNode::it("skippable", || {
+            crate::skip("not ready");
+            // skip!() macro does `skip() + return`, but we can't use the macro
+            // in a Fn closure, so just call skip() — the runner checks the flag
+            // regardless of whether the closure returned early.
+        })];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.skipped, 1, "should be reported as skipped");
+        assert_eq!(result.passed, 0, "should not be reported as passed");
+        assert_eq!(result.failed, 0);
+    }
+
+    #[test]
+    fn pending_reports_as_pending_with_its_reason() {
+        let nodes = vec![
+            TestNode::it("unfinished", || crate::pending("export not implemented")),
+            TestNode::it("skipped", || crate::skip("no database")),
+            TestNode::it("failed anyway", || {
+                crate::pending("too late");
+                panic!("boom");
+            }),
+        ];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+
+        assert_eq!((result.pending, result.skipped, result.failed), (1, 1, 1));
+        let statuses: Vec<_> =
+            result.tests.iter().map(|t| (t.status, t.message.as_deref())).collect();
+        assert_eq!(statuses[0], (TestStatus::Pending, Some("export not implemented")));
+        assert_eq!(statuses[1], (TestStatus::Skipped, Some("no database")));
+        assert!(crate::leaked_thread_locals().is_empty());
+    }
+
+    // I1 regression: before_all panic should fail gracefully, not abort
+    #[test]
+    fn before_all_panic_reports_failure_and_runs_after_all() {
+        static AFTER_ALL_RAN: AtomicBool = AtomicBool::new(false);
+        AFTER_ALL_RAN.store(false, Ordering::SeqCst);
+
+        let nodes = vec![TestNode::describe_with_hooks(
+            "broken setup",
+            vec![Box::new(|| panic!("setup exploded"))],
+            vec![Box::new(|| {
+                AFTER_ALL_RAN.store(true, Ordering::SeqCst);
+            })],
+            vec![TestNode::it("should not run", || {
+                panic!("child should be skipped");
+            })],
+        )];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.failed, 1, "before_all failure counted");
+        assert_eq!(result.passed, 0, "child should not have run");
+        assert_eq!(result.skipped, 1, "child reported as skipped");
+        assert_eq!(result.tests[0].message.as_deref(), Some("before_all failed"));
+        assert!(AFTER_ALL_RAN.load(Ordering::SeqCst), "after_all must still run");
+    }
+
+    #[test]
+    fn skip_scope_skips_the_scope_and_still_runs_after_all() {
+        static AFTER_ALL_RAN: AtomicBool = AtomicBool::new(false);
+        AFTER_ALL_RAN.store(false, Ordering::SeqCst);
+
+        let nodes = vec![TestNode::describe_with_hooks(
+            "payments",
+            vec![
+                Box::new(|| crate::skip_scope("sandbox unreachable")),
+                Box::new(|| panic!("later before_all should not run")),
+            ],
+            vec![Box::new(|| AFTER_ALL_RAN.store(true, Ordering::SeqCst))],
+            vec![
+                TestNode::it("charges", || panic!("should not run")),
+                TestNode::describe("refunds", vec![TestNode::it("refunds", || {})]),
+            ],
+        )];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+
+        assert_eq!((result.failed, result.passed, result.skipped), (0, 0, 2));
+        assert!(result
+            .tests
+            .iter()
+            .all(|t| t.message.as_deref() == Some("sandbox unreachable")));
+        assert!(AFTER_ALL_RAN.load(Ordering::SeqCst), "after_all must still run");
+        assert!(crate::leaked_thread_locals().is_empty());
+    }
+
+    #[test]
+    fn scope_hooks_run_once_only_where_tests_execute_under_nested_focus() {
+        use std::sync::Mutex;
+        static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        fn scope(name: &'static str, focus: bool, children: Vec<TestNode>) -> TestNode {
+            let log = move |hook: &'static str| -> Box<dyn Fn()> {
+                Box::new(move || LOG.lock().unwrap().push(format!("{hook} {name}")))
+            };
+            let hooks = (vec![log("before_all")], vec![log("after_all")]);
+            let node = TestNode::describe_with_hooks(name, hooks.0, hooks.1, children);
+            with_fields!(node, Describe { focused: focus })
+        }
+        let idle = || TestNode::it("idle", || panic!("unfocused test ran"));
+
+        let nodes = vec![
+            scope(
+                "outer",
+                false,
+                vec![
+                    scope("focused", true, vec![TestNode::it("runs", || {}), pending_it("later")]),
+                    scope("unfocused", false, vec![idle(), pending_it("later")]),
+                    scope("pending only", true, vec![pending_it("later")]),
+                    scope("skipped only", true, vec![skipped_it("elsewhere", "not here")]),
+                ],
+            ),
+            scope("sibling", false, vec![idle()]),
+        ];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!((result.passed, result.failed), (1, 0));
+        assert_eq!(
+            *LOG.lock().unwrap(),
+            vec![
+                "before_all outer",
+                "before_all focused",
+                "after_all focused",
+                "after_all outer"
+            ]
+        );
+    }
+
+    // I1 regression: after_all panic should report failure
+    #[test]
+    fn after_all_panic_reports_failure() {
+        let nodes = vec![TestNode::describe_with_hooks(
+            "broken teardown",
+            vec![],
+            vec![Box::new(|| panic!("teardown exploded"))],
+            vec![TestNode::it("passes", || {})],
+        )];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.passed, 1, "test itself passed");
+        assert_eq!(result.failed, 1, "after_all failure counted");
+    }
+
+    // I3 regression: one cleanup panic should not prevent other cleanups
+    #[test]
+    fn deferred_cleanup_panic_does_not_skip_remaining() {
+        static SECOND_CLEANUP_RAN: AtomicBool = AtomicBool::new(false);
+        SECOND_CLEANUP_RAN.store(false, Ordering::SeqCst);
+
+        let nodes = vec![TestNode::it("cleanup test", || {
+            // First registered = runs last (LIFO)
+            crate::defer_cleanup(|| {
+                SECOND_CLEANUP_RAN.store(true, Ordering::SeqCst);
+            });
+            // Second registered = runs first, and panics
+            crate::defer_cleanup(|| {
+                panic!("cleanup boom");
+            });
+        })];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        // The test body itself passed, but cleanup panicked → reported as failure
+        assert_eq!(result.failed, 1);
+        assert!(
+            SECOND_CLEANUP_RAN.load(Ordering::SeqCst),
+            "second cleanup must run despite first panicking"
+        );
+    }
+
+    // C1 regression: before_each panic must still run after_each
+    #[test]
+    fn before_each_panic_still_runs_after_each() {
+        static AFTER_EACH_RAN: AtomicBool = AtomicBool::new(false);
+        AFTER_EACH_RAN.store(false, Ordering::SeqCst);
+
+        let nodes = vec![TestNode::describe_with_each_hooks(
+            "broken before_each",
+            vec![Box::new(|| panic!("before_each exploded"))],
+            vec![Box::new(|| {
+                AFTER_EACH_RAN.store(true, Ordering::SeqCst);
+            })],
+            vec![TestNode::it("test", || {})],
+        )];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.failed, 1, "before_each failure reported");
+        assert!(AFTER_EACH_RAN.load(Ordering::SeqCst), "after_each must still run");
+    }
+
+    // C2 regression: after_each panic must not lose the original test failure
+    #[test]
+    fn after_each_panic_preserves_test_failure() {
+        let nodes = vec![TestNode::describe_with_each_hooks(
+            "both fail",
+            vec![],
+            vec![Box::new(|| panic!("after_each exploded"))],
+            vec![TestNode::it("fails", || {
+                panic!("test body failed");
+            })],
+        )];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.failed, 1);
+        // The failure message should contain the body's error, not after_each's
+        assert!(
+            result.failures[0].message.contains("test body failed"),
+            "original test failure must be reported, got: {}",
+            result.failures[0]
+        );
+    }
+
+    // C2 regression: one after_each panic must not skip remaining after_each hooks
+    #[test]
+    fn after_each_panic_runs_remaining_hooks() {
+        static SECOND_AFTER_EACH_RAN: AtomicBool = AtomicBool::new(false);
+        SECOND_AFTER_EACH_RAN.store(false, Ordering::SeqCst);
+
+        // Outer describe has one after_each, inner describe has another that panics.
+        // The outer after_each must still run (after_each runs innermost first).
+        let inner = TestNode::describe_with_each_hooks(
+            "inner",
+            vec![],
+            vec![Box::new(|| panic!("inner after_each panicked"))],
+            vec![TestNode::it("test", || {})],
+        );
+        let outer = TestNode::describe_with_each_hooks(
+            "outer",
+            vec![],
+            vec![Box::new(|| {
+                SECOND_AFTER_EACH_RAN.store(true, Ordering::SeqCst);
+            })],
+            vec![inner],
+        );
+
+        let config = RunConfig::default();
+        let result = run_tree(&[outer], &config);
+
+        assert_eq!(result.failed, 1);
+        assert!(
+            SECOND_AFTER_EACH_RAN.load(Ordering::SeqCst),
+            "outer after_each must still run despite inner after_each panicking"
+        );
+    }
+
+    // I7 regression: mixed +, filter is rejected
+    #[test]
+    fn mixed_and_or_filter_is_rejected() {
+        assert!(!crate::filter::labels_match_filter(&["a", "b"], "a+b,c"));
+    }
+
+    #[test]
+    fn retries_and_timeout_compose() {
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+        ATTEMPTS.store(0, Ordering::SeqCst);
+
+        let node = TestNode::it("combined", || {
+            let n = ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(10));
+            assert!(n >= 2, "attempt {n}");
+        });
+        let nodes = vec![with_fields!(node, It { retries: Some(2), timeout_ms: Some(5) })];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+        assert_eq!(result.failed, 1);
+    }
+
+    #[test]
+    fn max_duration_checks_each_attempt() {
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+        ATTEMPTS.store(0, Ordering::SeqCst);
+
+        let node = TestNode::it("slow first", || {
+            if ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+                std::thread::sleep(Duration::from_millis(30));
+            }
+        });
+        let over = TestNode::it("over budget", || {
+            std::thread::sleep(Duration::from_millis(30));
+        });
+        let nodes = vec![
+            with_fields!(node, It { retries: Some(1), max_duration_ms: Some(10) }),
+            with_fields!(over, It { max_duration_ms: Some(10) }),
+        ];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 2);
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.failed, 1);
+        assert!(result.failures[0].message.contains("budget 10ms"));
+    }
+
+    #[test]
+    fn failures_keep_the_panic_backtrace_when_backtraces_are_enabled() {
+        let nodes = vec![
+            TestNode::it("recovers", || {
+                let _ = std::panic::catch_unwind(|| panic!("handled"));
+            }),
+            TestNode::it("breaks", || panic!("boom")),
+        ];
+        let result = run_tree(&nodes, &RunConfig::default());
+
+        let enabled = std::backtrace::Backtrace::capture().status()
+            == std::backtrace::BacktraceStatus::Captured;
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(result.failures[0].backtrace.is_some(), enabled);
+        assert!(crate::take_backtrace().is_none());
+    }
+
+    #[test]
+    fn annotate_failure_only_touches_a_failed_test() {
+        let mut result = RunResult::default();
+        result.record("fails", TestStatus::Failed);
+        result.failures.push(Failure::new("fails", "boom"));
+        annotate_failure(&mut result, None, Some("at fails".to_string()));
+        assert_eq!(result.failures[0].backtrace.as_deref(), Some("at fails"));
+
+        result.record("passes", TestStatus::Passed);
+        annotate_failure(&mut result, None, Some("at passes".to_string()));
+        assert_eq!(result.failures[0].backtrace.as_deref(), Some("at fails"));
+    }
+
+    #[test]
+    fn failures_keep_a_backtrace_only_from_the_panic_that_failed_them() {
+        // Seeded as if a panic the test caught had left its backtrace.
+        let caught = || crate::seed_backtrace("caught panic");
+        let slow = with_fields!(
+            TestNode::it("times out", move || {
+                caught();
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }),
+            It { timeout_ms: Some(1) }
+        );
+        let nodes = vec![
+            TestNode::it("panics", move || {
+                caught();
+                panic!("boom");
+            }),
+            TestNode::it("makes no assertions", caught),
+            xfail_it("passes unexpectedly", caught),
+            slow,
+        ];
+        let config = RunConfig {
+            strict_assertions: true,
+            ..RunConfig::default()
+        };
+        let result = run_tree(&nodes, &config);
+
+        let backtraces: Vec<(&str, bool)> = result
+            .failures
+            .iter()
+            .map(|f| (f.path.as_str(), f.backtrace.is_some()))
+            .collect();
+        assert_eq!(
+            backtraces,
+            [
+                ("panics", true),
+                ("makes no assertions", false),
+                ("passes unexpectedly", false),
+                ("times out", false),
+            ]
+        );
+        assert!(crate::take_backtrace().is_none());
+    }
+
+    #[test]
+    fn failures_point_at_where_the_test_is_defined() {
+        let node = TestNode::it("breaks", || panic!("boom"));
+        let line = line!() - 1;
+        let result = run_tree(&[node], &RunConfig::default());
+        assert_eq!(result.failures[0].location, Some(format!("{}:{line}", file!())));
+    }
+
+    #[test]
+    fn failure_message_lists_context_notes() {
+        let nodes = vec![
+            TestNode::it("loop", || {
+                crate::context_note("batch 3");
+                for user in 40..45 {
+                    crate::with_context_note(format!("processing user {user}"), || {
+                        assert_ne!(user, 42, "invalid user");
+                    });
+                }
+            }),
+            TestNode::it("next test", || panic!("plain")),
+        ];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        let message = &result.failures[0].message;
+        assert!(message.contains("invalid user"));
+        assert!(message.ends_with("\n  note: processing user 42\n  note: batch 3"));
+        assert!(!message.contains("user 41"));
+        assert_eq!(result.failures[1].message, "plain");
+    }
+
+    #[test]
+    fn soft_assertion_failures_fail_the_test_after_hooks_still_run() {
+        static AFTER_EACH: AtomicU32 = AtomicU32::new(0);
+        AFTER_EACH.store(0, Ordering::SeqCst);
+        let nodes = vec![TestNode::describe_with_each_hooks(
+            "response",
+            vec![],
+            vec![Box::new(|| {
+                AFTER_EACH.fetch_add(1, Ordering::SeqCst);
+            })],
+            vec![
+                TestNode::it("fields", || {
+                    let mut soft = crate::soft();
+                    soft.eq(404, 200, "status").eq("text", "json", "format");
+                }),
+                TestNode::it("hard failure", || {
+                    let mut soft = crate::soft();
+                    soft.check(false, "cached");
+                    panic!("connection reset");
+                }),
+            ],
+        )];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+
+        assert_eq!(AFTER_EACH.load(Ordering::SeqCst), 2);
+        assert_eq!(result.failed, 2);
+        let message = &result.failures[0].message;
+        assert!(message.starts_with("2 soft assertions failed:\n  1. status"), "{message}");
+        let message = &result.failures[1].message;
+        assert!(message.starts_with("connection reset\n  note: soft assertion failed: cached"));
+    }
+
+    #[test]
+    fn attachments_are_kept_only_for_failed_tests() {
+        let nodes = vec![TestNode::describe(
+            "attachment spec",
+            vec![
+                TestNode::it("fails", || {
+                    crate::attach("out.log", "failing output");
+                    panic!("boom");
+                }),
+                TestNode::it("passes", || crate::attach("out.log", "passing output")),
+            ],
+        )];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+        let failed = &result.tests[0].attachments;
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "out.log");
+        assert_eq!(std::fs::read_to_string(&failed[0].path).unwrap(), "failing output");
+        assert!(result.tests[1].attachments.is_empty());
+        let test_dir = failed[0].path.parent().unwrap();
+        assert!(!test_dir.with_file_name("attachment_spec___passes").exists());
+        crate::artifacts::discard(result.tests[0].attachments.clone());
+
+        let config = RunConfig::parse(&args(&["--keep-artifacts"]));
+        let result = run_tree(&nodes, &config);
+        assert_eq!(result.tests[1].attachments.len(), 1);
+        crate::artifacts::discard(result.tests[1].attachments.clone());
+        crate::artifacts::discard(result.tests[0].attachments.clone());
+    }
+
+    #[test]
+    fn retry_on_timeout_gives_each_attempt_a_deadline() {
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+        let slow_start = |retry_on_timeout: bool| {
+            let node = TestNode::it("slow start", || {
+                if ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+                    std::thread::sleep(Duration::from_millis(30));
+                }
+            });
+            let node = with_fields!(node, It { retries: Some(1), timeout_ms: Some(10) });
+            if retry_on_timeout {
+                vec![with_fields!(node, It { timeout_mode: TimeoutMode::PerIteration })]
+            } else {
+                vec![node]
+            }
+        };
+        let config = RunConfig::default();
+
+        // Default: the timeout wraps the retry cycle, so a slow attempt that
+        // didn't panic isn't retried and the run fails.
+        ATTEMPTS.store(0, Ordering::SeqCst);
+        let result = run_tree(&slow_start(false), &config);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 1);
+        assert_eq!(result.failed, 1);
+        assert!(result.failures[0].message.contains("timed out after 10ms"));
+
+        ATTEMPTS.store(0, Ordering::SeqCst);
+        let result = run_tree(&slow_start(true), &config);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 2);
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.tests[0].attempts, 2);
+    }
+
+    #[test]
+    fn timeout_mode_bounds_all_iterations_or_each_one() {
+        static RUNS: AtomicU32 = AtomicU32::new(0);
+
+        // Three iterations under a 200ms timeout, the second one `slow_ms` long.
+        let repeated = |mode: TimeoutMode, ms: u64, slow_ms: u64| {
+            let node = TestNode::it("repeated", move || {
+                let nap = if RUNS.fetch_add(1, Ordering::SeqCst) == 1 { slow_ms } else { ms };
+                std::thread::sleep(Duration::from_millis(nap));
+            });
+            let iterations = Some(3);
+            vec![with_fields!(
+                node,
+                It { timeout_ms: Some(200), timeout_mode: mode, must_pass_repeatedly: iterations }
+            )]
+        };
+        let run = |nodes: Vec<TestNode>| {
+            RUNS.store(0, Ordering::SeqCst);
+            run_tree(&nodes, &RunConfig::default())
+        };
+
+        // Total: 300ms of iterations overrun the shared budget, though each fits.
+        let result = run(repeated(TimeoutMode::Total, 100, 100));
+        assert!(result.failures[0].message.starts_with("test timed out after 200ms"));
+
+        // Per iteration: each 5ms run fits, but a slow one times out alone.
+        assert_eq!(run(repeated(TimeoutMode::PerIteration, 5, 5)).passed, 1);
+        let result = run(repeated(TimeoutMode::PerIteration, 5, 400));
+        assert!(result.failures[0].message.starts_with("test timed out after 200ms"));
+        assert_eq!(RUNS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn send_bodies_are_abandoned_at_the_timeout() {
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+
+        let timed = |name: &str, ms: u64, body: fn()| {
+            with_fields!(send_it(name, body), It { timeout_ms: Some(ms) })
+        };
+        let nodes = vec![
+            timed("hangs", 20, || {
+                while !RELEASE.load(Ordering::SeqCst) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }),
+            timed("skips", 1000, || {
+                crate::context_note("on a worker thread");
+                crate::skip("not today");
+            }),
+            timed("fails", 1000, || {
+                crate::context_note("on a worker thread");
+                assert_eq!(crate::current_test().unwrap().name, "fails");
+                panic!("boom");
+            }),
+        ];
+
+        let start = Instant::now();
+        let result = run_tree(&nodes, &RunConfig::default());
+        RELEASE.store(true, Ordering::SeqCst);
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(result.failures[0].message.starts_with("test timed out after 20ms"));
+        assert_eq!(result.tests[1].status, TestStatus::Skipped);
+        assert_eq!(result.tests[1].message.as_deref(), Some("not today"));
+        assert_eq!(result.failures[1].message, "boom\n  note: on a worker thread");
+    }
+
+    #[test]
+    fn repeat_stops_at_a_send_body_timeout() {
+        static RUNS: AtomicU32 = AtomicU32::new(0);
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+
+        let node = send_it("hangs", || {
+            RUNS.fetch_add(1, Ordering::SeqCst);
+            while !RELEASE.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        });
+        let node = with_fields!(node, It { timeout_ms: Some(20), repeat: Some(3) });
+
+        let result = run_tree(&[node], &RunConfig::default());
+        RELEASE.store(true, Ordering::SeqCst);
+
+        assert!(result.failures[0].message.starts_with("test timed out after 20ms"));
+        assert_eq!(RUNS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn skip_if_reports_skipped_without_running_hooks() {
+        static HOOK_RUNS: AtomicU32 = AtomicU32::new(0);
+
+        let skipped = skipped_it("on ci", "flaky on CI");
+        let nodes = vec![TestNode::describe_with_each_hooks(
+            "net",
+            vec![Box::new(|| {
+                HOOK_RUNS.fetch_add(1, Ordering::SeqCst);
+            })],
+            vec![],
+            vec![skipped, TestNode::it("locally", || {})],
+        )];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!((result.passed, result.skipped, result.pending), (1, 1, 0));
+        assert_eq!(result.tests[0].status, TestStatus::Skipped);
+        assert_eq!(result.tests[0].message.as_deref(), Some("flaky on CI"));
+        assert_eq!(HOOK_RUNS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn retries_and_must_pass_repeatedly_compose() {
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+        ATTEMPTS.store(0, Ordering::SeqCst);
+
+        let node = TestNode::it("combined", || {
+            let n = ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+            assert!(n > 0, "first call should fail and retry");
+        });
+        let node = with_fields!(node, It { retries: Some(1), must_pass_repeatedly: Some(2) });
+        let nodes = vec![node];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+        assert_eq!(result.failed, 0);
+        assert_eq!(result.passed, 1);
+    }
+
+    #[test]
+    fn repeat_runs_every_time_and_reports_how_many_failed() {
+        static RUNS: AtomicU32 = AtomicU32::new(0);
+        RUNS.store(0, Ordering::SeqCst);
+
+        let node = TestNode::it("stress", || {
+            let run = RUNS.fetch_add(1, Ordering::SeqCst) + 1;
+            assert!(!run.is_multiple_of(3), "unlucky run");
+        });
+        let nodes = vec![with_fields!(node, It { repeat: Some(10) })];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+
+        assert_eq!(RUNS.load(Ordering::SeqCst), 10);
+        assert_eq!(result.tests[0].attempts, 10);
+        assert_eq!(
+            result.failures[0].message,
+            "3/10 runs failed; first failure (run 3): unlucky run"
+        );
+        crate::repeat(5, || {});
+
+        let zero = std::panic::catch_unwind(|| {
+            crate::context::run_tree_with(RunConfig::default(), |ctx| {
+                ctx.it("never", || {}).repeat(0);
+            })
+        });
+        let message = panic_message(&*zero.expect_err("repeat(0) is rejected when built"));
+        assert_eq!(message, "rsspec: repeat requires n >= 1");
+    }
+
+    #[test]
+    fn flaky_failure_is_quarantined_after_default_retries() {
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+        ATTEMPTS.store(0, Ordering::SeqCst);
+
+        let node = TestNode::it("flaky", || {
+            ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+            panic!("always fails");
+        });
+        let nodes = vec![with_fields!(node, It { flaky: true })];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), FLAKY_DEFAULT_RETRIES + 1);
+        assert_eq!(result.failed, 0, "flaky failures must not fail the run");
+        assert_eq!(result.flaky, 1);
+        assert!(result.flaky_failures[0].message.contains("always fails"));
+
+        let strict = RunConfig::parse(&args(&["--strict-flaky"]));
+        let result = run_tree(&nodes, &strict);
+        assert_eq!((result.failed, result.flaky), (1, 0));
+        assert!(result.flaky_failures.is_empty());
+    }
+
+    fn xfail_it(name: &str, f: impl Fn() + 'static) -> TestNode {
+        with_fields!(TestNode::it(name, f), It { xfail: Some("bug #42".to_string()) })
+    }
+
+    #[test]
+    fn xfail_failure_is_expected() {
+        let nodes = vec![xfail_it("known bug", || panic!("still broken"))];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.xfailed, 1);
+        assert_eq!(result.failed, 0);
+        assert_eq!(result.passed, 0);
+    }
+
+    #[test]
+    fn xfail_pass_fails_the_run() {
+        let nodes = vec![xfail_it("fixed bug", || {})];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.failed, 1, "XPASS must fail the run");
+        assert_eq!(result.xfailed, 0);
+        assert!(result.failures[0].message.contains("XPASS"));
+        assert!(result.failures[0].message.contains("bug #42"));
+    }
+
+    #[test]
+    fn order_suites_by_name_and_file() {
+        let suites = vec![
+            Suite::new("b", Vec::new()).file("tests/z.rs"),
+            Suite::new("c", Vec::new()),
+            Suite::new("a", Vec::new()).file("tests/y.rs"),
+        ];
+        let names = |order| {
+            order_suites(&suites, order)
+                .iter()
+                .map(|s| s.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(SuiteOrder::Declared), ["b", "c", "a"]);
+        assert_eq!(names(SuiteOrder::Name), ["a", "b", "c"]);
+        assert_eq!(names(SuiteOrder::File), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn suite_skip_reason_skips_everything_without_hooks() {
+        static RAN: AtomicBool = AtomicBool::new(false);
+        RAN.store(false, Ordering::SeqCst);
+
+        let mut suite = Suite::new(
+            "",
+            vec![TestNode::describe_with_hooks(
+                "gpu",
+                vec![Box::new(|| RAN.store(true, Ordering::SeqCst))],
+                vec![],
+                vec![
+                    TestNode::it("renders", || RAN.store(true, Ordering::SeqCst)),
+                    TestNode::it("blits", || RAN.store(true, Ordering::SeqCst)),
+                ],
+            )],
+        );
+        suite.skip_reason = Some("missing capability: gpu".to_string());
+
+        let config = RunConfig::default();
+        let result = run_suites(&[suite], &config, &mut *config.reporter());
+
+        assert!(!RAN.load(Ordering::SeqCst));
+        assert_eq!(result.skipped, 2);
+        assert_eq!(result.failed, 0);
+        assert_eq!(result.tests[0].path, "gpu > renders");
+        assert_eq!(result.tests[0].message.as_deref(), Some("missing capability: gpu"));
+    }
+
+    #[test]
+    fn exit_code_distinguishes_setup_failures() {
+        let config = RunConfig::parse(&args(&["--fail-exit-code", "3", "--setup-exit-code=4"]));
+
+        let nodes = vec![TestNode::it("ok", || {})];
+        assert_eq!(config.exit_code(&run_tree(&nodes, &config)), None);
+
+        let nodes = vec![TestNode::it("broken", || panic!("no"))];
+        assert_eq!(config.exit_code(&run_tree(&nodes, &config)), Some(3));
+
+        let nodes = vec![TestNode::describe_with_hooks(
+            "setup",
+            vec![Box::new(|| panic!("db down"))],
+            vec![],
+            vec![TestNode::it("never runs", || {})],
+        )];
+        assert_eq!(config.exit_code(&run_tree(&nodes, &config)), Some(4));
+        assert_eq!(
+            RunConfig::default().exit_code(&run_tree(&nodes, &config)),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn focus_file_entries_focus_matching_tests() {
+        assert_eq!(
+            parse_focus_file("# flaky ones\nlogin\n\n  Cart > checkout  \n").collect::<Vec<_>>(),
+            vec!["login", "Cart > checkout"]
+        );
+
+        let nodes = vec![
+            TestNode::describe("Login", vec![TestNode::it("works", || {})]),
+            TestNode::describe(
+                "Cart",
+                vec![
+                    TestNode::it("checkout", || {}),
+                    TestNode::it("empty", || {}),
+                ],
+            ),
+        ];
+        let config = RunConfig {
+            focus_paths: vec!["login".to_string(), "Cart > checkout".to_string()],
+            ..RunConfig::default()
+        };
+        let result = run_tree(&nodes, &config);
+
+        assert_eq!(result.passed, 2);
+        assert_eq!(result.skipped, 1);
+    }
+
+    #[test]
+    fn plan_selects_by_label_or_path() {
+        let smoke = TestNode::it("tagged", || {});
+        let smoke = with_fields!(smoke, It { labels: vec!["smoke".to_string()] });
+        let mut suite = Suite::new(
+            "",
+            vec![
+                smoke,
+                TestNode::describe("Login", vec![TestNode::it("works", || {})]),
+                TestNode::it("other", || {}),
+            ],
+        );
+        suite.plans.push(Plan {
+            name: "quick".to_string(),
+            terms: vec!["smoke".to_string(), "login".to_string()],
+        });
+        let suites = [suite];
+
+        let config = RunConfig::parse(&args(&["--plan", "quick"]));
+        let result = run_suites(&suites, &config, &mut *config.reporter());
+        assert_eq!(result.passed, 2);
+        assert_eq!(result.failed, 0);
+
+        let config = RunConfig::parse(&args(&["--plan", "nightly"]));
+        let result = run_suites(&suites, &config, &mut *config.reporter());
+        assert_eq!(result.passed, 0);
+        assert_eq!(result.failures[0].message, "unknown plan 'nightly' (defined: quick)");
+    }
+
+    fn owned(owner: &str, mut node: TestNode) -> TestNode {
+        match &mut node {
+            TestNode::Describe { owner: o, .. } | TestNode::It { owner: o, .. } => {
+                *o = Some(owner.to_string());
+            }
+            TestNode::Ordered { .. } => {}
+        }
+        node
+    }
+
+    #[test]
+    fn owner_is_inherited_filtered_and_grouped() {
+        let nodes = vec![
+            owned(
+                "payments",
+                TestNode::describe(
+                    "billing",
+                    vec![
+                        TestNode::it("charges", || panic!("declined")),
+                        owned("search", TestNode::it("indexes", || panic!("stale"))),
+                    ],
+                ),
+            ),
+            TestNode::it("unowned", || panic!("oops")),
+        ];
+
+        let config = RunConfig::default();
+        let result = run_tree(&nodes, &config);
+        assert_eq!(
+            failures_by_owner(&RunSummary::new(&result, Duration::ZERO).tests),
+            vec![
+                (Some("payments"), vec!["billing > charges"]),
+                (Some("search"), vec!["billing > indexes"]),
+                (None, vec!["unowned"]),
+            ]
+        );
+
+        let config = RunConfig::parse(&args(&["--owner", "payments"]));
+        let result = run_tree(&nodes, &config);
+        assert_eq!(result.failed, 1);
+        assert_eq!(result.failures[0].path, "billing > charges");
+    }
+
+    #[test]
+    fn inventory_counts_effective_labels_and_owners() {
+        let mut labeled = TestNode::describe(
+            "db",
+            vec![TestNode::it("reads", || {}), TestNode::fit("writes", || {})],
+        );
+        if let TestNode::Describe { labels, .. } = &mut labeled {
+            labels.push("integration".to_string());
+        }
+        let nodes = vec![owned("storage", labeled), pending_it("later")];
+
+        let config = RunConfig::parse(&args(&["--list-tags"]));
+        assert_eq!(config.inventory, Some(InventoryView::Tags));
+        let mut inventory = Inventory::default();
+        inventory.collect(&nodes, &[], &HookChain::default(), false, false, &config);
+        assert_eq!(inventory.total, 3);
+        assert_eq!(inventory.pending, 1);
+        assert_eq!(inventory.focused, 1);
+        assert_eq!(inventory.unlabeled, 1);
+        assert_eq!(inventory.unowned, 1);
+        assert_eq!(inventory.by_label.get("integration"), Some(&2));
+        assert_eq!(inventory.by_owner.get("storage"), Some(&2));
+
+        let config = RunConfig::parse(&args(&["--list-owners", "writes"]));
+        let mut inventory = Inventory::default();
+        inventory.collect(&nodes, &[], &HookChain::default(), false, false, &config);
+        assert_eq!(inventory.total, 1);
+    }
+
+    #[test]
+    fn nextest_summary_line_matches_nextest_shape() {
+        let result = RunSummary {
+            passed: 40,
+            failed: 2,
+            flaky: 1,
+            pending: 1,
+            skipped: 2,
+            duration: Duration::from_millis(1234),
+            ..RunSummary::default()
+        };
+        assert_eq!(
+            nextest_summary_line(&result),
+            "     Summary [   1.234s] 43 tests run: 41 passed (1 flaky), 2 failed, 3 skipped"
+        );
+
+        let result = RunSummary {
+            passed: 1,
+            duration: Duration::from_millis(5),
+            ..RunSummary::default()
+        };
+        assert_eq!(
+            nextest_summary_line(&result),
+            "     Summary [   0.005s] 1 test run: 1 passed, 0 skipped"
+        );
+        assert_eq!(
+            RunConfig::parse(&args(&["--summary-format=nextest"])).summary_format,
+            SummaryFormat::Nextest
+        );
+    }
+
+    #[test]
+    fn parse_order_flag() {
+        assert_eq!(RunConfig::parse(&args(&["--order", "name"])).order, SuiteOrder::Name);
+        assert_eq!(RunConfig::parse(&args(&["--order=file"])).order, SuiteOrder::File);
+        assert_eq!(RunConfig::parse(&args(&[])).order, SuiteOrder::Declared);
+        // The order value must not be mistaken for a filter.
+        assert_eq!(RunConfig::parse(&args(&["--order", "name"])).filter, None);
+        assert_eq!(
+            RunConfig::parse(&args(&["--order", "bogus"])).order,
+            SuiteOrder::Declared
+        );
+    }
+
+    #[test]
+    fn output_format_json_reports_every_test() {
+        let config = RunConfig::parse(&args(&["--output-format", "json"]));
+        assert_eq!(config.output_format, OutputFormat::Json);
+        assert_eq!(config.filter, None);
+        assert_eq!(
+            RunConfig::parse(&args(&["--output-format=tree"])).output_format,
+            OutputFormat::Pretty
+        );
+
+        let mut later = TestNode::describe("later", vec![TestNode::it("waits", || {})]);
+        if let TestNode::Describe { pending, .. } = &mut later {
+            *pending = true;
+        }
+        let nodes = vec![
+            TestNode::describe("math", vec![TestNode::it("adds", || {}), later]),
+            TestNode::it("fails", || panic!("boom")),
+        ];
+        let result = run_tree(&nodes, &config);
+        assert_eq!(result.tests.len(), 3);
+        assert_eq!(result.reported, 3);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn capture_keeps_the_output_of_failed_tests_only() {
+        use std::io::Write;
+        // Written to the streams directly: `println!` goes to libtest's own
+        // capture in unit tests.
+        fn print(text: &str) {
+            std::io::stdout().write_all(text.as_bytes()).unwrap();
+        }
+        let nodes = vec![
+            TestNode::it("quiet pass", || print("noise\n")),
+            TestNode::it("loud failure", || {
+                print("connecting\n");
+                std::io::stderr().write_all(b"refused\n").unwrap();
+                panic!("no connection");
+            }),
+        ];
+
+        let config = RunConfig::parse(&args(&["--capture"]));
+        assert!(config.capture);
+        let result = run_tree(&nodes, &config);
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(result.tests[0].output, None);
+        let output = result.tests[1].output.as_deref().unwrap_or_default();
+        assert!(output.starts_with("connecting\nrefused\n"), "{output:?}");
+    }
+
+    #[test]
+    fn dry_run_counts_selected_tests_without_running_anything() {
+        let boom = || -> Box<dyn Fn()> { Box::new(|| panic!("must not run")) };
+        let nodes = vec![
+            TestNode::describe_with_hooks(
+                "db",
+                vec![boom()],
+                vec![boom()],
+                vec![
+                    TestNode::describe_with_each_hooks(
+                        "writes",
+                        vec![boom()],
+                        vec![boom()],
+                        vec![TestNode::it("inserts", || panic!("must not run"))],
+                    ),
+                    TestNode::it("reads", || panic!("must not run")),
+                ],
+            ),
+            TestNode::describe("cache", vec![TestNode::it("evicts", || panic!("must not run"))]),
+        ];
+
+        let config = RunConfig::parse(&args(&["--dry-run", "db"]));
+        assert!(config.dry_run);
+        let result = run_tree(&nodes, &config);
+        assert_eq!((result.would_run, result.failed, result.passed), (2, 0, 0));
+        let selected: Vec<_> = result
+            .tests
+            .iter()
+            .map(|t| (t.path.as_str(), t.status, t.message.as_deref()))
+            .collect();
+        assert_eq!(
+            selected,
+            vec![
+                ("db > writes > inserts", TestStatus::Skipped, Some("dry run")),
+                ("db > reads", TestStatus::Skipped, Some("dry run")),
+            ]
+        );
+    }
 
-            // Filter check
-            if let Some(ref f) = config.filter {
-                if !full_path.to_lowercase().contains(&f.to_lowercase()) {
-                    return;
-                }
-            }
+    #[test]
+    fn run_config_builder_matches_the_flags() {
+        let built = RunConfig::default()
+            .filter("cart")
+            .reverse()
+            .shuffle()
+            .seed(7)
+            .owner("payments")
+            .bail(2)
+            .dry_run();
+        let parsed = RunConfig::parse_args([
+            "cart", "--shuffle", "--seed", "7", "--owner", "payments", "--bail", "2", "--dry-run",
+        ]);
+        for config in [&built, &parsed] {
+            assert_eq!(config.filter.as_deref(), Some("cart"));
+            assert!(config.shuffle && !config.reverse && config.dry_run);
+            assert_eq!((config.seed, config.bail), (Some(7), Some(2)));
+            assert_eq!(config.owner.as_deref(), Some("payments"));
+        }
+    }
 
-            // Focus mode: skip non-focused ordered tests unless include_ignored is set.
-            if focus_mode && !force_focused && !config.include_ignored {
-                result.skipped += 1;
-                return;
-            }
+    #[test]
+    fn hand_assembled_suites_run_with_public_constructors() {
+        let suites = [
+            Suite::new("math", vec![TestNode::describe("add", vec![TestNode::it("one", || {})])]),
+            Suite::new(
+                "flow",
+                vec![TestNode::ordered(
+                    "checkout",
+                    vec![
+                        OrderedStep::new("pay", || {}),
+                        OrderedStep::new("ship", || panic!("no stock")),
+                    ],
+                )],
+            ),
+        ];
+        let config = RunConfig::default();
+        let result = run_suites(&suites, &config, &mut *config.reporter());
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(result.failures[0].path, "checkout");
+    }
 
-            // Fail-on-focus CI check for ordered tests inside focused containers.
-            if force_focused && focus_mode {
-                crate::check_fail_on_focus();
-            }
+    #[test]
+    fn a_panicking_suite_body_leaves_no_builder_behind() {
+        let built = std::panic::catch_unwind(|| {
+            crate::run_inline(|ctx| {
+                ctx.it("defined", || {});
+                panic!("broken suite");
+            })
+        });
+        assert!(built.is_err());
+        let outside = std::panic::catch_unwind(|| {
+            crate::Context.it("stray", || {});
+        });
+        assert!(outside.is_err(), "Context must not reach the abandoned builder");
+
+        let result = crate::run_inline(|ctx| {
+            ctx.it("fresh", || {});
+        });
+        assert_eq!((result.passed, result.tests().len()), (1, 1));
+    }
 
-            // Label check
-            let all_labels: Vec<&str> = hooks
-                .labels
-                .iter()
-                .copied()
-                .chain(labels.iter().map(|s| s.as_str()))
-                .collect();
-            if !crate::check_labels(&all_labels) {
-                return;
+    #[test]
+    fn run_result_lists_failures_and_every_test() {
+        let (result, _) = crate::context::run_tree_with(RunConfig::default(), |ctx| {
+            ctx.describe("math", |ctx| {
+                ctx.it("adds", || assert_eq!(1 + 1, 2));
+                ctx.it("subtracts", || assert_eq!(2 - 1, 0));
+                ctx.xit("divides", || {});
+            });
+        });
+        assert_eq!((result.passed, result.failed, result.pending), (1, 1, 1));
+        assert_eq!(result.failures[0].path, "math > subtracts");
+        let statuses: Vec<_> = result.tests().into_iter().map(|t| (t.name, t.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("adds".to_string(), TestStatus::Passed),
+                ("subtracts".to_string(), TestStatus::Failed),
+                ("divides".to_string(), TestStatus::Pending),
+            ]
+        );
+    }
+
+    #[test]
+    fn workers_hold_every_resource_in_their_subtree() {
+        fn with_resources(mut node: TestNode, names: &[&str]) -> TestNode {
+            match &mut node {
+                TestNode::Describe { resources, .. } | TestNode::It { resources, .. } => {
+                    resources.extend(names.iter().map(|s| s.to_string()));
+                }
+                TestNode::Ordered { .. } => {}
             }
+            node
+        }
+        let store = with_resources(
+            TestNode::describe(
+                "store",
+                vec![
+                    with_resources(TestNode::it("writes", || {}), &["db", "cache"]),
+                    TestNode::describe(
+                        "nested",
+                        vec![with_resources(TestNode::it("reads", || {}), &["db"])],
+                    ),
+                ],
+            ),
+            &["port"],
+        );
+        let parser = TestNode::describe("parser", vec![TestNode::it("parses", || {})]);
+
+        let store = subtree_resources(&store);
+        assert_eq!(store, vec!["cache", "db", "port"]);
+        assert!(subtree_resources(&parser).is_empty());
+        assert!(shares_resource(&["db"], &store));
+        assert!(!shares_resource(&["files"], &store));
+        assert!(!shares_resource(&store, &[]));
+    }
 
-            let start = Instant::now();
+    #[test]
+    fn parse_jobs_flag() {
+        assert_eq!(RunConfig::parse(&args(&["--jobs", "4"])).jobs, Some(4));
+        assert_eq!(RunConfig::parse(&args(&["--jobs=2"])).jobs, Some(2));
+        assert_eq!(RunConfig::parse(&args(&[])).jobs, None);
+        assert_eq!(RunConfig::parse(&args(&["--jobs", "0"])).jobs, None);
+
+        // A bare `--jobs` uses all cores and leaves a following filter alone.
+        let config = RunConfig::parse(&args(&["--jobs", "Calculator"]));
+        assert!(config.jobs.is_some_and(|n| n >= 1));
+        assert_eq!(config.filter.as_deref(), Some("Calculator"));
+
+        let config = RunConfig::parse(&args(&["--rsspec-worker=1:2:/tmp/r"]));
+        assert_eq!(config.worker.map(|w| (w.suite, w.node)), Some((1, 2)));
+        assert_eq!(config.args, args(&["--rsspec-worker=1:2:/tmp/r"]));
+    }
 
-            let outcome = catch_unwind(AssertUnwindSafe(|| {
-                // Run before_each + just_before_each + steps, catching any panic
-                // so that after_each and cleanups are guaranteed to run.
-                let body_result = catch_unwind(AssertUnwindSafe(|| {
-                    for hook in &hooks.before_each {
-                        hook();
-                    }
-                    for hook in &hooks.just_before_each {
-                        hook();
-                    }
+    #[test]
+    fn effective_config_shows_resolved_settings() {
+        let config = RunConfig::parse(&args(&["--seed", "7", "--bail", "--show-config", "cart"]));
+        assert!(config.show_config);
+        let shown = config.effective_config();
+        assert!(shown.starts_with("rsspec: effective configuration\n"));
+        for line in [
+            "  filter:          cart\n",
+            "  order:           shuffled (seed 7)\n",
+            "  bail:            1\n",
+            "  jobs:            1\n",
+            "  output format:   pretty\n",
+            "  setup exit code: 1\n",
+        ] {
+            assert!(shown.contains(line), "missing {line:?} in:\n{shown}");
+        }
+    }
 
-                    let mut failures: Vec<Box<dyn std::any::Any + Send>> = Vec::new();
-                    let total = steps.len();
+    #[test]
+    fn focus_and_skip_patterns_compose() {
+        let nodes = || {
+            vec![
+                TestNode::describe(
+                    "cart",
+                    vec![
+                        TestNode::it("adds item", || {}),
+                        TestNode::it("adds coupon", || {}),
+                        TestNode::it("removes item", || {}),
+                    ],
+                ),
+                TestNode::describe("wishlist", vec![TestNode::it("adds item", || {})]),
+            ]
+        };
+        let ran = |flags: &[&str]| -> Vec<String> {
+            let result = run_tree(&nodes(), &RunConfig::parse(&args(flags)));
+            let passed = result.tests.iter().filter(|t| t.status == TestStatus::Passed);
+            passed.map(|t| t.path.clone()).collect()
+        };
 
-                    for (i, step) in steps.iter().enumerate() {
-                        eprintln!("  [{}/{}] {}", i + 1, total, step.name);
-                        if *continue_on_failure {
-                            if let Err(e) = catch_unwind(AssertUnwindSafe(|| (step.body)())) {
-                                failures.push(e);
-                            }
-                        } else {
-                            (step.body)();
-                        }
-                    }
+        assert_eq!(
+            ran(&["--focus", "adds", "--skip", "coupon"]),
+            ["cart > adds item", "wishlist > adds item"]
+        );
+        assert_eq!(
+            ran(&["--focus", "cart > adds", "--focus", "removes", "--skip=wishlist"]),
+            ["cart > adds item", "cart > adds coupon", "cart > removes item"]
+        );
+        #[cfg(feature = "regex")]
+        assert_eq!(
+            ran(&["--focus", r"^\w+ > adds (item|coupon)$", "--skip", "^wish"]),
+            ["cart > adds item", "cart > adds coupon"]
+        );
 
-                    if !failures.is_empty() {
-                        panic!(
-                            "{} of {} ordered steps failed",
-                            failures.len(),
-                            steps.len()
-                        );
-                    }
-                }));
+        let config = RunConfig::parse(&args(&["--skip", ""]));
+        assert!(config.skip_patterns.is_empty());
+    }
 
-                // after_each (innermost first) — each individually protected
-                let mut after_each_panic = None;
-                for hook in hooks.after_each.iter().rev() {
-                    if let Err(e) = catch_unwind(AssertUnwindSafe(hook)) {
-                        eprintln!("  warning: after_each hook panicked");
-                        if after_each_panic.is_none() {
-                            after_each_panic = Some(e);
-                        }
-                    }
-                }
+    #[test]
+    fn libtest_nocapture_exact_and_quiet_flags() {
+        assert!(RunConfig::parse(&args(&["--capture"])).capture);
+        assert!(!RunConfig::parse(&args(&["--capture", "--nocapture"])).capture);
+        assert!(!RunConfig::parse(&args(&["--nocapture", "--capture"])).capture);
+        assert!(RunConfig::parse(&args(&["-q"])).quiet);
+        assert!(RunConfig::parse(&args(&["--quiet"])).quiet);
 
-                crate::run_deferred_cleanups();
+        let config = RunConfig::parse(&args(&["--exact", "Calculator > adds"]));
+        assert!(config.exact);
+        assert_eq!(config.filter.as_deref(), Some("Calculator > adds"));
 
-                // Propagate the first failure: body takes priority over after_each
-                if let Err(e) = body_result {
-                    std::panic::resume_unwind(e);
-                }
-                if let Some(e) = after_each_panic {
-                    std::panic::resume_unwind(e);
-                }
-            }));
+        let nodes = vec![TestNode::describe(
+            "Calculator",
+            vec![
+                TestNode::it("adds", || {}),
+                TestNode::it("adds negatives", || {}),
+            ],
+        )];
+        let result = run_tree(&nodes, &config);
+        let ran: Vec<_> = result.tests.iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(ran, ["Calculator > adds"]);
+        // Exact matching is case-sensitive, like libtest's.
+        let config = RunConfig::parse(&args(&["calculator > adds", "--exact"]));
+        assert_eq!(run_tree(&nodes, &config).passed, 0);
+        let config = RunConfig::parse(&args(&["calculator > adds"]));
+        assert_eq!(run_tree(&nodes, &config).passed, 2);
+    }
 
-            report_outcome(&indent, name, &full_path, outcome, start, result);
-        }
+    #[test]
+    fn scope_timings_cover_describes_with_scope_hooks() {
+        let nodes = vec![
+            TestNode::describe_with_hooks(
+                "db",
+                vec![Box::new(|| std::thread::sleep(std::time::Duration::from_millis(20)))],
+                vec![],
+                vec![
+                    TestNode::it("reads", || {}),
+                    TestNode::it("writes", || panic!("read-only")),
+                    TestNode::describe("nested", vec![TestNode::it("deletes", || {})]),
+                ],
+            ),
+            TestNode::describe("plain", vec![TestNode::it("adds", || {})]),
+        ];
+
+        let result = run_tree(&nodes, &RunConfig::default());
+        assert_eq!(result.scope_timings.len(), 1);
+        let timing = &result.scope_timings[0];
+        assert_eq!((timing.path.as_str(), timing.tests), ("db", 3));
+        assert!(timing.before_all >= std::time::Duration::from_millis(20));
+        assert!(RunConfig::parse(&args(&["--timing"])).timing);
     }
-}
 
-/// Mark all descendant It nodes as pending (for xdescribe).
-fn run_nodes_pending(nodes: &[TestNode], depth: usize, result: &mut RunResult) {
-    let indent = "  ".repeat(depth);
-    for node in nodes {
-        match node {
-            TestNode::Describe { name, children, .. } => {
-                println!("{indent}{}", bold(&dim(name)));
-                run_nodes_pending(children, depth + 1, result);
-            }
-            TestNode::It { name, .. } => {
-                println!("{indent}{} {}", yellow("-"), dim(name));
-                result.pending += 1;
-            }
-            TestNode::Ordered { name, .. } => {
-                println!("{indent}{} {}", yellow("-"), dim(name));
-                result.pending += 1;
-            }
-        }
+    #[test]
+    fn steps_and_assertions_are_counted_per_test() {
+        let nodes = vec![
+            TestNode::it("checkout", || {
+                crate::by("add to cart");
+                crate::compare!(1, 1);
+                crate::by("pay");
+                crate::expect(true).to_be_true().to_be_true();
+            }),
+            TestNode::it("untested", || {}),
+        ];
+        let result = run_tree(&nodes, &RunConfig::default());
+        let tests = result.tests();
+        assert_eq!((tests[0].steps, tests[0].assertions), (2, 3));
+        assert_eq!((tests[1].steps, tests[1].assertions), (0, 0));
+        assert_eq!(step_counts(&tests[0]), "(2 steps, 3 assertions)");
+        let mut single = tests[1].clone();
+        (single.steps, single.assertions) = (1, 1);
+        assert_eq!(step_counts(&single), "(1 step, 1 assertion)");
+        assert!(RunConfig::parse(&args(&["--show-steps"])).show_steps);
     }
-}
 
-fn report_outcome(
-    indent: &str,
-    name: &str,
-    full_path: &str,
-    outcome: Result<(), Box<dyn std::any::Any + Send>>,
-    start: Instant,
-    result: &mut RunResult,
-) {
-    let elapsed = start.elapsed();
-    let ms = elapsed.as_millis();
-    let time_str = if ms > 100 {
-        format!(" {}", dim(&format!("({ms}ms)")))
-    } else {
-        String::new()
-    };
+    #[test]
+    fn strict_assertions_fail_tests_that_check_nothing() {
+        let nodes = vec![
+            TestNode::it("stub", || {}),
+            TestNode::it("checks", || crate::compare!(2, 2)),
+            TestNode::it("smoke", crate::noted),
+            TestNode::it("not yet", || crate::skip("later")),
+            TestNode::ordered("flow", vec![OrderedStep::new("open", || {})]),
+        ];
+        let config = RunConfig::parse(&args(&["--strict-assertions"]));
+        let result = run_tree(&nodes, &config);
 
-    match outcome {
-        Ok(()) => {
-            println!("{indent}{} {}{}", green("✓"), name, time_str);
-            result.passed += 1;
-        }
-        Err(e) => {
-            let msg = panic_message(&*e);
-            println!("{indent}{} {}{}", red("✗"), red(name), time_str);
-            println!("{indent}  {}", red(&format!("Error: {msg}")));
-            result.failed += 1;
-            result.failures.push(format!("{full_path}: {msg}"));
-        }
+        let failed: Vec<_> = result.failures.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(failed, ["stub", "flow"]);
+        assert_eq!(result.failures[0].message, "test passed but made no assertions");
+        assert_eq!((result.passed, result.skipped), (2, 1));
+        assert_eq!(run_tree(&nodes, &RunConfig::default()).failed, 0);
     }
-}
 
-/// Run a closure with a timeout.
-///
-/// The closure runs on the current thread. A separate timer thread signals
-/// if the deadline is exceeded. Since we can't abort the current thread,
-/// the closure must finish before we can check the result — but if it takes
-/// too long, we report a timeout failure.
-fn run_with_timeout(
-    ms: u64,
-    f: &dyn Fn(),
-) -> Result<(), Box<dyn std::any::Any + Send>> {
-    use std::time::Duration;
+    #[test]
+    fn scope_timing_lines_put_the_costliest_scopes_first() {
+        let timing = |path: &str, before_ms, after_ms, tests| ScopeTiming {
+            path: path.to_string(),
+            before_all: std::time::Duration::from_millis(before_ms),
+            after_all: std::time::Duration::from_millis(after_ms),
+            tests,
+        };
+        let lines = scope_timing_lines(&[timing("cache", 5, 0, 1), timing("db", 2100, 300, 12)]);
+        assert_eq!(
+            lines,
+            vec![
+                "db: before_all 2.100s, after_all 0.300s, 12 tests",
+                "cache: before_all 0.005s, after_all 0.000s, 1 test",
+            ]
+        );
+    }
 
-    let start = Instant::now();
-    let deadline = Duration::from_millis(ms);
+    #[test]
+    fn bail_stops_scheduling_but_finishes_entered_scopes() {
+        static AFTER_EACH: AtomicU32 = AtomicU32::new(0);
+        static AFTER_ALL_RAN: AtomicBool = AtomicBool::new(false);
+        static LATER_SETUP_RAN: AtomicBool = AtomicBool::new(false);
 
-    // Run the closure on the current thread
-    // (Cleanups are already handled inside test_body before any panic re-raises.)
-    let result = catch_unwind(AssertUnwindSafe(|| {
-        f();
-    }));
+        assert_eq!(RunConfig::parse(&args(&["--bail"])).bail, Some(1));
+        assert_eq!(RunConfig::parse(&args(&["--bail=3"])).bail, Some(3));
+        let config = RunConfig::parse(&args(&["--bail", "2", "db"]));
+        assert_eq!(config.bail, Some(2));
+        assert_eq!(config.filter.as_deref(), Some("db"));
 
-    // Check if the closure exceeded the deadline
-    if start.elapsed() > deadline {
-        // If the test also panicked, include the original error
-        if let Err(e) = result {
-            let msg = panic_message(&*e);
-            Err(Box::new(format!("test timed out after {ms}ms (original error: {msg})")))
-        } else {
-            Err(Box::new(format!("test timed out after {ms}ms")))
+        let mut db = TestNode::describe_with_each_hooks(
+            "db",
+            vec![],
+            vec![Box::new(|| {
+                AFTER_EACH.fetch_add(1, Ordering::SeqCst);
+            })],
+            vec![
+                TestNode::it("first failure", || panic!("one")),
+                TestNode::it("passes", || {}),
+                TestNode::it("second failure", || panic!("two")),
+                TestNode::it("never runs", || {}),
+            ],
+        );
+        if let TestNode::Describe { after_all, .. } = &mut db {
+            after_all.push(Box::new(|| AFTER_ALL_RAN.store(true, Ordering::SeqCst)));
         }
-    } else {
-        result
+        let nodes = vec![
+            db,
+            TestNode::describe_with_hooks(
+                "later",
+                vec![Box::new(|| LATER_SETUP_RAN.store(true, Ordering::SeqCst))],
+                vec![],
+                vec![TestNode::it("never runs", || {})],
+            ),
+        ];
+
+        let config = RunConfig::parse(&args(&["--bail=2"]));
+        let result = run_tree(&nodes, &config);
+        assert!(result.bailed);
+        assert_eq!((result.failed, result.passed), (2, 1));
+        assert_eq!(result.tests.len(), 3);
+        assert_eq!(AFTER_EACH.load(Ordering::SeqCst), 3, "in-flight test's after_each ran");
+        assert!(AFTER_ALL_RAN.load(Ordering::SeqCst), "entered scope's after_all ran");
+        assert!(!LATER_SETUP_RAN.load(Ordering::SeqCst), "no new scope entered");
     }
-}
 
-fn print_summary(result: &RunResult, elapsed: std::time::Duration) {
-    let elapsed_str = format!("{:.3}s", elapsed.as_secs_f64());
+    #[test]
+    fn custom_reporter_receives_events_in_run_order() {
+        #[derive(Default)]
+        struct Recorder(Vec<String>);
+        impl Reporter for Recorder {
+            fn run_started(&mut self, seed: Option<u64>) {
+                self.0.push(format!("run {seed:?}"));
+            }
+            fn suite_started(&mut self, name: &str) {
+                self.0.push(format!("suite {name}"));
+            }
+            fn describe_entered(&mut self, path: &str, _name: &str) {
+                self.0.push(format!("enter {path}"));
+            }
+            fn describe_finished(&mut self, path: &str) {
+                self.0.push(format!("exit {path}"));
+            }
+            fn it_finished(&mut self, test: &TestResult) {
+                self.0.push(format!("{:?} {} ({})", test.status, test.path, test.name));
+            }
+            fn hook_failed(&mut self, path: &str, hook: &str, message: &str) {
+                self.0.push(format!("{hook} {path}: {message}"));
+            }
+            fn run_finished(&mut self, summary: &RunSummary) {
+                self.0.push(format!("done {}/{}", summary.passed, summary.failed));
+            }
+        }
 
-    let mut parts: Vec<String> = [
-        (result.passed > 0).then(|| green(&format!("{} passed", result.passed))),
-        (result.failed > 0).then(|| red(&format!("{} failed", result.failed))),
-        (result.pending > 0).then(|| yellow(&format!("{} pending", result.pending))),
-        (result.skipped > 0).then(|| dim(&format!("{} skipped", result.skipped))),
-    ]
-    .into_iter()
-    .flatten()
-    .collect();
+        let suites = [Suite::new(
+            "math",
+            vec![
+                TestNode::describe("adds", vec![TestNode::it("small", || {})]),
+                TestNode::describe_with_hooks(
+                    "db",
+                    vec![],
+                    vec![Box::new(|| panic!("teardown"))],
+                    vec![TestNode::it("reads", || panic!("boom"))],
+                ),
+            ],
+        )];
+        let mut recorder = Recorder::default();
+        let result = run_suites(&suites, &RunConfig::default(), &mut recorder);
 
-    // Avoid an empty summary line when all tests are filtered out
-    if parts.is_empty() {
-        parts.push(dim("0 matched"));
+        assert_eq!(result.failed, 2);
+        assert_eq!(
+            recorder.0,
+            vec![
+                "run None",
+                "suite math",
+                "enter adds",
+                "Passed adds > small (small)",
+                "exit adds",
+                "enter db",
+                "Failed db > reads (reads)",
+                "after_all db: teardown",
+                "exit db",
+                "done 1/2",
+            ]
+        );
     }
 
-    let summary = format!("{} ({})", parts.join(", "), dim(&elapsed_str));
-
-    println!();
-    if result.failed > 0 {
-        println!("{}", red("FAIL"));
-        println!("{summary}");
-        println!();
-        println!("Failures:");
-        for (i, failure) in result.failures.iter().enumerate() {
-            println!("  {}. {}", i + 1, failure);
+    #[test]
+    fn replaced_labels_drop_the_inherited_ones() {
+        let mut outer = TestNode::describe("db", vec![]);
+        if let TestNode::Describe { labels, .. } = &mut outer {
+            labels.extend(["db".to_string(), "slow".to_string()]);
         }
-        println!();
-    } else {
-        println!("{}", green("PASS"));
-        println!("{summary}");
+        let mut fast = TestNode::describe("parsing", vec![]);
+        if let TestNode::Describe {
+            labels,
+            replace_labels,
+            ..
+        } = &mut fast
+        {
+            labels.push("db".to_string());
+            *replace_labels = true;
+        }
+        let mut nested = TestNode::describe("nested", vec![]);
+        if let TestNode::Describe { labels, .. } = &mut nested {
+            labels.push("unit".to_string());
+        }
+
+        let chain = HookChain::default().with_describe(&outer);
+        assert_eq!(chain.with_describe(&nested).labels, ["db", "slow", "unit"]);
+        let chain = chain.with_describe(&fast);
+        assert_eq!(chain.labels, ["db"]);
+        assert_eq!(chain.with_describe(&nested).labels, ["db", "unit"]);
     }
-}
 
-fn list_tree(nodes: &[TestNode], path: &[String], config: &RunConfig) {
-    for node in nodes {
-        match node {
-            TestNode::Describe { name, children, .. } => {
-                let mut child_path = path.to_vec();
-                child_path.push(name.clone());
-                list_tree(children, &child_path, config);
-            }
-            TestNode::It { name, pending, .. } => {
-                let full_path = {
-                    let mut p = path.to_vec();
-                    p.push(name.clone());
-                    p.join(" > ")
-                };
+    #[test]
+    fn allow_focus_is_inherited_until_a_nested_describe_focuses_itself() {
+        let outer = with_fields!(
+            TestNode::describe("canary", vec![]),
+            Describe { focused: true, allow_focus: true }
+        );
+        let inner = TestNode::describe("inner", vec![]);
+        let stray = with_fields!(TestNode::describe("stray", vec![]), Describe { focused: true });
 
-                if let Some(ref f) = config.filter {
-                    if !full_path.to_lowercase().contains(&f.to_lowercase()) {
-                        continue;
-                    }
-                }
+        let chain = HookChain::default().with_describe(&outer).with_describe(&inner);
+        assert!(chain.allow_focus);
+        assert!(!chain.with_describe(&stray).allow_focus);
+        assert!(!HookChain::default().with_describe(&inner).allow_focus);
+    }
 
-                if *pending {
-                    println!("{full_path} (pending)");
-                } else {
-                    println!("{full_path}");
-                }
-            }
-            TestNode::Ordered { name, .. } => {
-                let full_path = {
-                    let mut p = path.to_vec();
-                    p.push(name.clone());
-                    p.join(" > ")
-                };
+    /// Serializes the `--watch` tests, which share the SIGUSR1 flag.
+    static WATCH_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
-                if let Some(ref f) = config.filter {
-                    if !full_path.to_lowercase().contains(&f.to_lowercase()) {
-                        continue;
-                    }
-                }
+    #[test]
+    fn watch_reruns_once_per_input_line() {
+        let _lock = WATCH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        static RUNS: AtomicU32 = AtomicU32::new(0);
+        RUNS.store(0, Ordering::SeqCst);
+
+        let suites = vec![Suite::new(
+            "",
+            vec![TestNode::it("counts", || {
+                RUNS.fetch_add(1, Ordering::SeqCst);
+            })],
+        )];
+        let input = std::io::Cursor::new("\n\n");
+        let config = RunConfig::default();
+        let result = run_suites_watch(&suites, &config, &mut *config.reporter(), input);
 
-                println!("{full_path}");
-            }
-        }
+        assert_eq!(RUNS.load(Ordering::SeqCst), 3, "initial run + one per line");
+        assert_eq!(result.passed, 1, "result reflects the last run only");
     }
-}
 
-fn tree_has_focus(nodes: &[TestNode]) -> bool {
-    nodes.iter().any(|node| match node {
-        TestNode::It { focused, .. } => *focused,
-        TestNode::Describe {
-            focused, children, ..
-        } => *focused || tree_has_focus(children),
-        TestNode::Ordered { .. } => false,
-    })
-}
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn watch_reruns_on_sigusr1() {
+        let _lock = WATCH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        static RUNS: AtomicU32 = AtomicU32::new(0);
+
+        /// Stdin that signals the process, then reaches EOF a while later.
+        struct Signaller;
+        impl std::io::Read for Signaller {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                unsafe { libc::raise(libc::SIGUSR1) };
+                std::thread::sleep(Duration::from_millis(500));
+                Ok(0)
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-    use std::time::Duration;
+        let suites = vec![Suite::new(
+            "",
+            vec![TestNode::it("counts", || {
+                RUNS.fetch_add(1, Ordering::SeqCst);
+            })],
+        )];
+        let input = std::io::BufReader::new(Signaller);
+        let config = RunConfig::default();
+        run_suites_watch(&suites, &config, &mut *config.reporter(), input);
+
+        assert_eq!(RUNS.load(Ordering::SeqCst), 2, "initial run + one for the signal");
+    }
 
     #[test]
-    fn ordered_is_skipped_when_focus_mode_is_active() {
-        static ORDERED_RAN: AtomicBool = AtomicBool::new(false);
-        ORDERED_RAN.store(false, Ordering::SeqCst);
+    fn group_failures_by_message_in_first_seen_order() {
+        let failures = vec![
+            Failure::new("table > row 1", "helper broke"),
+            Failure::new("other", "unrelated"),
+            Failure::new("table > row 2", "helper broke"),
+        ];
 
-        let nodes = vec![TestNode::describe(
-            "root",
+        let groups = group_failures(&failures);
+
+        assert_eq!(
+            groups,
             vec![
-                TestNode::fit("focused", || {}),
-                TestNode::Ordered {
-                    name: "ordered".to_string(),
-                    labels: Vec::new(),
-                    continue_on_failure: false,
-                    steps: vec![OrderedStep {
-                        name: "step".to_string(),
-                        body: Box::new(|| {
-                            ORDERED_RAN.store(true, Ordering::SeqCst);
-                        }),
-                    }],
-                },
-            ],
-        )];
+                ("helper broke", vec!["table > row 1", "table > row 2"]),
+                ("unrelated", vec!["other"]),
+            ]
+        );
+    }
 
-        let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
-        };
-        let result = run_tree(&nodes, &config);
+    #[test]
+    fn describe_labels_reach_the_label_filter_of_unlabeled_children() {
+        static SEEN: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+        let mut integration = TestNode::describe(
+            "api",
+            vec![TestNode::describe(
+                "users",
+                vec![TestNode::it("lists", || {
+                    *SEEN.lock().unwrap() = crate::current_test().unwrap().labels;
+                })],
+            )],
+        );
+        if let TestNode::Describe { labels, .. } = &mut integration {
+            labels.push("integration".to_string());
+        }
+        run_tree(&[integration], &RunConfig::default());
 
-        assert_eq!(result.failed, 0);
-        assert_eq!(result.passed, 1);
-        assert_eq!(result.skipped, 1);
-        assert!(!ORDERED_RAN.load(Ordering::SeqCst));
+        // The labels the runner checks against RSSPEC_LABEL_FILTER.
+        let labels = SEEN.lock().unwrap().clone();
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        assert_eq!(labels, ["integration"]);
+        assert!(!crate::filter::labels_match_filter(&labels, "!integration"));
     }
 
-    // C3 regression: skip!() should report as skipped, not passed
     #[test]
-    fn skip_reports_as_skipped_not_passed() {
-        let nodes = vec![TestNode::it("skippable", || {
-            crate::skip("not ready");
-            // skip!() macro does `skip() + return`, but we can't use the macro
-            // in a Fn closure, so just call skip() — the runner checks the flag
-            // regardless of whether the closure returned early.
-        })];
-
+    fn label_filter_is_parsed_once_and_malformed_filters_fail_the_run() {
+        assert!(label_filter(None).unwrap().is_none());
+        assert!(label_filter(Some("")).unwrap().is_none());
+        let error = label_filter(Some("a+b,c")).unwrap_err();
+        assert!(error.starts_with("'a+b,c' is invalid — "), "{error}");
+
+        let slow = TestNode::it("slow", || {});
+        let slow = with_fields!(slow, It { labels: vec!["slow".to_string()] });
+        let nodes = vec![TestNode::it("fast", || {}), slow];
         let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
+            label_filter: label_filter(Some("!slow")).unwrap().map(Arc::new),
+            ..RunConfig::default()
         };
         let result = run_tree(&nodes, &config);
-
-        assert_eq!(result.skipped, 1, "should be reported as skipped");
-        assert_eq!(result.passed, 0, "should not be reported as passed");
-        assert_eq!(result.failed, 0);
+        assert_eq!(run_order(&result), ["fast"]);
     }
 
-    // I1 regression: before_all panic should fail gracefully, not abort
     #[test]
-    fn before_all_panic_reports_failure_and_runs_after_all() {
-        static AFTER_ALL_RAN: AtomicBool = AtomicBool::new(false);
-        AFTER_ALL_RAN.store(false, Ordering::SeqCst);
+    fn current_test_is_set_only_while_a_test_runs() {
+        static SEEN: std::sync::Mutex<Option<crate::TestInfo>> = std::sync::Mutex::new(None);
 
-        let nodes = vec![TestNode::describe_with_hooks(
-            "broken setup",
-            vec![Box::new(|| panic!("setup exploded"))],
-            vec![Box::new(|| {
-                AFTER_ALL_RAN.store(true, Ordering::SeqCst);
-            })],
-            vec![TestNode::it("should not run", || {
-                panic!("child should be skipped");
+        let mut inner = TestNode::describe(
+            "inner",
+            vec![TestNode::it("probe", || {
+                *SEEN.lock().unwrap() = crate::current_test();
             })],
-        )];
+        );
+        if let TestNode::Describe { labels, .. } = &mut inner {
+            labels.push("auth".to_string());
+        }
+        let nodes = vec![TestNode::describe("outer", vec![inner])];
 
-        let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
-        };
-        let result = run_tree(&nodes, &config);
+        let config = RunConfig::default();
+        run_tree(&nodes, &config);
 
-        assert_eq!(result.failed, 1, "before_all failure counted");
-        assert_eq!(result.passed, 0, "child should not have run");
-        assert!(AFTER_ALL_RAN.load(Ordering::SeqCst), "after_all must still run");
+        let info = SEEN.lock().unwrap().take().expect("current_test set during body");
+        assert_eq!(info.name, "probe");
+        assert_eq!(info.path, "outer > inner > probe");
+        assert!(info.has_label("auth"));
+        assert!(crate::current_test().is_none(), "cleared after the test");
     }
 
-    // I1 regression: after_all panic should report failure
     #[test]
-    fn after_all_panic_reports_failure() {
-        let nodes = vec![TestNode::describe_with_hooks(
-            "broken teardown",
-            vec![],
-            vec![Box::new(|| panic!("teardown exploded"))],
-            vec![TestNode::it("passes", || {})],
-        )];
+    fn panic_message_renders_boxed_errors() {
+        let err: Box<dyn std::error::Error + Send + Sync> = "disk full".into();
+        let payload: Box<dyn std::any::Any + Send> = Box::new(err);
+        assert_eq!(panic_message(&*payload), "disk full");
+    }
+
+    #[test]
+    fn panic_message_renders_registered_types() {
+        #[derive(Debug)]
+        struct MyError(u32);
+        impl std::fmt::Display for MyError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "my error {}", self.0)
+            }
+        }
 
+        let payload: Box<dyn std::any::Any + Send> = Box::new(MyError(7));
+        assert!(panic_message(&*payload)
+            .starts_with("unknown panic payload of an unregistered type"));
+
+        crate::register_panic_payload::<MyError>();
+        assert_eq!(panic_message(&*payload), "my error 7");
+        let other: Box<dyn std::any::Any + Send> = Box::new(7u8);
+        assert!(panic_message(&*other).contains("MyError"));
+    }
+
+    #[test]
+    fn at_runs_only_the_nth_eligible_test() {
+        static RAN: std::sync::Mutex<Vec<&str>> = std::sync::Mutex::new(Vec::new());
+        RAN.lock().unwrap().clear();
+
+        let suites = vec![Suite::new(
+            "",
+            vec![TestNode::describe(
+                "root",
+                vec![
+                    TestNode::it("first", || RAN.lock().unwrap().push("first")),
+                    TestNode::it("second", || RAN.lock().unwrap().push("second")),
+                    TestNode::it("third", || RAN.lock().unwrap().push("third")),
+                ],
+            )],
+        )];
         let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
+            at: Some(2),
+            ..RunConfig::default()
         };
-        let result = run_tree(&nodes, &config);
+        let result = run_suites(&suites, &config, &mut *config.reporter());
 
-        assert_eq!(result.passed, 1, "test itself passed");
-        assert_eq!(result.failed, 1, "after_all failure counted");
+        assert_eq!(*RAN.lock().unwrap(), ["second"]);
+        assert_eq!(result.passed, 1);
     }
 
-    // I3 regression: one cleanup panic should not prevent other cleanups
     #[test]
-    fn deferred_cleanup_panic_does_not_skip_remaining() {
-        static SECOND_CLEANUP_RAN: AtomicBool = AtomicBool::new(false);
-        SECOND_CLEANUP_RAN.store(false, Ordering::SeqCst);
+    fn top_level_before_all_runs_once_around_the_suite() {
+        use std::sync::Mutex;
+        static LOG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        let log = |entry: &'static str| move || LOG.lock().unwrap().push(entry);
 
-        let nodes = vec![TestNode::it("cleanup test", || {
-            // First registered = runs last (LIFO)
-            crate::defer_cleanup(|| {
-                SECOND_CLEANUP_RAN.store(true, Ordering::SeqCst);
-            });
-            // Second registered = runs first, and panics
-            crate::defer_cleanup(|| {
-                panic!("cleanup boom");
-            });
-        })];
+        let mut suite = Suite::new(
+            "",
+            vec![
+                TestNode::it("first", log("first")),
+                TestNode::describe("nested", vec![TestNode::it("second", log("second"))]),
+            ],
+        );
+        suite.root = TestNode::describe_with_hooks(
+            "",
+            vec![Box::new(log("before_all"))],
+            vec![Box::new(log("after_all"))],
+            Vec::new(),
+        );
+        let config = RunConfig::default();
+        let result = run_suites(&[suite], &config, &mut *config.reporter());
+        assert_eq!(result.passed, 2);
+        assert_eq!(*LOG.lock().unwrap(), ["before_all", "first", "second", "after_all"]);
+
+        let mut suite = Suite::new("db", vec![TestNode::it("queries", log("queries"))]);
+        suite.root = TestNode::describe_with_hooks(
+            "",
+            vec![Box::new(|| panic!("no database"))],
+            vec![Box::new(log("after_all"))],
+            Vec::new(),
+        );
+        let result = run_suites(&[suite], &config, &mut *config.reporter());
+        assert_eq!((result.failed, result.skipped), (1, 1));
+        assert_eq!(result.failures[0].path, "db (before_all)");
+        assert_eq!(LOG.lock().unwrap()[4..], ["after_all"]);
+    }
+
+    #[test]
+    fn top_level_each_hooks_and_labels_apply_to_every_test() {
+        use std::sync::Mutex;
+        static LOG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        let log = |entry: &'static str| move || LOG.lock().unwrap().push(entry);
+        let labeled = |entry: &'static str| {
+            move || {
+                assert!(crate::current_test().unwrap().has_label("integration"));
+                LOG.lock().unwrap().push(entry);
+            }
+        };
+
+        let mut suite = Suite::new(
+            "",
+            vec![
+                TestNode::it("first", labeled("first")),
+                TestNode::describe("nested", vec![TestNode::it("second", labeled("second"))]),
+            ],
+        );
+        let mut root = TestNode::describe_with_each_hooks(
+            "",
+            vec![Box::new(log("before_each"))],
+            vec![Box::new(log("after_each"))],
+            Vec::new(),
+        );
+        if let TestNode::Describe { labels, .. } = &mut root {
+            labels.push("integration".to_string());
+        }
+        suite.root = root;
+        let config = RunConfig::default();
+        let result = run_suites(&[suite], &config, &mut *config.reporter());
+
+        assert_eq!(result.passed, 2);
+        assert_eq!(
+            *LOG.lock().unwrap(),
+            ["before_each", "first", "after_each", "before_each", "second", "after_each"]
+        );
+    }
 
+    #[test]
+    fn at_out_of_range_fails() {
+        let suites = vec![Suite::new("", vec![TestNode::it("only", || {})])];
         let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
+            at: Some(5),
+            ..RunConfig::default()
         };
-        let result = run_tree(&nodes, &config);
+        let result = run_suites(&suites, &config, &mut *config.reporter());
 
-        // The test body itself passed, but cleanup panicked → reported as failure
         assert_eq!(result.failed, 1);
-        assert!(
-            SECOND_CLEANUP_RAN.load(Ordering::SeqCst),
-            "second cleanup must run despite first panicking"
+        assert_eq!(result.passed, 0);
+    }
+
+    #[test]
+    fn outline_renders_the_tree_structure() {
+        let mut later = TestNode::describe("later", vec![TestNode::it("waits", || {})]);
+        if let TestNode::Describe { pending, .. } = &mut later {
+            *pending = true;
+        }
+        let step = |name: &str| OrderedStep::new(name, || {});
+        let nodes = vec![
+            TestNode::describe(
+                "db",
+                vec![
+                    TestNode::it("reads", || {}),
+                    TestNode::fit("writes", || {}),
+                    TestNode::ordered("migrates", vec![step("up"), step("down")]),
+                    later,
+                ],
+            ),
+            TestNode::it("top", || {}),
+        ];
+
+        assert_eq!(
+            outline(&nodes),
+            "db
+  - reads
+  - writes (focused)
+  - migrates (ordered)
+      1. up
+      2. down
+  later (pending)
+    - waits
+- top
+"
         );
     }
 
-    // C1 regression: before_each panic must still run after_each
     #[test]
-    fn before_each_panic_still_runs_after_each() {
-        static AFTER_EACH_RAN: AtomicBool = AtomicBool::new(false);
-        AFTER_EACH_RAN.store(false, Ordering::SeqCst);
+    fn plan_dump_lists_selected_and_pending_tests_in_run_order() {
+        let mut db = TestNode::describe(
+            "db",
+            vec![TestNode::it("reads", || {}), TestNode::it("writes", || {})],
+        );
+        if let TestNode::Describe { labels, .. } = &mut db {
+            labels.push("integration".to_string());
+        }
+        let mut later = TestNode::describe("later", vec![TestNode::it("migrates", || {})]);
+        if let TestNode::Describe { pending, .. } = &mut later {
+            *pending = true;
+        }
+        let nodes = vec![db, later, TestNode::it("unit", || {})];
+
+        let config = RunConfig::parse(&args(&["--plan-dump", "--reverse", "s"]));
+        assert!(config.plan_dump);
+        let mut planned = Vec::new();
+        let _ = visit_tests(&nodes, &[], &HookChain::default(), false, false, &config, &mut |t| {
+            planned.push(crate::json::plan_line(&t));
+            ControlFlow::Continue(())
+        });
+
+        assert_eq!(
+            planned,
+            [
+                r#"{"kind":"plan","path":"later > migrates","labels":[],"pending":true}"#,
+                r#"{"kind":"plan","path":"db > writes","labels":["integration"],"pending":false}"#,
+                r#"{"kind":"plan","path":"db > reads","labels":["integration"],"pending":false}"#,
+            ]
+        );
+    }
 
-        let nodes = vec![TestNode::describe_with_each_hooks(
-            "broken before_each",
-            vec![Box::new(|| panic!("before_each exploded"))],
-            vec![Box::new(|| {
-                AFTER_EACH_RAN.store(true, Ordering::SeqCst);
-            })],
-            vec![TestNode::it("test", || {})],
-        )];
+    fn shuffle_tree() -> Vec<TestNode> {
+        vec![
+            TestNode::describe(
+                "group",
+                vec![
+                    TestNode::it("a", || {}),
+                    TestNode::it("b", || {}),
+                    TestNode::it("c", || {}),
+                ],
+            ),
+            TestNode::it("d", || {}),
+        ]
+    }
+
+    fn run_order(result: &RunResult) -> Vec<&str> {
+        result.tests.iter().map(|t| t.path.as_str()).collect()
+    }
 
+    #[test]
+    fn shuffle_is_reproducible_by_seed_and_matches_at_order() {
+        let nodes = shuffle_tree();
         let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
+            shuffle: true,
+            seed: Some(1234),
+            ..RunConfig::default()
         };
-        let result = run_tree(&nodes, &config);
 
-        assert_eq!(result.failed, 1, "before_each failure reported");
-        assert!(AFTER_EACH_RAN.load(Ordering::SeqCst), "after_each must still run");
+        let first = run_tree(&nodes, &config);
+        let second = run_tree(&nodes, &config);
+        assert_eq!(run_order(&first), run_order(&second));
+        assert_eq!(first.passed, 4);
+
+        let mut paths = Vec::new();
+        collect_runnable_paths(&nodes, &HookChain::default(), false, &config, &mut paths);
+        assert_eq!(paths, run_order(&first));
     }
 
-    // C2 regression: after_each panic must not lose the original test failure
     #[test]
-    fn after_each_panic_preserves_test_failure() {
-        let nodes = vec![TestNode::describe_with_each_hooks(
-            "both fail",
-            vec![],
-            vec![Box::new(|| panic!("after_each exploded"))],
-            vec![TestNode::it("fails", || {
-                panic!("test body failed");
-            })],
-        )];
+    fn custom_order_strategy_controls_sibling_order() {
+        let nodes = shuffle_tree();
+        let config = RunConfig::default().order_strategy(|indices, _seed| indices.reverse());
 
-        let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
-        };
         let result = run_tree(&nodes, &config);
 
-        assert_eq!(result.failed, 1);
-        // The failure message should contain the body's error, not after_each's
-        assert!(
-            result.failures[0].contains("test body failed"),
-            "original test failure must be reported, got: {}",
-            result.failures[0]
+        assert_eq!(
+            run_order(&result),
+            vec!["d", "group > c", "group > b", "group > a"]
         );
     }
 
-    // C2 regression: one after_each panic must not skip remaining after_each hooks
     #[test]
-    fn after_each_panic_runs_remaining_hooks() {
-        static SECOND_AFTER_EACH_RAN: AtomicBool = AtomicBool::new(false);
-        SECOND_AFTER_EACH_RAN.store(false, Ordering::SeqCst);
-
-        // Outer describe has one after_each, inner describe has another that panics.
-        // The outer after_each must still run (after_each runs innermost first).
-        let inner = TestNode::describe_with_each_hooks(
-            "inner",
-            vec![],
-            vec![Box::new(|| panic!("inner after_each panicked"))],
-            vec![TestNode::it("test", || {})],
-        );
-        let outer = TestNode::describe_with_each_hooks(
-            "outer",
-            vec![],
-            vec![Box::new(|| {
-                SECOND_AFTER_EACH_RAN.store(true, Ordering::SeqCst);
-            })],
-            vec![inner],
+    fn describe_shuffle_overrides_apply_to_their_subtree() {
+        let mut nodes = shuffle_tree();
+        if let TestNode::Describe { shuffle, .. } = &mut nodes[0] {
+            *shuffle = Some(ShuffleOverride::Pinned);
+        }
+        let config = RunConfig::default().order_strategy(|indices, _seed| indices.reverse());
+        let result = run_tree(&nodes, &config);
+        assert_eq!(
+            run_order(&result),
+            vec!["d", "group > a", "group > b", "group > c"]
         );
 
-        let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
-        };
-        let result = run_tree(&[outer], &config);
+        if let TestNode::Describe { shuffle, .. } = &mut nodes[0] {
+            *shuffle = Some(ShuffleOverride::Seed(5));
+        }
+        let seeds = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen = std::rc::Rc::clone(&seeds);
+        let mut config = RunConfig::default()
+            .order_strategy(move |_indices, seed| seen.borrow_mut().push(seed));
+        let group = ["group".to_string()];
+        for run_seed in [1, 2] {
+            config.seed = Some(run_seed);
+            seeds.borrow_mut().clear();
+            run_tree(&nodes, &config);
+            let seeds = seeds.borrow();
+            assert!(seeds.contains(&crate::shuffle::group_seed(5, &group)));
+            assert!(!seeds.contains(&crate::shuffle::group_seed(run_seed, &group)));
+            // The top level still follows the run seed.
+            assert!(seeds.contains(&crate::shuffle::group_seed(run_seed, &[])));
+        }
+    }
 
-        assert_eq!(result.failed, 1);
-        assert!(
-            SECOND_AFTER_EACH_RAN.load(Ordering::SeqCst),
-            "outer after_each must still run despite inner after_each panicking"
+    #[test]
+    fn reverse_runs_siblings_bottom_to_top() {
+        let nodes = shuffle_tree();
+        let config = RunConfig::parse(&args(&["--reverse"]));
+
+        let result = run_tree(&nodes, &config);
+        assert_eq!(
+            run_order(&result),
+            vec!["d", "group > c", "group > b", "group > a"]
         );
+
+        let mut paths = Vec::new();
+        collect_runnable_paths(&nodes, &HookChain::default(), false, &config, &mut paths);
+        assert_eq!(paths, run_order(&result));
+
+        let config = RunConfig::parse(&args(&["--reverse", "--seed", "7"]));
+        assert!(!config.reverse, "--shuffle wins over --reverse");
     }
 
-    // I7 regression: mixed +, filter is rejected
     #[test]
-    fn mixed_and_or_filter_is_rejected() {
-        assert!(!crate::labels_match_filter(&["a", "b"], "a+b,c"));
+    fn parse_shuffle_flags() {
+        let config = RunConfig::parse(&args(&["--seed", "99"]));
+        assert!(config.shuffle);
+        assert_eq!(config.seed, Some(99));
+        let config = RunConfig::parse(&args(&["--shuffle"]));
+        assert!(config.shuffle);
+        assert_eq!(config.seed, None);
+    }
+
+    fn it_depending_on(name: &str, dep: &str, f: impl Fn() + 'static) -> TestNode {
+        with_fields!(TestNode::it(name, f), It { depends_on: vec![dep.to_string()] })
     }
 
     #[test]
-    fn retries_and_timeout_compose() {
-        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
-        ATTEMPTS.store(0, Ordering::SeqCst);
+    fn depends_on_runs_dependencies_first_in_any_order() {
+        let nodes = vec![
+            TestNode::describe(
+                "profile",
+                vec![
+                    it_depending_on("edits user", "setup > creates user", || {}),
+                    TestNode::it("shows help", || {}),
+                ],
+            ),
+            TestNode::describe("setup", vec![TestNode::it("creates user", || {})]),
+            it_depending_on("deletes user", "edits user", || {}),
+        ];
+
+        let reverse = RunConfig {
+            reverse: true,
+            ..RunConfig::default()
+        };
+        let shuffled = (0..8).map(|seed| RunConfig {
+            shuffle: true,
+            seed: Some(seed),
+            ..RunConfig::default()
+        });
+        for config in [RunConfig::default(), reverse].into_iter().chain(shuffled) {
+            let result = run_tree(&nodes, &config);
+            let order = run_order(&result);
+            let position = |path: &str| order.iter().position(|p| *p == path).unwrap();
+            assert!(position("setup > creates user") < position("profile > edits user"));
+            assert!(position("profile > edits user") < position("deletes user"));
+            assert_eq!(result.passed, 4);
+        }
+    }
 
-        let nodes = vec![TestNode::It {
-            name: "combined".to_string(),
-            focused: false,
-            pending: false,
-            labels: Vec::new(),
-            retries: Some(2),
-            timeout_ms: Some(5),
-            must_pass_repeatedly: None,
-            test_fn: Box::new(|| {
-                let n = ATTEMPTS.fetch_add(1, Ordering::SeqCst);
-                std::thread::sleep(Duration::from_millis(10));
-                assert!(n >= 2, "attempt {n}");
+    #[test]
+    fn depends_on_skips_when_dependency_did_not_pass() {
+        static DEPENDENT_RAN: AtomicBool = AtomicBool::new(false);
+        DEPENDENT_RAN.store(false, Ordering::SeqCst);
+
+        let nodes = vec![
+            it_depending_on("uses user", "creates user", || {
+                DEPENDENT_RAN.store(true, Ordering::SeqCst);
             }),
-        }];
+            it_depending_on("deletes user", "uses user", || {}),
+            TestNode::it("creates user", || panic!("db down")),
+        ];
 
-        let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
-        };
+        let config = RunConfig::default();
         let result = run_tree(&nodes, &config);
 
-        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+        assert!(!DEPENDENT_RAN.load(Ordering::SeqCst));
         assert_eq!(result.failed, 1);
+        assert_eq!(result.skipped, 2);
+        let message = result.tests[1].message.as_deref();
+        assert_eq!(message, Some("dependency 'creates user' did not pass"));
     }
 
     #[test]
-    fn retries_and_must_pass_repeatedly_compose() {
-        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
-        ATTEMPTS.store(0, Ordering::SeqCst);
+    fn depends_on_rejects_unknown_ambiguous_and_cyclic_dependencies() {
+        let failures = |nodes: Vec<TestNode>| {
+            let suites = [Suite::new("", nodes)];
+            let config = RunConfig::default();
+            let result = run_suites(&suites, &config, &mut *config.reporter());
+            assert_eq!(result.passed, 0, "nothing runs");
+            result.failures.into_iter().map(|f| f.message).collect::<Vec<_>>()
+        };
 
-        let nodes = vec![TestNode::It {
-            name: "combined".to_string(),
-            focused: false,
-            pending: false,
-            labels: Vec::new(),
-            retries: Some(1),
-            timeout_ms: None,
-            must_pass_repeatedly: Some(2),
-            test_fn: Box::new(|| {
-                let n = ATTEMPTS.fetch_add(1, Ordering::SeqCst);
-                assert!(n > 0, "first call should fail and retry");
-            }),
-        }];
+        let unknown = failures(vec![it_depending_on("uses", "missing", || {})]);
+        assert_eq!(unknown, ["'uses' depends on 'missing', which names no test"]);
+
+        let ambiguous = failures(vec![
+            TestNode::describe("a", vec![TestNode::it("creates", || {})]),
+            TestNode::describe("b", vec![TestNode::it("creates", || {})]),
+            it_depending_on("uses", "creates", || {}),
+        ]);
+        assert_eq!(
+            ambiguous,
+            ["'uses' depends on 'creates', which matches several tests \
+              (a > creates, b > creates); use the full path"]
+        );
+
+        let cycle = failures(vec![
+            it_depending_on("first", "second", || {}),
+            it_depending_on("second", "first", || {}),
+        ]);
+        assert_eq!(cycle, ["depends_on cycle: 'first' → 'second' → 'first'"]);
+
+        let crossed = failures(vec![
+            TestNode::describe(
+                "a",
+                vec![it_depending_on("one", "b > two", || {}), TestNode::it("three", || {})],
+            ),
+            TestNode::describe(
+                "b",
+                vec![it_depending_on("four", "a > three", || {}), TestNode::it("two", || {})],
+            ),
+        ]);
+        assert_eq!(
+            crossed,
+            ["'b' and 'a' each hold a dependency of the other, so neither can run first"]
+        );
+    }
+
+    #[test]
+    fn depends_on_runs_dependencies_the_selection_left_out() {
+        static RAN: std::sync::Mutex<Vec<&str>> = std::sync::Mutex::new(Vec::new());
+        RAN.lock().unwrap().clear();
 
+        let suites = [Suite::new(
+            "",
+            vec![
+                it_depending_on("uses user", "creates user", || RAN.lock().unwrap().push("uses")),
+                TestNode::it("creates user", || RAN.lock().unwrap().push("creates")),
+                TestNode::it("unrelated", || RAN.lock().unwrap().push("unrelated")),
+            ],
+        )];
         let config = RunConfig {
-            filter: None,
-            list: false,
-            include_ignored: false,
+            filter: Some("uses".to_string()),
+            ..RunConfig::default()
         };
-        let result = run_tree(&nodes, &config);
+        let result = run_suites(&suites, &config, &mut *config.reporter());
 
-        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
-        assert_eq!(result.failed, 0);
-        assert_eq!(result.passed, 1);
+        assert_eq!(*RAN.lock().unwrap(), ["creates", "uses"]);
+        assert_eq!(result.passed, 2);
     }
 
     // ---- detect_libtest_args regression tests ----
@@ -1378,6 +6449,70 @@ mod tests {
         assert!(detect_libtest_args(&args(&["--test-threads", "2"])).is_some());
     }
 
+    #[test]
+    fn default_timeout_applies_to_tests_without_their_own() {
+        let nap = || std::thread::sleep(std::time::Duration::from_millis(60));
+        let patient = TestNode::it("own timeout", nap);
+        let patient = with_fields!(patient, It { timeout_ms: Some(5_000) });
+        let nodes = vec![
+            TestNode::it("slow", nap),
+            patient,
+            TestNode::ordered("slow steps", vec![OrderedStep::new("nap", nap)]),
+            // Only a Send body can be abandoned; this one never returns.
+            send_it("hangs", || loop {
+                std::thread::park();
+            }),
+        ];
+        let config = RunConfig::parse(&args(&["--default-timeout", "20"]));
+        let result = run_tree(&nodes, &config);
+
+        let failed: Vec<_> = result.failures.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(failed, ["slow", "slow steps", "hangs"]);
+        assert!(result.failures[0].message.starts_with("test timed out after 20ms"));
+        assert!(result.failures[2].message.contains("its thread was abandoned"));
+        assert_eq!(result.passed, 1);
+
+        // RSSPEC_DEFAULT_TIMEOUT_MS applies only when the flag wasn't given.
+        let env = |flags: &[&str], ms| {
+            RunConfig::parse(&args(flags)).default_timeout_fallback(Some(ms)).default_timeout_ms
+        };
+        assert_eq!(env(&[], "30000"), Some(30_000));
+        assert_eq!(env(&["--default-timeout=100"], "30000"), Some(100));
+        assert_eq!(env(&[], "soon"), None);
+    }
+
+    #[test]
+    fn test_threads_sets_jobs_in_harness_free_binaries() {
+        assert_eq!(RunConfig::parse(&args(&["--test-threads=1"])).jobs, Some(1));
+        let config = RunConfig::parse(&args(&["--test-threads", "3", "cart"]));
+        assert_eq!((config.jobs, config.filter.as_deref()), (Some(3), Some("cart")));
+        assert_eq!(RunConfig::parse(&args(&["--test-threads=0"])).jobs, None);
+
+        // RUST_TEST_THREADS only supplies the count of a bare `--jobs`.
+        let env = |flags: &[&str], threads| {
+            RunConfig::parse(&args(flags)).test_threads_fallback(Some(threads)).jobs
+        };
+        assert_eq!(env(&[], "2"), None);
+        assert_eq!(env(&["--jobs"], "2"), Some(2));
+        assert_eq!(env(&["--test-threads=4"], "2"), Some(4));
+        assert_eq!(env(&["--jobs", "5"], "2"), Some(5));
+        assert_eq!(env(&["--jobs"], "many"), RunConfig::parse(&args(&["--jobs"])).jobs);
+
+        // On the main thread, a harness-free binary owns the flag.
+        let on_main = std::thread::Builder::new()
+            .name("main".to_string())
+            .spawn(|| {
+                (
+                    detect_libtest_args(&args(&["--test-threads=1"])),
+                    detect_libtest_args(&args(&["--test-threads=1", "--format=json"])),
+                )
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(on_main, (None, Some("--format=json".to_string())));
+    }
+
     #[test]
     fn detect_libtest_args_catches_other_libtest_flags() {
         assert!(detect_libtest_args(&args(&["--show-output"])).is_some());
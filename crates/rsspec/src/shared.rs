@@ -0,0 +1,57 @@
+//! Values built once by a scope's `before_all` and read by its tests — see
+//! [`Context::before_all_value`](crate::Context::before_all_value).
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A handle to the value built by
+/// [`Context::before_all_value`](crate::Context::before_all_value).
+///
+/// Clone it into each test that needs the value. Tests run on one thread,
+/// so `T` needs neither `Send` nor `Sync`.
+pub struct Shared<T> {
+    slot: Rc<RefCell<Option<Rc<T>>>>,
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared {
+            slot: Rc::clone(&self.slot),
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    pub(crate) fn empty() -> Self {
+        Shared {
+            slot: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Store the value built by the `before_all` hook, replacing the one from
+    /// an earlier run (e.g. under `--watch`).
+    pub(crate) fn set(&self, value: T) {
+        *self.slot.borrow_mut() = Some(Rc::new(value));
+    }
+
+    /// The value, once the scope's `before_all` has run.
+    ///
+    /// # Panics
+    ///
+    /// If called before then, e.g. from a test outside the scope.
+    pub fn get(&self) -> Rc<T> {
+        self.slot
+            .borrow()
+            .clone()
+            .expect("rsspec: before_all value used before its before_all ran")
+    }
+
+    /// Take the value out, e.g. in the scope's `after_all` to shut down a
+    /// server built by `before_all`. It drops once the returned `Rc` and
+    /// any others from [`get`](Self::get) are gone. `None` if the
+    /// `before_all` hasn't run or the value was already taken; `get`
+    /// panics again until the next run of the scope builds it anew.
+    pub fn take(&self) -> Option<Rc<T>> {
+        self.slot.borrow_mut().take()
+    }
+}
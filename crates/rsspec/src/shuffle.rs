@@ -0,0 +1,92 @@
+//! Seeded shuffling of sibling tests for `--shuffle`.
+//!
+//! Each group of siblings is permuted with its own seed, derived from the run
+//! seed and the group's path, so the order of one describe doesn't depend on
+//! how many tests precede it and a given `--seed` always reproduces the run.
+
+use std::rc::Rc;
+
+/// Reorders a group of sibling indices in place, given the group's seed.
+///
+/// Receives `0..n` in declaration order. Set via
+/// [`RunConfig::order_strategy`](crate::runner::RunConfig::order_strategy).
+pub(crate) type OrderStrategy = Rc<dyn Fn(&mut Vec<usize>, u64)>;
+
+/// A describe's override of the run's sibling order, applied to its whole
+/// subtree until a nested describe sets its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShuffleOverride {
+    /// Keep declaration order, even under `--shuffle` or `--reverse`.
+    Pinned,
+    /// Under `--shuffle`, use this seed instead of the run seed.
+    Seed(u64),
+}
+
+/// The default strategy: a Fisher-Yates shuffle driven by SplitMix64.
+pub(crate) fn fisher_yates(indices: &mut [usize], seed: u64) {
+    let mut state = seed;
+    for i in (1..indices.len()).rev() {
+        let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+        indices.swap(i, j);
+    }
+}
+
+/// Seed for the sibling group at `path`: the run seed mixed with an FNV-1a
+/// hash of the path, stable across platforms and Rust versions.
+pub(crate) fn group_seed(seed: u64, path: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for segment in path {
+        for byte in segment.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    let mut state = seed ^ hash;
+    splitmix64(&mut state)
+}
+
+/// A seed for runs that ask for `--shuffle` without `--seed`.
+pub(crate) fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    let mut state = nanos ^ u64::from(std::process::id());
+    splitmix64(&mut state)
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fisher_yates_is_a_seeded_permutation() {
+        let mut a: Vec<usize> = (0..20).collect();
+        let mut b = a.clone();
+        fisher_yates(&mut a, 42);
+        fisher_yates(&mut b, 42);
+        assert_eq!(a, b);
+
+        let mut sorted = a.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert_ne!(a, sorted);
+    }
+
+    #[test]
+    fn group_seed_depends_on_path() {
+        let outer = vec!["outer".to_string()];
+        let inner = vec!["outer".to_string(), "inner".to_string()];
+        assert_eq!(group_seed(7, &outer), group_seed(7, &outer));
+        assert_ne!(group_seed(7, &outer), group_seed(7, &inner));
+        assert_ne!(group_seed(7, &outer), group_seed(8, &outer));
+    }
+}
@@ -0,0 +1,150 @@
+//! Soft assertions: check many things and see every failure at once.
+//!
+//! Each check on a [`SoftAssertions`] guard records a failure instead of
+//! panicking. When the guard is dropped at the end of the test body, or on
+//! [`assert_all`](SoftAssertions::assert_all), it panics once listing them
+//! all:
+//!
+//! ```rust
+//! let mut soft = rsspec::soft();
+//! soft.eq(200, 200, "status");
+//! soft.eq("application/json", "application/json", "content type");
+//! soft.check(!"body".is_empty(), "body present");
+//! soft.assert_all();
+//! ```
+//!
+//! The panic happens inside the test body, so it's the test's failure and
+//! `after_each` hooks and [`defer_cleanup`](crate::defer_cleanup) cleanups
+//! still run afterwards, as for any failing test. If the body panics for
+//! another reason first, the recorded failures are added to that failure
+//! as notes instead.
+
+use std::fmt::Debug;
+use std::panic::Location;
+
+/// Start collecting soft assertions for the current test.
+pub fn soft() -> SoftAssertions {
+    SoftAssertions {
+        failures: Vec::new(),
+    }
+}
+
+/// Records failed checks and reports them together. See the
+/// [module docs](self).
+#[derive(Debug, Default)]
+pub struct SoftAssertions {
+    failures: Vec<String>,
+}
+
+impl SoftAssertions {
+    /// Record a failure unless `actual == expected`.
+    #[track_caller]
+    pub fn eq<T, U>(&mut self, actual: T, expected: U, label: &str) -> &mut Self
+    where
+        T: PartialEq<U> + Debug,
+        U: Debug,
+    {
+        let passed = actual == expected;
+        self.record(passed, || format!("{label}: expected {expected:?}, got {actual:?}"))
+    }
+
+    /// Record a failure if `actual == unexpected`.
+    #[track_caller]
+    pub fn ne<T, U>(&mut self, actual: T, unexpected: U, label: &str) -> &mut Self
+    where
+        T: PartialEq<U> + Debug,
+        U: Debug,
+    {
+        let passed = actual != unexpected;
+        self.record(passed, || format!("{label}: expected anything but {unexpected:?}"))
+    }
+
+    /// Record a failure unless `condition` holds.
+    #[track_caller]
+    pub fn check(&mut self, condition: bool, label: &str) -> &mut Self {
+        self.record(condition, || format!("{label}: condition was false"))
+    }
+
+    /// The failures recorded so far, each with its source location.
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    /// Fail now if any check failed, listing every failure.
+    #[track_caller]
+    pub fn assert_all(mut self) {
+        let failures = std::mem::take(&mut self.failures);
+        if !failures.is_empty() {
+            panic!("{}", summary(&failures));
+        }
+    }
+
+    #[track_caller]
+    fn record(&mut self, passed: bool, failure: impl FnOnce() -> String) -> &mut Self {
+        crate::record_assertion();
+        if !passed {
+            let at = Location::caller();
+            self.failures.push(format!("{} (at {}:{})", failure(), at.file(), at.line()));
+        }
+        self
+    }
+}
+
+impl Drop for SoftAssertions {
+    fn drop(&mut self) {
+        let failures = std::mem::take(&mut self.failures);
+        if failures.is_empty() {
+            return;
+        }
+        if std::thread::panicking() {
+            // A second panic would abort; attach them to the first instead.
+            for failure in failures {
+                crate::context_note(format!("soft assertion failed: {failure}"));
+            }
+        } else {
+            panic!("{}", summary(&failures));
+        }
+    }
+}
+
+fn summary(failures: &[String]) -> String {
+    let mut out = match failures.len() {
+        1 => "1 soft assertion failed:".to_string(),
+        n => format!("{n} soft assertions failed:"),
+    };
+    for (i, failure) in failures.iter().enumerate() {
+        out.push_str(&format!("\n  {}. {failure}", i + 1));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_failure_is_listed_once_the_guard_drops() {
+        let payload = std::panic::catch_unwind(|| {
+            let mut soft = soft();
+            soft.eq(1, 2, "id").check(true, "ok").ne("a", "a", "name");
+            soft.check(false, "active");
+        })
+        .unwrap_err();
+        let message = crate::runner::panic_message(&*payload);
+        let lines: Vec<&str> = message.lines().collect();
+        assert_eq!(lines.len(), 4, "{message}");
+        assert_eq!(lines[0], "3 soft assertions failed:");
+        assert!(lines[1].starts_with("  1. id: expected 2, got 1 (at "), "{message}");
+        assert!(lines[1].contains("src/soft.rs:"), "{message}");
+        assert!(lines[2].starts_with("  2. name: expected anything but \"a\""), "{message}");
+        assert!(lines[3].starts_with("  3. active: condition was false"), "{message}");
+    }
+
+    #[test]
+    fn passing_checks_do_not_fail() {
+        let mut soft = soft();
+        soft.eq(vec![1], [1], "items").ne(1, 2, "count");
+        assert!(soft.failures().is_empty());
+        soft.assert_all();
+    }
+}
@@ -0,0 +1,149 @@
+//! Hook call counts for meta-tests. [`HookSpy`] is available with the
+//! `test-support` feature.
+//!
+//! The runner reports every hook it calls here; without the feature that
+//! compiles to nothing.
+
+#[cfg(feature = "test-support")]
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "test-support")]
+use std::rc::Rc;
+
+/// A kind of scope hook, as counted by [`HookSpy`](crate::HookSpy).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HookPhase {
+    BeforeAll,
+    BeforeEach,
+    JustBeforeEach,
+    AfterEach,
+    AfterAll,
+}
+
+#[cfg(feature = "test-support")]
+type Counts = Rc<Cell<[u32; 5]>>;
+
+#[cfg(feature = "test-support")]
+thread_local! {
+    static ACTIVE: RefCell<Option<Counts>> = const { RefCell::new(None) };
+}
+
+/// Count one call of a `phase` hook for the attached spy, if any.
+pub(crate) fn record(phase: HookPhase) {
+    #[cfg(feature = "test-support")]
+    ACTIVE.with(|active| {
+        if let Some(counts) = &*active.borrow() {
+            let mut current = counts.get();
+            current[phase as usize] += 1;
+            counts.set(current);
+        }
+    });
+    #[cfg(not(feature = "test-support"))]
+    let _ = phase;
+}
+
+/// Counts the hooks the runner calls on this thread while the spy is
+/// attached, for tests of a suite's hook setup. Requires the `test-support`
+/// feature.
+///
+/// Each hook closure counts once per call, so a scope with two
+/// `before_each` hooks and three tests counts six. Hooks run by `--jobs`
+/// workers happen in other processes and aren't counted.
+///
+/// ```rust
+/// use rsspec::{HookPhase, HookSpy};
+///
+/// let spy = HookSpy::attach();
+/// rsspec::run_inline(|ctx| {
+///     ctx.describe("cart", |ctx| {
+///         ctx.before_all(|| {});
+///         ctx.before_each(|| {});
+///         ctx.it("adds", || {});
+///         ctx.it("removes", || {});
+///     });
+/// });
+/// assert_eq!(spy.count(HookPhase::BeforeAll), 1);
+/// assert_eq!(spy.count(HookPhase::BeforeEach), 2);
+/// ```
+#[cfg(feature = "test-support")]
+pub struct HookSpy {
+    counts: Counts,
+    /// The spy this one replaced, attached again when this one is dropped.
+    previous: Option<Counts>,
+}
+
+#[cfg(feature = "test-support")]
+impl HookSpy {
+    /// Start counting hook calls on this thread, until the spy is dropped.
+    pub fn attach() -> Self {
+        let counts = Counts::default();
+        let previous = ACTIVE.with(|active| active.borrow_mut().replace(Rc::clone(&counts)));
+        HookSpy { counts, previous }
+    }
+
+    /// How many `phase` hooks have been called since the spy was attached
+    /// or last [`reset`](Self::reset).
+    pub fn count(&self, phase: HookPhase) -> u32 {
+        self.counts.get()[phase as usize]
+    }
+
+    /// Set every count back to zero.
+    pub fn reset(&self) {
+        self.counts.set([0; 5]);
+    }
+}
+
+#[cfg(feature = "test-support")]
+impl Drop for HookSpy {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| *active.borrow_mut() = self.previous.take());
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_every_hook_call_by_phase() {
+        let spy = HookSpy::attach();
+        crate::run_inline(|ctx| {
+            ctx.describe("outer", |ctx| {
+                ctx.before_all(|| {});
+                ctx.before_each(|| {});
+                ctx.after_each(|| {});
+                ctx.it("one", || {});
+                ctx.describe("inner", |ctx| {
+                    ctx.before_each(|| {});
+                    ctx.just_before_each(|| {});
+                    ctx.after_all(|| {});
+                    ctx.it("two", || {});
+                    ctx.it("three", || {});
+                });
+            });
+        });
+
+        let counts = [
+            HookPhase::BeforeAll,
+            HookPhase::BeforeEach,
+            HookPhase::JustBeforeEach,
+            HookPhase::AfterEach,
+            HookPhase::AfterAll,
+        ]
+        .map(|phase| spy.count(phase));
+        assert_eq!(counts, [1, 5, 2, 3, 1]);
+
+        spy.reset();
+        assert_eq!(spy.count(HookPhase::BeforeEach), 0);
+    }
+
+    #[test]
+    fn dropping_a_spy_reattaches_the_previous_one() {
+        let outer = HookSpy::attach();
+        {
+            let _inner = HookSpy::attach();
+            record(HookPhase::AfterAll);
+        }
+        record(HookPhase::AfterAll);
+        assert_eq!(outer.count(HookPhase::AfterAll), 1);
+    }
+}
@@ -0,0 +1,119 @@
+//! Per-test typed state for the closure API — see
+//! [`Context::describe_with_state`](crate::Context::describe_with_state).
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::context::{Context, ItBuilder};
+
+/// A describe scope whose hooks and tests share a value of type `S`.
+///
+/// A fresh `S` is built for every test (and every retry) before the scope's
+/// `before_each` hooks run, handed by `&mut` to those hooks, the test body
+/// and the `after_each` hooks, and dropped after the last of them.
+///
+/// ```rust,no_run
+/// # fn main() { rsspec::run(|ctx| {
+/// ctx.describe_with_state("cart", Vec::new, |cart| {
+///     cart.before_each(|items: &mut Vec<&str>| items.push("apple"));
+///
+///     cart.it("starts with the fixture", |items| assert_eq!(items, &["apple"]));
+///
+///     cart.describe_with_state("with a discount", |items| (items.clone(), 10), |sale| {
+///         sale.it("keeps the items", |(items, percent)| {
+///             assert_eq!(items.len(), 1);
+///             assert_eq!(*percent, 10);
+///         });
+///     });
+/// });
+/// # }); }
+/// ```
+pub struct StateContext<S> {
+    slot: Rc<RefCell<Option<S>>>,
+}
+
+impl<S: 'static> StateContext<S> {
+    /// Set up state handling in the current (just pushed) scope and run `body`.
+    pub(crate) fn enter(init: impl Fn() -> S + 'static, body: impl FnOnce(&StateContext<S>)) {
+        let state = StateContext {
+            slot: Rc::new(RefCell::new(None)),
+        };
+        // Registered first: `after_each` hooks in a scope run in reverse, so
+        // this one drops the state after every other hook has used it.
+        let slot = Rc::clone(&state.slot);
+        Context.after_each(move || drop(slot.borrow_mut().take()));
+        let slot = Rc::clone(&state.slot);
+        Context.before_each(move || {
+            let value = init();
+            *slot.borrow_mut() = Some(value);
+        });
+        body(&state);
+    }
+
+    /// Run `f` on the current test's state.
+    fn with<R>(slot: &RefCell<Option<S>>, f: impl FnOnce(&mut S) -> R) -> R {
+        let mut state = slot.borrow_mut();
+        f(state.as_mut().expect("rsspec: state used outside of its test"))
+    }
+
+    /// The stateless [`Context`], for everything else in this scope
+    /// (`before_all`, `labels`, stateless tests, ...).
+    pub fn context(&self) -> Context {
+        Context
+    }
+
+    /// A nested describe sharing this scope's state.
+    pub fn describe(&self, name: &str, body: impl FnOnce(&StateContext<S>)) {
+        Context.describe(name, |_| body(self));
+    }
+
+    /// A nested describe whose hooks and tests get their own state `T`, built
+    /// for each test from this scope's state by `init`. Tests in the nested
+    /// scope see only `T`; wrap or clone what they need from `S` into it.
+    pub fn describe_with_state<T: 'static>(
+        &self,
+        name: &str,
+        init: impl Fn(&mut S) -> T + 'static,
+        body: impl FnOnce(&StateContext<T>),
+    ) {
+        let slot = Rc::clone(&self.slot);
+        Context.describe(name, |_| {
+            StateContext::enter(move || Self::with(&slot, &init), body);
+        });
+    }
+
+    /// Define a test that receives the state. See [`Context::it`].
+    pub fn it(&self, name: &str, body: impl Fn(&mut S) + 'static) -> ItBuilder {
+        Context.it(name, self.bind(body))
+    }
+
+    /// Focused variant of [`it`](Self::it).
+    pub fn fit(&self, name: &str, body: impl Fn(&mut S) + 'static) -> ItBuilder {
+        Context.fit(name, self.bind(body))
+    }
+
+    /// Pending variant of [`it`](Self::it).
+    pub fn xit(&self, name: &str, body: impl Fn(&mut S) + 'static) -> ItBuilder {
+        Context.xit(name, self.bind(body))
+    }
+
+    /// Like [`Context::before_each`], after the state was built.
+    pub fn before_each(&self, hook: impl Fn(&mut S) + 'static) {
+        Context.before_each(self.bind(hook));
+    }
+
+    /// Like [`Context::just_before_each`].
+    pub fn just_before_each(&self, hook: impl Fn(&mut S) + 'static) {
+        Context.just_before_each(self.bind(hook));
+    }
+
+    /// Like [`Context::after_each`], before the state is dropped.
+    pub fn after_each(&self, hook: impl Fn(&mut S) + 'static) {
+        Context.after_each(self.bind(hook));
+    }
+
+    fn bind(&self, f: impl Fn(&mut S) + 'static) -> impl Fn() + 'static {
+        let slot = Rc::clone(&self.slot);
+        move || Self::with(&slot, &f)
+    }
+}
@@ -0,0 +1,169 @@
+//! ANSI styling — a small composable `Style` used by all terminal output.
+//!
+//! Styles combine into a single escape sequence, so `bold + dim` emits
+//! `\x1b[1;2m...\x1b[0m` instead of nesting two sequences whose inner reset
+//! would clear the outer style.
+
+/// Foreground colors used by the runner.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Color {
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Color {
+    fn code(self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+        }
+    }
+}
+
+/// A combinable set of ANSI attributes.
+///
+/// ```text
+/// Style::new().red().bold().paint("FAIL")  // "\x1b[1;31mFAIL\x1b[0m"
+/// ```
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub(crate) struct Style {
+    fg: Option<Color>,
+    bold: bool,
+    dim: bool,
+}
+
+impl Style {
+    /// A style with no attributes. Painting with it returns the input unchanged.
+    pub(crate) const fn new() -> Self {
+        Style {
+            fg: None,
+            bold: false,
+            dim: false,
+        }
+    }
+
+    pub(crate) const fn red(self) -> Self {
+        Style {
+            fg: Some(Color::Red),
+            ..self
+        }
+    }
+
+    pub(crate) const fn green(self) -> Self {
+        Style {
+            fg: Some(Color::Green),
+            ..self
+        }
+    }
+
+    pub(crate) const fn yellow(self) -> Self {
+        Style {
+            fg: Some(Color::Yellow),
+            ..self
+        }
+    }
+
+    pub(crate) const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    pub(crate) const fn dim(self) -> Self {
+        Style { dim: true, ..self }
+    }
+
+    fn is_plain(&self) -> bool {
+        self.fg.is_none() && !self.bold && !self.dim
+    }
+
+    /// Apply the style if color output is enabled, otherwise return `s` as-is.
+    pub(crate) fn paint(&self, s: &str) -> String {
+        if use_color() {
+            self.wrap(s)
+        } else {
+            s.to_string()
+        }
+    }
+
+    /// Wrap `s` in one opening sequence and one reset, regardless of whether
+    /// color output is enabled.
+    pub(crate) fn wrap(&self, s: &str) -> String {
+        if self.is_plain() {
+            return s.to_string();
+        }
+        let mut codes: Vec<String> = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.dim {
+            codes.push("2".to_string());
+        }
+        if let Some(fg) = self.fg {
+            codes.push(fg.code().to_string());
+        }
+        format!("\x1b[{}m{s}\x1b[0m", codes.join(";"))
+    }
+}
+
+/// Whether ANSI output should be emitted: stdout is a terminal (or this is a
+/// `--jobs` worker whose parent's is) and `NO_COLOR` is not set.
+pub(crate) fn use_color() -> bool {
+    if std::env::var("NO_COLOR").is_ok() {
+        return false;
+    }
+    if std::env::var_os(crate::parallel::COLOR_ENV).is_some() {
+        return true;
+    }
+    std::io::IsTerminal::is_terminal(&std::io::stdout())
+}
+
+pub(crate) fn green(s: &str) -> String {
+    Style::new().green().paint(s)
+}
+
+pub(crate) fn red(s: &str) -> String {
+    Style::new().red().paint(s)
+}
+
+pub(crate) fn yellow(s: &str) -> String {
+    Style::new().yellow().paint(s)
+}
+
+pub(crate) fn bold(s: &str) -> String {
+    Style::new().bold().paint(s)
+}
+
+pub(crate) fn dim(s: &str) -> String {
+    Style::new().dim().paint(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_style_matches_legacy_sequences() {
+        assert_eq!(Style::new().green().wrap("ok"), "\x1b[32mok\x1b[0m");
+        assert_eq!(Style::new().red().wrap("x"), "\x1b[31mx\x1b[0m");
+        assert_eq!(Style::new().yellow().wrap("-"), "\x1b[33m-\x1b[0m");
+        assert_eq!(Style::new().bold().wrap("b"), "\x1b[1mb\x1b[0m");
+        assert_eq!(Style::new().dim().wrap("d"), "\x1b[2md\x1b[0m");
+    }
+
+    #[test]
+    fn combined_styles_emit_one_sequence() {
+        assert_eq!(Style::new().bold().dim().wrap("n"), "\x1b[1;2mn\x1b[0m");
+        assert_eq!(Style::new().red().bold().wrap("n"), "\x1b[1;31mn\x1b[0m");
+    }
+
+    #[test]
+    fn later_color_overrides_earlier() {
+        assert_eq!(Style::new().red().green(), Style::new().green());
+    }
+
+    #[test]
+    fn plain_style_is_identity() {
+        assert_eq!(Style::new().wrap("plain"), "plain");
+    }
+}
@@ -1,7 +1,9 @@
 //! Table-driven tests — parameterized test cases via a builder.
 
 use crate::context::with_builder;
+use crate::csv::{CsvRow, CsvSource};
 use crate::runner::TestNode;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 /// Builder for table-driven (parameterized) tests.
@@ -35,17 +37,132 @@ impl TableBuilder {
     pub fn case<T: 'static>(self, label: &str, data: T) -> TypedTableBuilder<T> {
         TypedTableBuilder {
             name: self.name,
-            cases: vec![(label.to_string(), data)],
+            cases: vec![Row::new(label.to_string(), data)],
             auto_index: 0,
+            retries: None,
+            timeout_ms: None,
         }
     }
 
+    /// Add named cases from an iterator, fixing the data type for all
+    /// subsequent cases. Useful when rows are loaded or computed:
+    /// `.cases(load_fixtures())`.
+    pub fn cases<T: 'static>(
+        self,
+        cases: impl IntoIterator<Item = (String, T)>,
+    ) -> TypedTableBuilder<T> {
+        TypedTableBuilder {
+            name: self.name,
+            cases: Vec::new(),
+            auto_index: 0,
+            retries: None,
+            timeout_ms: None,
+        }
+        .cases(cases)
+    }
+
+    /// Add unnamed cases from an iterator (auto-named `case_1`, `case_2`, ...).
+    pub fn cases_unnamed<T: 'static>(
+        self,
+        cases: impl IntoIterator<Item = T>,
+    ) -> TypedTableBuilder<T> {
+        TypedTableBuilder::unnamed(self.name, cases)
+    }
+
+    /// Add one case per data row of an embedded CSV file, named by its first
+    /// column, with the other columns parsed into `T` via `FromStr`.
+    ///
+    /// The first line is a header and is skipped. Panics naming the file
+    /// and line if a row has the wrong number of columns or a cell doesn't
+    /// parse.
+    ///
+    /// ```rust,ignore
+    /// ctx.describe_table("pricing")
+    ///     .csv(rsspec::include_csv!("tests/data/pricing.csv"))
+    ///     .run(|(quantity, total): &(u32, f64)| assert_eq!(price(*quantity), *total));
+    /// ```
+    pub fn csv<T: CsvRow + 'static>(self, source: CsvSource) -> TypedTableBuilder<T> {
+        self.cases(source.rows())
+    }
+
+    /// Add one case per combination of two named dimensions, fixing the data
+    /// type to the pair `(A, B)`.
+    ///
+    /// Cases run in row-major order (every `b` for the first `a`, then the
+    /// next `a`) and are named `<a>_x_<b>`. Labels are lowercased, with each
+    /// run of characters other than letters, digits and `_` replaced by a
+    /// single `_`; names that collide after that get `_2`, `_3`, ... as in
+    /// [`run`](TypedTableBuilder::run).
+    ///
+    /// ```rust,no_run
+    /// # fn main() { rsspec::run(|ctx| {
+    /// ctx.describe_table("layout")
+    ///     .product(
+    ///         &[("Chrome", "chrome"), ("Firefox", "firefox")],
+    ///         &[("mobile", 375u32), ("desktop", 1440)],
+    ///     )
+    ///     // chrome_x_mobile, chrome_x_desktop, firefox_x_mobile, ...
+    ///     .run_each(|browser: &&str, width: &u32| {
+    ///         assert!(!browser.is_empty() && *width > 0);
+    ///     });
+    /// # }); }
+    /// ```
+    ///
+    /// Panics if either dimension is empty.
+    pub fn product<A: Clone + 'static, B: Clone + 'static>(
+        self,
+        a: &[(&str, A)],
+        b: &[(&str, B)],
+    ) -> TypedTableBuilder<(A, B)> {
+        assert!(
+            !a.is_empty() && !b.is_empty(),
+            "rsspec: product for table '{}' needs at least one value per dimension",
+            self.name
+        );
+        let cases = a
+            .iter()
+            .flat_map(|(a_label, a)| {
+                b.iter().map(move |(b_label, b)| {
+                    let label = format!("{}_x_{}", sanitize(a_label), sanitize(b_label));
+                    Row::new(label, (a.clone(), b.clone()))
+                })
+            })
+            .collect();
+        TypedTableBuilder {
+            name: self.name,
+            cases,
+            auto_index: 0,
+            retries: None,
+            timeout_ms: None,
+        }
+    }
+
+    /// Like [`case`](Self::case), with labels for this row only, so it can
+    /// be selected or excluded by `RSSPEC_LABEL_FILTER`.
+    pub fn case_labeled<T: 'static>(
+        self,
+        label: &str,
+        data: T,
+        labels: &[&str],
+    ) -> TypedTableBuilder<T> {
+        TypedTableBuilder {
+            name: self.name,
+            cases: Vec::new(),
+            auto_index: 0,
+            retries: None,
+            timeout_ms: None,
+        }
+        .case_labeled(label, data, labels)
+    }
+
     /// Add the first unnamed test case (auto-named `case_1`).
     pub fn case_unnamed<T: 'static>(self, data: T) -> TypedTableBuilder<T> {
         TypedTableBuilder {
             name: self.name,
-            cases: vec![("case_1".to_string(), data)],
+            cases: vec![Row::new("case_1".to_string(), data)],
             auto_index: 1,
+            retries: None,
+            timeout_ms: None,
         }
     }
 }
@@ -55,16 +172,70 @@ impl TableBuilder {
 /// Created by [`TableBuilder::case`] or [`TableBuilder::case_unnamed`].
 /// Add more cases with [`.case()`](Self::case), then call
 /// [`.run()`](Self::run) to register the tests.
+///
+/// [`.retries()`](Self::retries) and [`.timeout()`](Self::timeout) apply to
+/// every row; [`.row_retries()`](Self::row_retries) and
+/// [`.row_timeout()`](Self::row_timeout) override them for the most recently
+/// added case.
 pub struct TypedTableBuilder<T> {
     name: String,
-    cases: Vec<(String, T)>,
+    cases: Vec<Row<T>>,
     auto_index: usize,
+    retries: Option<u32>,
+    timeout_ms: Option<u64>,
+}
+
+/// A single table case and its per-row overrides.
+struct Row<T> {
+    label: String,
+    data: T,
+    labels: Vec<String>,
+    retries: Option<u32>,
+    timeout_ms: Option<u64>,
+}
+
+impl<T> Row<T> {
+    fn new(label: String, data: T) -> Self {
+        Row {
+            label,
+            data,
+            labels: Vec::new(),
+            retries: None,
+            timeout_ms: None,
+        }
+    }
 }
 
 impl<T: 'static> TypedTableBuilder<T> {
+    /// A table of auto-named cases (`case_1`, `case_2`, ...), used by
+    /// [`Context::it_table`](crate::Context::it_table).
+    pub(crate) fn unnamed(name: String, cases: impl IntoIterator<Item = T>) -> Self {
+        let cases: Vec<Row<T>> = cases
+            .into_iter()
+            .enumerate()
+            .map(|(i, data)| Row::new(format!("case_{}", i + 1), data))
+            .collect();
+        TypedTableBuilder {
+            name,
+            auto_index: cases.len(),
+            cases,
+            retries: None,
+            timeout_ms: None,
+        }
+    }
+
     /// Add a named test case with parameter data.
     pub fn case(mut self, label: &str, data: T) -> Self {
-        self.cases.push((label.to_string(), data));
+        self.cases.push(Row::new(label.to_string(), data));
+        self
+    }
+
+    /// Add a named test case with labels for this row only, e.g. to mark
+    /// a huge input `slow`.
+    pub fn case_labeled(mut self, label: &str, data: T, labels: &[&str]) -> Self {
+        let mut row = Row::new(label.to_string(), data);
+        row.labels = labels.iter().map(|s| s.to_string()).collect();
+        self.cases.push(row);
         self
     }
 
@@ -72,19 +243,77 @@ impl<T: 'static> TypedTableBuilder<T> {
     pub fn case_unnamed(mut self, data: T) -> Self {
         self.auto_index += 1;
         let label = format!("case_{}", self.auto_index);
-        self.cases.push((label, data));
+        self.cases.push(Row::new(label, data));
+        self
+    }
+
+    /// Add named cases from an iterator, after the existing ones.
+    pub fn cases(mut self, cases: impl IntoIterator<Item = (String, T)>) -> Self {
+        self.cases.extend(cases.into_iter().map(|(label, data)| Row::new(label, data)));
         self
     }
 
+    /// Add unnamed cases from an iterator, continuing the `case_N` numbering.
+    pub fn cases_unnamed(mut self, cases: impl IntoIterator<Item = T>) -> Self {
+        for data in cases {
+            self = self.case_unnamed(data);
+        }
+        self
+    }
+
+    /// Retry every row up to `n` additional times on failure, like
+    /// [`ItBuilder::retries`](crate::ItBuilder::retries).
+    pub fn retries(mut self, n: u32) -> Self {
+        self.retries = Some(n);
+        self
+    }
+
+    /// Fail any row that exceeds `ms` milliseconds, like
+    /// [`ItBuilder::timeout`](crate::ItBuilder::timeout). Rows run on the
+    /// runner's thread, so the timeout is checked once a row returns.
+    pub fn timeout(mut self, ms: u64) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+
+    /// Override the table's retries for the most recently added case.
+    pub fn row_retries(mut self, n: u32) -> Self {
+        self.last_row().retries = Some(n);
+        self
+    }
+
+    /// Override the table's timeout for the most recently added case.
+    pub fn row_timeout(mut self, ms: u64) -> Self {
+        self.last_row().timeout_ms = Some(ms);
+        self
+    }
+
+    fn last_row(&mut self) -> &mut Row<T> {
+        self.cases
+            .last_mut()
+            .expect("rsspec: row_retries/row_timeout need a case to apply to")
+    }
+
     /// Run all cases. Each case becomes a separate test node.
     ///
     /// The test function receives a reference to the data for each case.
+    /// Repeated labels are made unique by appending `_2`, `_3`, ... in case
+    /// order, so every row keeps a distinct path.
+    #[track_caller]
     pub fn run(self, test_fn: impl Fn(&T) + 'static) {
         with_builder(|b| b.push_group(self.name, false, false));
 
         let test_fn = Arc::new(test_fn);
+        let labels = unique_labels(self.cases.iter().map(|row| row.label.as_str()));
 
-        for (label, data) in self.cases {
+        for (row, label) in self.cases.into_iter().zip(labels) {
+            let Row {
+                data,
+                labels: row_labels,
+                retries,
+                timeout_ms,
+                ..
+            } = row;
             let test_fn = test_fn.clone();
 
             // Data is owned by the closure and passed by reference to test_fn.
@@ -93,18 +322,19 @@ impl<T: 'static> TypedTableBuilder<T> {
                 test_fn(&data);
             };
 
-            with_builder(|b| {
-                b.add_node(TestNode::It {
-                    name: label,
-                    focused: false,
-                    pending: false,
-                    labels: Vec::new(),
-                    retries: None,
-                    timeout_ms: None,
-                    must_pass_repeatedly: None,
-                    test_fn: Box::new(body),
-                });
-            });
+            let mut node = TestNode::it(label, body);
+            if let TestNode::It {
+                labels,
+                retries: node_retries,
+                timeout_ms: node_timeout_ms,
+                ..
+            } = &mut node
+            {
+                *labels = row_labels;
+                *node_retries = retries.or(self.retries);
+                *node_timeout_ms = timeout_ms.or(self.timeout_ms);
+            }
+            with_builder(|b| b.add_node(node));
         }
 
         with_builder(|b| b.pop_group());
@@ -140,3 +370,178 @@ impl<T: 'static> TypedTableBuilder<T> {
         });
     }
 }
+
+impl<A: 'static, B: 'static> TypedTableBuilder<(A, B)> {
+    /// Like [`run`](Self::run), with the pair of a
+    /// [`product`](TableBuilder::product) table unpacked into two arguments.
+    #[track_caller]
+    pub fn run_each(self, test_fn: impl Fn(&A, &B) + 'static) {
+        self.run(move |(a, b): &(A, B)| test_fn(a, b));
+    }
+}
+
+/// A product dimension label as a name segment: lowercased, with every run
+/// of characters other than letters, digits and `_` collapsed to one `_`.
+fn sanitize(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    let mut gap = false;
+    for c in label.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            if gap && !out.is_empty() {
+                out.push('_');
+            }
+            gap = false;
+            out.extend(c.to_lowercase());
+        } else {
+            gap = true;
+        }
+    }
+    out
+}
+
+/// `labels` with repeats suffixed `_2`, `_3`, ... (skipping any suffix
+/// already taken by another label), so table rows never share a path.
+fn unique_labels<'a>(labels: impl Iterator<Item = &'a str> + Clone) -> Vec<String> {
+    let mut taken: HashSet<String> = labels.clone().map(str::to_string).collect();
+    let mut seen = HashSet::new();
+    labels
+        .map(|label| {
+            if seen.insert(label) {
+                return label.to_string();
+            }
+            let unique = (2..)
+                .map(|n| format!("{label}_{n}"))
+                .find(|candidate| !taken.contains(candidate))
+                .expect("rsspec: ran out of suffixes");
+            taken.insert(unique.clone());
+            unique
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_labels_get_numbered_suffixes() {
+        let labels = ["zero", "one", "zero", "zero_2", "zero", "one"];
+        assert_eq!(
+            unique_labels(labels.into_iter()),
+            vec!["zero", "one", "zero_3", "zero_2", "zero_4", "one_2"]
+        );
+    }
+
+    #[test]
+    fn duplicate_rows_run_under_distinct_paths() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            ctx.describe_table("parity")
+                .case("even", 2i32)
+                .case("even", 4)
+                .case("odd", 3)
+                .run(|n: &i32| assert!(*n > 0));
+        });
+        let paths: Vec<_> = tree.tests().iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(paths, vec!["parity > even", "parity > even_2", "parity > odd"]);
+    }
+
+    #[test]
+    fn sanitize_collapses_separators() {
+        assert_eq!(sanitize("Chrome"), "chrome");
+        assert_eq!(sanitize("  iPad Pro (12.9\")"), "ipad_pro_12_9");
+        assert_eq!(sanitize("snake_case"), "snake_case");
+    }
+
+    #[test]
+    fn product_runs_every_combination_under_composed_names() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            ctx.describe_table("layout")
+                .product(
+                    &[("Chrome", 'c'), ("Firefox", 'f')],
+                    &[("mobile", 375u32), ("Mobile!", 414), ("desktop", 1440)],
+                )
+                .run_each(|browser: &char, width: &u32| {
+                    assert!(*browser != 'f' || *width != 1440, "firefox at {width}");
+                });
+        });
+        let results: Vec<_> = tree
+            .tests()
+            .iter()
+            .map(|t| (t.name.as_str(), t.status))
+            .collect();
+        let passed = crate::TestStatus::Passed;
+        assert_eq!(
+            results,
+            vec![
+                ("chrome_x_mobile", passed),
+                ("chrome_x_mobile_2", passed),
+                ("chrome_x_desktop", passed),
+                ("firefox_x_mobile", passed),
+                ("firefox_x_mobile_2", passed),
+                ("firefox_x_desktop", crate::TestStatus::Failed),
+            ]
+        );
+    }
+
+    #[test]
+    fn cases_from_iterators_keep_the_auto_numbering() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            let named = (1..=2).map(|n| (format!("row {n}"), n));
+            ctx.describe_table("loaded")
+                .cases(named)
+                .case_unnamed(3)
+                .cases_unnamed(vec![4, 5])
+                .run(|n: &i32| assert!(*n > 0));
+            ctx.describe_table("computed").cases_unnamed(0..2u8).run(|_| {});
+        });
+        let paths: Vec<_> = tree.tests().iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "loaded > row 1",
+                "loaded > row 2",
+                "loaded > case_1",
+                "loaded > case_2",
+                "loaded > case_3",
+                "computed > case_1",
+                "computed > case_2",
+            ]
+        );
+    }
+
+    #[test]
+    fn row_labels_reach_only_their_row() {
+        let labels = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen = std::rc::Rc::clone(&labels);
+        crate::context::run_tree_with(crate::RunConfig::default(), move |ctx| {
+            ctx.describe_table("sizes")
+                .case_labeled("huge input", 100_000u32, &["slow"])
+                .case("small input", 10)
+                .run(move |_| {
+                    let test = crate::current_test().expect("running a test");
+                    seen.borrow_mut().push(test.labels);
+                });
+        });
+        assert_eq!(*labels.borrow(), vec![vec!["slow".to_string()], vec![]]);
+    }
+
+    #[test]
+    fn it_table_names_cases_by_index() {
+        let (_, tree) = crate::context::run_tree_with(crate::RunConfig::default(), |ctx| {
+            ctx.it_table("squares", &[2i32, -3, 4], |n: &i32| assert!(*n > 0, "{n} is negative"));
+        });
+        let results: Vec<_> = tree
+            .tests()
+            .iter()
+            .map(|t| (t.path.as_str(), t.status))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("squares > case_1", crate::TestStatus::Passed),
+                ("squares > case_2", crate::TestStatus::Failed),
+                ("squares > case_3", crate::TestStatus::Passed),
+            ]
+        );
+    }
+}
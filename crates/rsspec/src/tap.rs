@@ -0,0 +1,213 @@
+//! TAP version 13 output for `--output-format tap`.
+//!
+//! The whole document is written when the run finishes, so the plan line
+//! can give the exact number of test points: every test reached except the
+//! ones focus mode skipped silently, plus one point per failed
+//! `before_all`/`after_all` hook so a broken setup still fails the stream.
+//! An ordered block lists its steps in the YAML block under its point.
+
+use std::fmt::Write as _;
+
+use crate::reporter::{Reporter, RunSummary};
+use crate::result_tree::StepResult;
+use crate::runner::TestStatus;
+
+/// The [`Reporter`] behind `--output-format tap`.
+pub(crate) struct TapReporter;
+
+impl Reporter for TapReporter {
+    fn run_finished(&mut self, summary: &RunSummary) {
+        print!("{}", render(summary));
+    }
+}
+
+fn render(summary: &RunSummary) -> String {
+    // (ok, description with any directive, diagnostic block)
+    let mut points: Vec<(bool, String, Option<String>)> = Vec::new();
+    for test in &summary.tests {
+        let message = test.message.as_deref().unwrap_or_default();
+        let path = description(&test.path);
+        let steps = &test.ordered_steps;
+        points.push(match test.status {
+            TestStatus::Passed if steps.is_empty() => (true, path, None),
+            TestStatus::Passed => (true, path, Some(diagnostic(None, steps))),
+            TestStatus::Failed => (false, path, Some(diagnostic(Some(message), steps))),
+            TestStatus::Flaky => (false, format!("{path} # TODO flaky"), None),
+            TestStatus::XFailed => (false, format!("{path} # TODO xfail"), None),
+            TestStatus::Pending if message.is_empty() => {
+                (true, format!("{path} # SKIP pending"), None)
+            }
+            TestStatus::Pending => {
+                (true, format!("{path} # SKIP pending: {}", one_line(message)), None)
+            }
+            // Focus mode skips unfocused tests without a reason; they're
+            // not part of the plan.
+            TestStatus::Skipped if message.is_empty() => continue,
+            TestStatus::Skipped => (true, format!("{path} # SKIP {}", one_line(message)), None),
+        });
+    }
+    // Scope hook failures aren't tests, but must still fail the stream.
+    for failure in &summary.failures {
+        if !summary.tests.iter().any(|t| t.path == failure.path) {
+            let block = diagnostic(Some(&failure.message), &[]);
+            points.push((false, description(&failure.path), Some(block)));
+        }
+    }
+
+    let mut out = format!("TAP version 13\n1..{}\n", points.len());
+    for (i, (ok, text, block)) in points.iter().enumerate() {
+        let status = if *ok { "ok" } else { "not ok" };
+        let _ = writeln!(out, "{status} {} - {text}", i + 1);
+        if let Some(block) = block {
+            out.push_str(block);
+        }
+    }
+    out
+}
+
+/// A test point description: `#` would start a directive, so escape it.
+fn description(path: &str) -> String {
+    one_line(path).replace('#', "\\#")
+}
+
+fn one_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+/// The YAML diagnostic block under a test point: the failure `message`, if
+/// it failed, and the outcome of each of an ordered block's `steps`.
+fn diagnostic(message: Option<&str>, steps: &[StepResult]) -> String {
+    let mut block = String::from("  ---\n");
+    if let Some(message) = message {
+        block.push_str("  message: |\n");
+        for line in message.lines() {
+            let _ = writeln!(block, "    {line}");
+        }
+    }
+    if !steps.is_empty() {
+        block.push_str("  steps:\n");
+        for step in steps {
+            let status = match step.status {
+                TestStatus::Passed => "pass",
+                TestStatus::Failed => "fail",
+                TestStatus::Pending => "pending",
+                _ => "skip",
+            };
+            let name = crate::json::string(&step.name);
+            let _ = writeln!(block, "    - name: {name}\n      status: {status}");
+            if let Some(message) = &step.message {
+                let _ = writeln!(block, "      message: {}", crate::json::string(message));
+            }
+        }
+    }
+    if message.is_some() {
+        block.push_str("  severity: fail\n");
+    }
+    block.push_str("  ...\n");
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result_tree::TestResult;
+    use crate::runner::Failure;
+    use std::time::Duration;
+
+    fn test(path: &str, status: TestStatus, message: Option<&str>) -> TestResult {
+        TestResult {
+            name: path.rsplit(" > ").next().unwrap_or_default().to_string(),
+            path: path.to_string(),
+            status,
+            duration: Duration::ZERO,
+            attempts: 1,
+            message: message.map(str::to_string),
+            owner: None,
+            attachments: Vec::new(),
+            output: None,
+            steps: 0,
+            assertions: 0,
+            ordered_steps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_plan_points_and_diagnostics() {
+        let summary = RunSummary {
+            tests: vec![
+                test("math > adds", TestStatus::Passed, None),
+                test("math > issue #4", TestStatus::Failed, Some("left: 1\nright: 2")),
+                test("math > later", TestStatus::Pending, None),
+                test("math > unfocused", TestStatus::Skipped, None),
+                test("db > reads", TestStatus::Skipped, Some("no database")),
+                test("db > retries", TestStatus::Flaky, Some("timeout")),
+            ],
+            failures: vec![
+                Failure::new("math > issue #4", "left: 1\nright: 2"),
+                Failure::new("db (after_all)", "teardown"),
+            ],
+            ..RunSummary::default()
+        };
+
+        assert_eq!(
+            render(&summary),
+            "TAP version 13
+1..6
+ok 1 - math > adds
+not ok 2 - math > issue \\#4
+  ---
+  message: |
+    left: 1
+    right: 2
+  severity: fail
+  ...
+ok 3 - math > later # SKIP pending
+ok 4 - db > reads # SKIP no database
+not ok 5 - db > retries # TODO flaky
+not ok 6 - db (after_all)
+  ---
+  message: |
+    teardown
+  severity: fail
+  ...
+"
+        );
+    }
+
+    #[test]
+    fn ordered_blocks_list_their_steps() {
+        let mut signup = test("signup", TestStatus::Passed, None);
+        signup.ordered_steps = vec![
+            StepResult {
+                name: "create \"ann\"".to_string(),
+                status: TestStatus::Passed,
+                message: None,
+            },
+            StepResult {
+                name: "verify".to_string(),
+                status: TestStatus::Skipped,
+                message: Some("not focused".to_string()),
+            },
+        ];
+        let summary = RunSummary {
+            tests: vec![signup],
+            ..RunSummary::default()
+        };
+
+        assert_eq!(
+            render(&summary),
+            "TAP version 13
+1..1
+ok 1 - signup
+  ---
+  steps:
+    - name: \"create \\\"ann\\\"\"
+      status: pass
+    - name: \"verify\"
+      status: skip
+      message: \"not focused\"
+  ...
+"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants, clippy::eq_op)]
+
 use std::sync::atomic::{AtomicU32, Ordering};
 
 fn main() {
@@ -1,7 +1,25 @@
+#![allow(clippy::assertions_on_constants)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 fn main() {
     rsspec::run(|ctx| {
+        // =================================================================
+        // Hooks outside any describe apply to the whole suite
+        // =================================================================
+        static SUITE_SETUPS: AtomicU32 = AtomicU32::new(0);
+        ctx.before_all(|| {
+            SUITE_SETUPS.fetch_add(1, Ordering::SeqCst);
+        });
+        ctx.after_all(|| {
+            assert_eq!(SUITE_SETUPS.load(Ordering::SeqCst), 1);
+        });
+        ctx.it("top-level before_all ran once before the suite", || {
+            assert_eq!(SUITE_SETUPS.load(Ordering::SeqCst), 1);
+        });
+
         // =================================================================
         // Basic describe / context / it
         // =================================================================
@@ -77,6 +95,57 @@ fn main() {
                 });
             });
 
+            ctx.describe("before_all_value", |ctx| {
+                static BUILDS: AtomicU32 = AtomicU32::new(0);
+
+                let config = ctx.before_all_value(|| {
+                    BUILDS.fetch_add(1, Ordering::SeqCst);
+                    vec!["alpha", "beta"]
+                });
+
+                let c = config.clone();
+                ctx.it("sees the value built by before_all", move || {
+                    assert_eq!(*c.get(), ["alpha", "beta"]);
+                });
+
+                let c = config.clone();
+                ctx.it("shares it without rebuilding", move || {
+                    assert_eq!(c.get().len(), 2);
+                    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+                });
+
+                ctx.after_all(move || {
+                    let value = config.take().expect("taken once");
+                    assert_eq!(std::rc::Rc::strong_count(&value), 1, "the tests kept no Rc");
+                    assert!(config.take().is_none());
+                });
+            });
+
+            ctx.describe("after_each_result", |ctx| {
+                static OUTCOMES: std::sync::Mutex<Vec<(String, bool)>> =
+                    std::sync::Mutex::new(Vec::new());
+
+                ctx.after_each_result(|outcome| {
+                    let panicked = outcome.body_panicked;
+                    OUTCOMES.lock().unwrap().push((outcome.name.to_string(), panicked));
+                });
+
+                // Checked after the scope so the tests may run in any order.
+                ctx.after_all(|| {
+                    let mut outcomes = OUTCOMES.lock().unwrap().clone();
+                    outcomes.sort();
+                    assert_eq!(
+                        outcomes,
+                        [("fails".to_string(), true), ("passes".to_string(), false)]
+                    );
+                });
+
+                ctx.it("passes", || {});
+
+                ctx.it("fails", || panic!("on purpose"))
+                    .xfail("shows after_each_result a failure");
+            });
+
             ctx.describe("just_before_each", |ctx| {
                 static ORDER: AtomicU32 = AtomicU32::new(0);
 
@@ -116,6 +185,42 @@ fn main() {
                 });
             });
 
+            ctx.describe("before_each_if", |ctx| {
+                static LOGINS: AtomicU32 = AtomicU32::new(0);
+
+                ctx.before_each_if(|info| info.has_label("auth"), || {
+                    LOGINS.fetch_add(1, Ordering::SeqCst);
+                });
+
+                ctx.it("skips the hook for unlabelled tests", || {
+                    assert_eq!(LOGINS.load(Ordering::SeqCst), 0);
+                });
+
+                ctx.it("runs the hook for matching tests", || {
+                    assert_eq!(LOGINS.load(Ordering::SeqCst), 1);
+                    let info = rsspec::current_test().expect("inside a test");
+                    assert_eq!(info.name, "runs the hook for matching tests");
+                })
+                .labels(&["auth"]);
+            });
+
+            ctx.describe("after_each_if", |ctx| {
+                static LOGOUTS: AtomicU32 = AtomicU32::new(0);
+
+                ctx.after_each_if(|info| info.has_label("auth"), || {
+                    LOGOUTS.fetch_add(1, Ordering::SeqCst);
+                });
+
+                ctx.it("runs the hook after matching tests", || {
+                    assert_eq!(LOGOUTS.load(Ordering::SeqCst), 0);
+                })
+                .labels(&["auth"]);
+
+                ctx.it("skips the hook for unlabelled tests", || {
+                    assert_eq!(LOGOUTS.load(Ordering::SeqCst), 1);
+                });
+            });
+
             ctx.describe("after_each guaranteed execution", |ctx| {
                 static AE_RAN: AtomicU32 = AtomicU32::new(0);
 
@@ -165,6 +270,14 @@ fn main() {
             })
             .retries(3);
 
+            static FLAKY_COUNT: AtomicU32 = AtomicU32::new(0);
+
+            ctx.it("flaky", || {
+                let n = FLAKY_COUNT.fetch_add(1, Ordering::SeqCst);
+                assert!(n >= 1, "should fail the first attempt");
+            })
+            .flaky();
+
             ctx.it("must pass repeatedly", || {
                 assert!(true);
             })
@@ -175,6 +288,21 @@ fn main() {
                 assert!(true);
             })
             .timeout(5000);
+
+            ctx.it("within memory limit", || {
+                let buf = vec![1u8; 1 << 20];
+                assert_eq!(buf.len(), 1 << 20);
+            })
+            .max_rss_mb(256);
+
+            if cfg!(target_os = "linux") {
+                ctx.it("over memory limit", || {
+                    let buf = vec![1u8; 2 << 30];
+                    assert_eq!(buf.len(), 2 << 30);
+                })
+                .max_rss_mb(256)
+                .xfail("allocates past its memory limit");
+            }
         });
 
         // =================================================================
@@ -188,6 +316,26 @@ fn main() {
                 .run(|(a, b, expected): &(i32, i32, i32)| {
                     assert_eq!(a + b, *expected);
                 });
+
+            ctx.describe_table("addition from csv")
+                .csv(rsspec::include_csv!("tests/data/addition.csv"))
+                .run(|(a, b, expected): &(i32, i32, i32)| {
+                    assert_eq!(a + b, *expected);
+                });
+
+            static ROW_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+            ctx.describe_table("with retries")
+                .case("stable", 0u32)
+                .case("fails twice", 2u32)
+                .row_retries(2)
+                .retries(1)
+                .timeout(5000)
+                .run(|failures: &u32| {
+                    if *failures > 0 {
+                        let attempt = ROW_ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+                        assert!(attempt >= *failures, "attempt {attempt} should fail");
+                    }
+                });
         });
 
         // =================================================================
@@ -218,6 +366,57 @@ fn main() {
             });
         });
 
+        // =================================================================
+        // Per-test state
+        // =================================================================
+        ctx.describe_with_state("describe_with_state", Vec::new, |cart| {
+            static DROPPED_WITH: AtomicU32 = AtomicU32::new(0);
+
+            cart.before_each(|items: &mut Vec<u32>| items.push(1));
+            cart.after_each(|items| {
+                DROPPED_WITH.store(items.len() as u32, Ordering::SeqCst);
+            });
+
+            cart.it("hands the state to the test", |items| {
+                items.push(2);
+                assert_eq!(items, &[1, 2]);
+            });
+
+            cart.it("builds fresh state for every test", |items| {
+                assert_eq!(DROPPED_WITH.load(Ordering::SeqCst), 2);
+                assert_eq!(items, &[1]);
+            });
+
+            cart.describe_with_state("nested", |items| items.len() * 10, |total| {
+                total.before_each(|total: &mut usize| *total += 1);
+
+                total.it("builds child state from the parent's", |total| {
+                    assert_eq!(*total, 11);
+                });
+            });
+        });
+
+        // =================================================================
+        // describe_with_fixtures
+        // =================================================================
+        ctx.describe_with_fixtures(
+            "describe_with_fixtures",
+            vec![("empty", 0usize), ("three", 3)],
+            |ctx, len| {
+                let expected = *len;
+                let items = Rc::new(RefCell::new(Vec::new()));
+                let fill = Rc::clone(&items);
+                ctx.before_each(move || *fill.borrow_mut() = vec![0; *len]);
+
+                ctx.it("runs every test against each fixture", move || {
+                    assert_eq!(items.borrow().len(), expected);
+                    let path = rsspec::current_test().unwrap().path;
+                    let fixture = if expected == 0 { "(empty)" } else { "(three)" };
+                    assert!(path.contains(fixture), "{path}");
+                });
+            },
+        );
+
         // =================================================================
         // defer_cleanup
         // =================================================================
@@ -255,5 +454,32 @@ fn main() {
                 assert!(true);
             });
         });
+
+        // =================================================================
+        // Named fn bodies
+        // =================================================================
+        ctx.describe("named fn bodies", |ctx| {
+            ctx.it("adds", adds_test);
+        });
+
+        // =================================================================
+        // Helper items local to a describe
+        // =================================================================
+        ctx.describe("local helpers", |ctx| {
+            const INPUT: &str = "a,b,c";
+
+            fn fields(line: &str) -> Vec<&str> {
+                line.split(',').collect()
+            }
+
+            ctx.it("uses a helper fn and const", || {
+                assert_eq!(fields(INPUT), ["a", "b", "c"]);
+            });
+        });
     });
 }
+
+fn adds_test() {
+    let (a, b) = (2, 3);
+    assert_eq!(a + b, 5);
+}
@@ -0,0 +1,69 @@
+//! `--coverage-markers`, checked on the stdout of this binary run with it.
+
+use std::process::Command;
+
+fn main() {
+    if std::env::args().any(|a| a == "--coverage-markers") {
+        return marked_suite();
+    }
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .arg("--coverage-markers")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "the panicking body fails the run");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    rsspec::run(|ctx| {
+        ctx.describe("--coverage-markers", |ctx| {
+            for (path, body) in [
+                ("markers > passes", "body: passes"),
+                ("markers > panics", "body: panics"),
+            ] {
+                let stdout = stdout.clone();
+                ctx.it(&format!("brackets the body of '{path}'"), move || {
+                    assert_eq!(slice(&stdout, path), [body]);
+                });
+            }
+
+            // The slices above include hook lines, so these ran outside them.
+            let stdout = stdout.clone();
+            ctx.it("runs the hooks outside the markers", move || {
+                let hooks = stdout.lines().filter(|l| l.starts_with("hook:")).count();
+                assert_eq!(hooks, 4, "{stdout}");
+            });
+        });
+    });
+}
+
+/// What the suite prints between `path`'s BEGIN and END markers, leaving
+/// out the lines the tree reporter writes.
+fn slice<'a>(stdout: &'a str, path: &str) -> Vec<&'a str> {
+    let begin = format!("##RSSPEC_TEST_BEGIN {path}##");
+    let end = format!("##RSSPEC_TEST_END {path}##");
+    let lines: Vec<&str> = stdout.lines().collect();
+    let start = lines.iter().position(|l| *l == begin).expect("no BEGIN marker");
+    let stop = lines.iter().position(|l| *l == end).expect("no END marker");
+    assert!(start < stop, "END before BEGIN");
+    lines[start + 1..stop]
+        .iter()
+        .copied()
+        .filter(|l| l.starts_with("hook:") || l.starts_with("body:"))
+        .collect()
+}
+
+/// The suite run with `--coverage-markers`: hooks and bodies print a line
+/// each, so the markers can be placed among them.
+fn marked_suite() {
+    rsspec::run(|ctx| {
+        ctx.describe("markers", |ctx| {
+            ctx.before_each(|| println!("hook: before_each"));
+            ctx.after_each(|| println!("hook: after_each"));
+            ctx.it("passes", || println!("body: passes"));
+            ctx.it("panics", || {
+                println!("body: panics");
+                panic!("expected");
+            });
+        });
+    });
+}
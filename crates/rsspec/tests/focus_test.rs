@@ -0,0 +1,36 @@
+//! `allow_focus` under `RSSPEC_FAIL_ON_FOCUS`, set the way CI sets it.
+
+fn main() {
+    std::env::set_var("RSSPEC_FAIL_ON_FOCUS", "1");
+
+    // The exemption covers the focus its describe declares, nested
+    // describes included, and a `fit` that opts out itself.
+    let result = rsspec::run_and_report(|ctx| {
+        ctx.fdescribe("canary", |ctx| {
+            ctx.allow_focus();
+            ctx.it("runs", || {});
+            ctx.describe("nested", |ctx| {
+                ctx.it("runs too", || {});
+            });
+        });
+        ctx.describe("elsewhere", |ctx| {
+            ctx.it("is left out by the focus", || {});
+        });
+        ctx.fit("intentional", || {}).allow_focus();
+    });
+    assert_eq!((result.passed, result.failed), (3, 0));
+
+    // A `fit` nested in the exempt describe is focus of its own.
+    let stray = std::panic::catch_unwind(|| {
+        rsspec::run_and_report(|ctx| {
+            ctx.fdescribe("canary", |ctx| {
+                ctx.allow_focus();
+                ctx.it("runs", || {});
+                ctx.fit("stray", || {});
+            });
+        })
+    });
+    let payload = stray.expect_err("the stray fit passed");
+    let message = payload.downcast_ref::<&str>().copied().unwrap_or_default();
+    assert!(message.contains("RSSPEC_FAIL_ON_FOCUS"), "{message}");
+}
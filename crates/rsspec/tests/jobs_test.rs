@@ -0,0 +1,83 @@
+//! A suite run with two worker processes, as `--jobs 2` runs it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the dependency, in whichever worker runs it.
+static CREATED: AtomicBool = AtomicBool::new(false);
+/// Set by the dependent test, so a skip fails its describe's `after_all`.
+static READ: AtomicBool = AtomicBool::new(false);
+
+fn main() {
+    // Cargo passes no flags to a harness-free target, so add `--jobs 2` by
+    // running this binary again with it. Workers inherit the flag.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.iter().any(|a| a.starts_with("--jobs")) {
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(&args)
+            .args(["--jobs", "2"])
+            .status()
+            .unwrap();
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    // Inherited by the workers, so tests can tell they run in one.
+    if std::env::var_os("JOBS_TEST_PARENT").is_none() {
+        std::env::set_var("JOBS_TEST_PARENT", std::process::id().to_string());
+    }
+
+    rsspec::run(|ctx| {
+        let built_in = ctx.before_all_value(std::process::id);
+
+        for name in ["first worker", "second worker"] {
+            let built_in = built_in.clone();
+            ctx.describe(name, |ctx| {
+                ctx.it("sees the top-level before_all value", move || {
+                    let parent: u32 = std::env::var("JOBS_TEST_PARENT").unwrap().parse().unwrap();
+                    assert_ne!(std::process::id(), parent, "expected to run in a worker");
+                    assert_eq!(*built_in.get(), std::process::id());
+                });
+            });
+        }
+
+        // Declared first, but linked to the describe below by `depends_on`,
+        // so both run in one worker with the dependency first.
+        ctx.describe("reads the record", |ctx| {
+            ctx.after_all(|| assert!(READ.load(Ordering::SeqCst), "the dependent was skipped"));
+            ctx.it("finds it", || {
+                assert!(CREATED.load(Ordering::SeqCst));
+                READ.store(true, Ordering::SeqCst);
+            })
+            .depends_on("creates the record > writes it");
+        });
+        ctx.describe("creates the record", |ctx| {
+            ctx.it("writes it", || CREATED.store(true, Ordering::SeqCst));
+        });
+
+        // Both hold the lock file for a while; sharing a resource keeps them
+        // in turn, though two workers are free. One declares it on a nested
+        // test, which its top-level describe holds for it.
+        ctx.describe("first lock holder", |ctx| {
+            ctx.resource("lock file");
+            ctx.it("holds the lock", hold_lock);
+        });
+        ctx.describe("second lock holder", |ctx| {
+            ctx.describe("nested", |ctx| {
+                ctx.it("holds the lock", hold_lock).resource("lock file");
+            });
+        });
+    });
+}
+
+/// Create the lock file, fail if another worker holds it, and remove it
+/// after a while.
+fn hold_lock() {
+    let parent = std::env::var("JOBS_TEST_PARENT").unwrap();
+    let path = std::env::temp_dir().join(format!("rsspec-jobs-test-{parent}.lock"));
+    std::fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .expect("the lock file is held by another worker");
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    std::fs::remove_file(&path).unwrap();
+}
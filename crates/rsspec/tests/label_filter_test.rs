@@ -0,0 +1,24 @@
+//! `RSSPEC_LABEL_FILTER`, set the way CI sets it, before the suite runs.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTEGRATION_RAN: AtomicBool = AtomicBool::new(false);
+
+fn main() {
+    std::env::set_var("RSSPEC_LABEL_FILTER", "!integration");
+
+    let result = rsspec::run_and_report(|ctx| {
+        ctx.describe("api", |ctx| {
+            ctx.it("parses requests", || {});
+            ctx.it("talks to the database", || INTEGRATION_RAN.store(true, Ordering::SeqCst))
+                .labels(&["integration"]);
+        });
+        ctx.describe("database", |ctx| {
+            ctx.labels(&["integration"]);
+            ctx.it("migrates", || INTEGRATION_RAN.store(true, Ordering::SeqCst));
+        });
+    });
+
+    assert!(!INTEGRATION_RAN.load(Ordering::SeqCst), "an integration test ran");
+    assert_eq!((result.passed, result.failed, result.tests().len()), (1, 0, 1));
+}
@@ -0,0 +1,35 @@
+//! `run_tree_result` through the process config, as a `harness = false`
+//! binary that cargo runs without arguments.
+
+use rsspec::{ResultNode, TestStatus};
+
+fn main() {
+    let tree = rsspec::run_tree_result(|ctx| {
+        ctx.describe("math", |ctx| {
+            ctx.it("adds", || assert_eq!([2, 3].iter().sum::<i32>(), 5));
+            ctx.it("fails", || panic!("boom"));
+            ctx.xit("divides", || {});
+        });
+        ctx.it("top-level", || {});
+    });
+
+    let [ResultNode::Describe { name, children }, ResultNode::Test(top)] = &tree.children[..]
+    else {
+        panic!("unexpected shape: {tree:?}");
+    };
+    assert_eq!((name.as_str(), children.len(), top.path.as_str()), ("math", 3, "top-level"));
+
+    let outcomes: Vec<(&str, TestStatus)> =
+        tree.tests().iter().map(|t| (t.path.as_str(), t.status)).collect();
+    assert_eq!(
+        outcomes,
+        [
+            ("math > adds", TestStatus::Passed),
+            ("math > fails", TestStatus::Failed),
+            ("math > divides", TestStatus::Pending),
+            ("top-level", TestStatus::Passed),
+        ]
+    );
+    let failed = tree.tests()[1];
+    assert!(failed.message.as_deref().is_some_and(|m| m.contains("boom")));
+}